@@ -0,0 +1,221 @@
+//! ASSETS.RS:
+//! Resolves game assets (fonts, the theme, sprites, level files) by name so the
+//! binary works no matter where it is launched from. Assets are looked up, in
+//! order, in an `assets` directory next to the executable, in the directory
+//! named by the `RANGER_ROYALE_ASSETS` environment variable, and finally in the
+//! copy of `src/assets` embedded into the binary at compile time.
+
+use std::borrow::Cow;
+use std::env;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use include_dir::{include_dir, Dir};
+
+/// Environment variable that points at a directory of assets to use instead of the bundled ones.
+pub const ASSETS_ENV_VAR: &str = "RANGER_ROYALE_ASSETS";
+
+/// Name of the assets directory looked for next to the executable.
+pub const ASSETS_DIR_NAME: &str = "assets";
+
+static EMBEDDED: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/src/assets");
+
+#[derive(Debug)]
+pub enum AssetError {
+    /// No location had an asset with this name.
+    NotFound(String),
+    /// The asset exists on disk but couldn't be read.
+    Io(PathBuf, io::Error),
+    /// The asset was found but its contents aren't usable.
+    Invalid(String, String),
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetError::NotFound(name) => write!(f, "asset '{}' not found", name),
+            AssetError::Io(path, e) => write!(f, "couldn't read asset {}: {}", path.display(), e),
+            AssetError::Invalid(name, why) => write!(f, "asset '{}' is invalid: {}", name, why),
+        }
+    }
+}
+
+///
+/// An on-disk directory assets are looked for in, before the embedded copy.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetSource {
+    ExecutableDir(PathBuf),
+    EnvOverride(PathBuf),
+}
+
+impl AssetSource {
+    fn dir(&self) -> &Path {
+        match self {
+            AssetSource::ExecutableDir(dir) | AssetSource::EnvOverride(dir) => dir,
+        }
+    }
+}
+
+pub struct Asset {
+    pub name: String,
+    pub bytes: Cow<'static, [u8]>,
+}
+
+impl Asset {
+    ///
+    /// # Returns
+    /// * The asset's contents as UTF-8 text.
+    ///
+    pub fn into_string(self) -> Result<String, AssetError> {
+        let name = self.name;
+        String::from_utf8(self.bytes.into_owned())
+            .map_err(|e| AssetError::Invalid(name, e.to_string()))
+    }
+
+    ///
+    /// # Returns
+    /// * The asset's contents with a `'static` lifetime. Assets read from disk are leaked,
+    ///   so this is only meant for things loaded once for the whole run, such as fonts.
+    ///
+    pub fn into_static_bytes(self) -> &'static [u8] {
+        match self.bytes {
+            Cow::Borrowed(bytes) => bytes,
+            Cow::Owned(bytes) => Box::leak(bytes.into_boxed_slice()),
+        }
+    }
+}
+
+///
+/// # Returns
+/// * The on-disk directories to search for this run. See `search_dirs_for`.
+///
+fn search_dirs() -> Vec<AssetSource> {
+    search_dirs_for(env::current_exe().ok(), env::var_os(ASSETS_ENV_VAR))
+}
+
+///
+/// # Arguments
+/// * `exe` - Path of the running executable, if known.
+/// * `env_dir` - The value of `ASSETS_ENV_VAR`, if set.
+///
+/// # Returns
+/// * The on-disk directories to search, in priority order.
+///
+fn search_dirs_for(exe: Option<PathBuf>, env_dir: Option<OsString>) -> Vec<AssetSource> {
+    let mut dirs = Vec::new();
+    if let Some(exe_dir) = exe.as_deref().and_then(Path::parent) {
+        dirs.push(AssetSource::ExecutableDir(exe_dir.join(ASSETS_DIR_NAME)));
+    }
+    if let Some(dir) = env_dir {
+        dirs.push(AssetSource::EnvOverride(PathBuf::from(dir)));
+    }
+    dirs
+}
+
+///
+/// Resolves an asset by its path relative to the assets directory,
+/// e.g. `"FiraSans-Medium.ttf"` or `"levels/level1.json"`.
+///
+/// # Returns
+/// * The asset's contents.
+///
+pub fn load(name: &str) -> Result<Asset, AssetError> {
+    load_from(&search_dirs(), name)
+}
+
+///
+/// Like `load`, searching `dirs` before the embedded copy.
+///
+fn load_from(dirs: &[AssetSource], name: &str) -> Result<Asset, AssetError> {
+    for source in dirs {
+        let path = source.dir().join(name);
+        if path.is_file() {
+            let bytes = fs::read(&path).map_err(|e| AssetError::Io(path, e))?;
+            return Ok(Asset { name: name.to_string(), bytes: Cow::Owned(bytes) });
+        }
+    }
+    EMBEDDED.get_file(name)
+        .map(|file| Asset { name: name.to_string(), bytes: Cow::Borrowed(file.contents()) })
+        .ok_or_else(|| AssetError::NotFound(name.to_string()))
+}
+
+///
+/// Resolves a text asset by name.
+///
+pub fn load_string(name: &str) -> Result<String, AssetError> {
+    load(name)?.into_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory in the temp directory, unique to this test run, that is removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = env::temp_dir().join(format!("ranger-royale-{}-{}", std::process::id(), name));
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        fn with_file(self, name: &str, contents: &str) -> Self {
+            fs::write(self.0.join(name), contents).unwrap();
+            self
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn searches_next_to_the_executable_before_the_env_var() {
+        let exe = PathBuf::from("/games/ranger-royale/RangerRoyale");
+        let dirs = search_dirs_for(Some(exe), Some(OsString::from("/custom/assets")));
+        assert_eq!(dirs, vec![
+            AssetSource::ExecutableDir(PathBuf::from("/games/ranger-royale").join(ASSETS_DIR_NAME)),
+            AssetSource::EnvOverride(PathBuf::from("/custom/assets")),
+        ]);
+    }
+
+    #[test]
+    fn searches_only_the_embedded_copy_with_nowhere_else_to_look() {
+        assert!(search_dirs_for(None, None).is_empty());
+    }
+
+    #[test]
+    fn prefers_the_executable_dir_over_the_env_var() {
+        let exe_dir = TempDir::new("exe-first").with_file("theme.toml", "exe");
+        let env_dir = TempDir::new("env-second").with_file("theme.toml", "env");
+        let dirs = [AssetSource::ExecutableDir(exe_dir.0.clone()), AssetSource::EnvOverride(env_dir.0.clone())];
+        assert_eq!(load_from(&dirs, "theme.toml").unwrap().into_string().unwrap(), "exe");
+    }
+
+    #[test]
+    fn prefers_the_env_var_over_the_embedded_copy() {
+        let exe_dir = TempDir::new("exe-empty");
+        let env_dir = TempDir::new("env-only").with_file("theme.toml", "env");
+        let dirs = [AssetSource::ExecutableDir(exe_dir.0.clone()), AssetSource::EnvOverride(env_dir.0.clone())];
+        assert_eq!(load_from(&dirs, "theme.toml").unwrap().into_string().unwrap(), "env");
+    }
+
+    #[test]
+    fn falls_back_to_the_embedded_copy() {
+        let env_dir = TempDir::new("env-empty");
+        let asset = load_from(&[AssetSource::EnvOverride(env_dir.0.clone())], "theme.toml").unwrap();
+        assert!(matches!(asset.bytes, Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn reports_an_asset_found_nowhere() {
+        assert!(matches!(load_from(&[], "no-such-asset.toml"), Err(AssetError::NotFound(_))));
+    }
+}
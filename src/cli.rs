@@ -0,0 +1,176 @@
+//! CLI.RS:
+//! The command line modes that run without a window: `--headless` plays one
+//! battle with the computer on every side, and `simulate` plays many between
+//! chosen class compositions to see how the classes are balanced. `--host` and
+//! `--join` set up a network battle, in the window or, after `--headless`,
+//! with the computer playing this machine's side.
+
+use std::net::SocketAddr;
+use crate::model::game::GameEvent;
+use crate::model::headless;
+use crate::model::inlevel::unit::Class;
+use crate::model::net::{NetSession, DEFAULT_PORT};
+use crate::model::simulate::{Simulation, DEFAULT_BATTLES};
+
+/// Runs battles with the computer on every side and prints how they ended, without a window.
+pub const HEADLESS_FLAG: &str = "--headless";
+
+/// Plays many battles between two class compositions and reports how each class did.
+pub const SIMULATE_COMMAND: &str = "simulate";
+
+/// Hosts a network battle: `--host [port]`.
+pub const HOST_FLAG: &str = "--host";
+
+/// Joins a network battle: `--join <address>`, e.g. `--join 127.0.0.1:7878`.
+pub const JOIN_FLAG: &str = "--join";
+
+const SIMULATE_USAGE: &str = "usage: simulate <side A> <side B> [--levels 1,2,3] [--battles N] \
+[--seed S] [--csv PATH]\n  Sides are comma-separated classes, e.g. Swordsman,Archer";
+
+///
+/// Runs the mode the command line asks for, if it asks for one.
+///
+/// # Returns
+/// * `None` if the game should open its window as usual, otherwise how the mode went.
+///
+pub fn run(args: &[String]) -> Option<Result<(), String>> {
+    match args.first().map(String::as_str) {
+        Some(HEADLESS_FLAG) => Some(run_headless(&args[1..])),
+        Some(SIMULATE_COMMAND) => Some(run_simulate(&args[1..])),
+        _ => None,
+    }
+}
+
+///
+/// # Returns
+/// * The event that starts the game off somewhere other than the title screen, e.g. hosting
+///   a network battle, if the command line asks for one.
+///
+pub fn startup_event(args: &[String]) -> Result<Option<GameEvent>, String> {
+    match args.first().map(String::as_str) {
+        Some(HOST_FLAG) => Ok(Some(GameEvent::HostGame(parse_port(args.get(1))?))),
+        Some(JOIN_FLAG) => Ok(Some(GameEvent::JoinGame(parse_address(args.get(1))?))),
+        _ => Ok(None),
+    }
+}
+
+fn parse_port(arg: Option<&String>) -> Result<u16, String> {
+    match arg {
+        Some(arg) => arg.parse().map_err(|_| format!("Not a port: {}", arg)),
+        None => Ok(DEFAULT_PORT),
+    }
+}
+
+fn parse_address(arg: Option<&String>) -> Result<SocketAddr, String> {
+    let arg = arg.ok_or_else(|| format!("{} needs an address, e.g. 127.0.0.1:{}", JOIN_FLAG, DEFAULT_PORT))?;
+    arg.parse().map_err(|_| format!("Not an address: {}", arg))
+}
+
+///
+/// Plays one battle with the computer in charge of every team and prints the outcome.
+///
+/// # Arguments
+/// * `args` - `[level] [seed]`: the level to play, 1 by default, and the battle's seed,
+///   random by default. Starting with `--host` or `--join` plays a network battle instead;
+///   see `run_network`.
+///
+fn run_headless(args: &[String]) -> Result<(), String> {
+    if matches!(args.first().map(String::as_str), Some(HOST_FLAG) | Some(JOIN_FLAG)) {
+        return run_network(args);
+    }
+    let level = match args.first() {
+        Some(arg) => arg.parse::<i32>().map_err(|_| format!("Not a level number: {}", arg))?,
+        None => 1,
+    };
+    let seed = match args.get(1) {
+        Some(arg) => arg.parse::<u64>().map_err(|_| format!("Not a seed: {}", arg))?,
+        None => rand::random(),
+    };
+    let result = headless::run_battle(level, seed, &[])
+        .map_err(|e| format!("Couldn't load level {}: {}", level, e))?;
+    println!("{}", result.describe());
+    Ok(())
+}
+
+///
+/// Plays a network battle with the computer giving this machine's orders, and prints how it
+/// ended along with the final state hash, which matches on both machines.
+///
+/// # Arguments
+/// * `args` - `--host [port] [level] [seed]` or `--join <address>`.
+///
+fn run_network(args: &[String]) -> Result<(), String> {
+    let (mut session, level, seed) = match args[0].as_str() {
+        HOST_FLAG => {
+            let port = parse_port(args.get(1))?;
+            let level = match args.get(2) {
+                Some(arg) => arg.parse::<i32>().map_err(|_| format!("Not a level number: {}", arg))?,
+                None => 1,
+            };
+            let seed = match args.get(3) {
+                Some(arg) => arg.parse::<u64>().map_err(|_| format!("Not a seed: {}", arg))?,
+                None => rand::random(),
+            };
+            let session = NetSession::host(port).map_err(|e| format!("Couldn't host on port {}: {}", port, e))?;
+            println!("Hosting on {}", session.get_address());
+            (session, level, seed)
+        }
+        _ => (NetSession::join(parse_address(args.get(1))?), 0, 0),
+    };
+    let battle = headless::play_network_battle(&mut session, level, seed).map_err(|e| e.to_string())?;
+    let winner = battle.get_winning_side().map_or("No one".to_string(), |side| side.name.clone());
+    println!("Level {} (seed {}): {} won on turn {}; final state hash {:016x}",
+             battle.get_level(), battle.get_seed(), winner, battle.get_turn(), battle.state_hash());
+    Ok(())
+}
+
+///
+/// Runs a simulation and prints its report, writing it as CSV too if asked.
+///
+/// # Arguments
+/// * `args` - See `SIMULATE_USAGE`.
+///
+fn run_simulate(args: &[String]) -> Result<(), String> {
+    let [side_a, side_b] = [args.first(), args.get(1)]
+        .map(|side| side.ok_or_else(|| SIMULATE_USAGE.to_string()).and_then(|s| parse_side(s)));
+    let mut simulation = Simulation {
+        levels: vec![1],
+        sides: [side_a?, side_b?],
+        battles: DEFAULT_BATTLES,
+        seed: 0,
+    };
+    let mut csv_path = None;
+    let mut options = args[2..].iter();
+    while let Some(option) = options.next() {
+        let value = options.next().ok_or_else(|| format!("{} needs a value\n{}", option, SIMULATE_USAGE))?;
+        let invalid = || format!("Invalid value for {}: {}", option, value);
+        match option.as_str() {
+            "--levels" => simulation.levels = value.split(',')
+                .map(|level| level.trim().parse::<i32>())
+                .collect::<Result<_, _>>()
+                .map_err(|_| invalid())?,
+            "--battles" => simulation.battles = value.parse().map_err(|_| invalid())?,
+            "--seed" => simulation.seed = value.parse().map_err(|_| invalid())?,
+            "--csv" => csv_path = Some(value.clone()),
+            _ => return Err(format!("Unknown option {}\n{}", option, SIMULATE_USAGE)),
+        }
+    }
+
+    let report = simulation.run().map_err(|e| format!("Couldn't simulate: {}", e))?;
+    print!("{}", report.describe());
+    if let Some(path) = csv_path {
+        report.write_csv(&path).map_err(|e| format!("Couldn't write {}: {}", path, e))?;
+        println!("Wrote {}", path);
+    }
+    Ok(())
+}
+
+///
+/// # Returns
+/// * The classes in a comma-separated list, e.g. "Swordsman,Archer".
+///
+fn parse_side(side: &str) -> Result<Vec<Class>, String> {
+    side.split(',')
+        .map(|name| Class::from_name(name.trim()).ok_or_else(|| format!("Unknown class: {}", name)))
+        .collect()
+}
@@ -1,7 +1,9 @@
-use piston_window::{Context, Button, Event, G2d, Glyphs, MouseButton, PressEvent};
+use piston_window::{Button, Event, Key, MouseButton, PressEvent, UpdateEvent};
 use crate::model::game::*;
+use crate::model::replay::ReplayControl;
 
 // Maybe implement?
+#[allow(dead_code)]
 pub enum ControllerEvent {
     StartGame,
     UnitSelected(f64, f64),
@@ -33,6 +35,18 @@ impl Controller {
     }
 
     pub fn handle_event(&mut self, event: &Event) {
+        if let Some(args) = event.update_args() {
+            self.model.update(args.dt);
+        }
+        if self.model.in_transition() {
+            return;
+        }
+
+        if self.model.is_replaying() {
+            self.handle_replay_event(event);
+            return;
+        }
+
         if let Some(Button::Mouse(MouseButton::Left)) = event.press_args() {
             println!("MouseX: {:?}", self.cursor[0]);
             println!("MouseY: {:?}", self.cursor[1]);
@@ -42,16 +56,70 @@ impl Controller {
                 if element.is_selectable() {
                     self.selected = Some(idx);
                 }
-                let game_event = element.click_event();
-                match game_event {
-                    Some(game_event) => { self.model.receive_event(&game_event); },
-                    None => {}
+                let game_event = element.click_event_at(self.cursor[0], self.cursor[1]);
+                if let Some(game_event) = game_event {
+                    self.model.receive_event(&game_event);
                 }
             }
         }
+
+        if let Some(Button::Mouse(MouseButton::Right)) = event.press_args() {
+            self.model.receive_event(&GameEvent::Cancel);
+        }
+
+        if let Some(Button::Keyboard(key)) = event.press_args() {
+            let game_event = match key {
+                Key::Left => Some(GameEvent::MoveCamera(-1, 0)),
+                Key::Right => Some(GameEvent::MoveCamera(1, 0)),
+                Key::Up => Some(GameEvent::MoveCamera(0, -1)),
+                Key::Down => Some(GameEvent::MoveCamera(0, 1)),
+                Key::Return => Some(GameEvent::EndTurn),
+                Key::F5 => Some(GameEvent::SaveBattle),
+                Key::F9 => Some(GameEvent::LoadBattle),
+                Key::P => Some(GameEvent::OpenSaveSlots),
+                Key::T => Some(GameEvent::ToggleThreatMap),
+                Key::F6 => Some(GameEvent::SaveReplay),
+                Key::Backspace => Some(GameEvent::UndoMove),
+                Key::R => Some(GameEvent::RewindTurn),
+                Key::E => Some(GameEvent::CycleWeapon),
+                _ => None,
+            };
+            if let Some(game_event) = game_event {
+                self.model.receive_event(&game_event);
+            }
+        }
     }
     
-    pub fn render_model(&self, con: &Context, graphics: &mut G2d, glyphs: &mut Glyphs) {
-        self.model.render_scene(con, graphics, glyphs);
+    ///
+    /// While a replay plays, the player can only look around and control playback.
+    ///
+    fn handle_replay_event(&mut self, event: &Event) {
+        if let Some(Button::Mouse(MouseButton::Left)) = event.press_args() {
+            let element = self.model.click_event(self.cursor[0], self.cursor[1])
+                .and_then(|idx| self.model.get_ui_element(idx));
+            let game_event = element.and_then(|e| e.click_event_at(self.cursor[0], self.cursor[1]));
+            if let Some(game_event @ GameEvent::CenterCamera(..)) = game_event {
+                self.model.receive_event(&game_event);
+            }
+        }
+
+        if let Some(Button::Keyboard(key)) = event.press_args() {
+            let game_event = match key {
+                Key::Left => Some(GameEvent::MoveCamera(-1, 0)),
+                Key::Right => Some(GameEvent::MoveCamera(1, 0)),
+                Key::Up => Some(GameEvent::MoveCamera(0, -1)),
+                Key::Down => Some(GameEvent::MoveCamera(0, 1)),
+                Key::T => Some(GameEvent::ToggleThreatMap),
+                Key::Space => Some(GameEvent::ReplayControl(ReplayControl::TogglePause)),
+                Key::Period => Some(GameEvent::ReplayControl(ReplayControl::Step)),
+                Key::Equals | Key::NumPadPlus => Some(GameEvent::ReplayControl(ReplayControl::Faster)),
+                Key::Minus | Key::NumPadMinus => Some(GameEvent::ReplayControl(ReplayControl::Slower)),
+                Key::Backspace => Some(GameEvent::ReplayControl(ReplayControl::Stop)),
+                _ => None,
+            };
+            if let Some(game_event) = game_event {
+                self.model.receive_event(&game_event);
+            }
+        }
     }
 }
\ No newline at end of file
@@ -1 +1,2 @@
+#[allow(clippy::module_inception)]
 pub(crate) mod controller;
\ No newline at end of file
@@ -0,0 +1,37 @@
+//! LAYOUT.RS:
+//! The size of a grid cell on screen, and conversions between grid cells and
+//! layout pixels. Kept apart from the view so the model can hit-test clicks
+//! without depending on the graphics backend.
+
+/// The size of one grid cell in pixels.
+///
+/// Used to convert between game logic coordinates (grid-based) and
+/// screen coordinates (pixel-based).
+pub const CELL_SIZE: f64 = 75.0;
+
+/// Converts a grid-based coordinate to a screen (pixel-based) coordinate.
+///
+/// # Arguments
+///
+/// * `game_coord` - The coordinate on the logical grid.
+///
+/// # Returns
+///
+/// * `f64` - The corresponding pixel-based coordinate.
+pub fn to_coord(game_coord: i32) -> f64 {
+    (game_coord as f64) * CELL_SIZE
+}
+
+/// Converts a grid-based coordinate to a screen coordinate as a `u32`.
+///
+/// # Arguments
+///
+/// * `game_coord` - The coordinate on the logical grid.
+///
+/// # Returns
+///
+/// * `u32` - The corresponding pixel-based coordinate rounded down to an integer.
+#[allow(dead_code)]
+pub fn to_coord_u32(game_coord: i32) -> u32 {
+    to_coord(game_coord) as u32
+}
@@ -1,19 +1,21 @@
-extern crate piston_window;
-
-use piston_window::*;
-use piston_window::types::Color;
-
-use view::util::to_coord_u32;
-use crate::view::util::to_coord;
-use crate::view::util::draw_button;
-use crate::controller::controller::Controller;
-use crate::model::game::Game;
+// Headless builds keep the menus and scenes but not the window that drives them.
+#![cfg_attr(not(feature = "graphics"), allow(dead_code))]
 
+mod assets;
+mod cli;
+mod layout;
 mod model;
+mod settings;
+#[cfg(feature = "graphics")]
 mod view;
+#[cfg(feature = "graphics")]
 mod controller;
+#[cfg(feature = "graphics")]
+mod window;
+
+use crate::model::game::GameEvent;
 
-const BG_COLOR: Color = [0.8, 0.8, 0.8, 1.0];
+const THEME_ASSET: &str = "theme.toml";
 const WIDTH: i32 = 15;
 const HEIGHT: i32 = 10;
 
@@ -21,31 +23,29 @@ const HALF_X : i32 = (WIDTH - 2) / 2;
 const HALF_Y : i32 = (HEIGHT - 2) / 2;
 
 fn main() {
-    let mut window: PistonWindow = WindowSettings::new(
-        "Ranger Royale",
-        [to_coord_u32(WIDTH), to_coord_u32(HEIGHT)]
-    ).exit_on_esc(true).build().unwrap();
-
-    let mut glyphs = window
-        .load_font("src/assets/FiraSans-Medium.ttf")
-        .expect("Couldn’t load font");
-    
-    let mut controller: Controller = Controller::new(Game::new());
-    
-    while let Some(event) = window.next() {
-        controller.handle_event(&event);
-        
-        if let Some(pos) = event.mouse_cursor_args() {
-            controller.update_cursor(pos)
-        }
-        
-        window.draw_2d(&event, |context, graphics, device| {
-            clear(BG_COLOR, graphics);
-            let model: &Game = controller.get_model();
-            model.render_scene(&context, graphics, &mut glyphs);
-
-            glyphs.factory.encoder.flush(device);
-        });
-
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match cli::run(&args) {
+        Some(result) => result,
+        None => cli::startup_event(&args).map(run_windowed),
+    };
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        std::process::exit(1);
     }
-}
\ No newline at end of file
+}
+
+///
+/// # Arguments
+/// * `startup` - An event to start the game off with, e.g. to host a network battle.
+///
+#[cfg(feature = "graphics")]
+fn run_windowed(startup: Option<GameEvent>) {
+    window::run(startup);
+}
+
+#[cfg(not(feature = "graphics"))]
+fn run_windowed(_startup: Option<GameEvent>) {
+    eprintln!("Built without the graphics feature; run with {} or {} instead",
+              cli::HEADLESS_FLAG, cli::SIMULATE_COMMAND);
+    std::process::exit(1);
+}
@@ -1,20 +1,30 @@
-use std::cmp::PartialEq;
 use crate::model::game::{GameError, GameEvent, UIElement, UIEvent};
+use crate::HALF_X;
 
 pub struct Button {
     text: String,
     top_left: (f64, f64),
     bottom_right: (f64, f64),
     activation_event: GameEvent,
+    /// Disabled buttons are drawn greyed out and ignore clicks.
+    enabled: bool,
 }
 
 impl Button {
     pub fn new(text: &str, top_left: (f64, f64), bottom_right: (f64, f64), 
            activation_event: GameEvent) -> Self {
         let text: String = text.to_string();
-        Self { text, top_left, bottom_right, activation_event }
+        Self { text, top_left, bottom_right, activation_event, enabled: true }
     }
-    
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
     pub fn get_label(&self) -> &str {
         &self.text
     }
@@ -50,6 +60,27 @@ impl UIElement for Button {
 
     fn click_event(&self) -> Option<GameEvent> {
         println!("Button Clicked");
-        Some(self.activation_event.clone())
+        if !self.enabled {
+            return None;
+        }
+        Some(self.activation_event)
     }
-}
\ No newline at end of file
+}
+
+///
+/// Lays out buttons in a column centered on the screen, one row of space apart, with the
+/// first at row `top`.
+///
+/// # Arguments
+/// * `entries` - The label, event and whether it is enabled, for each button.
+///
+pub fn button_column(top: i32, entries: Vec<(String, GameEvent, bool)>) -> Vec<Button> {
+    entries.into_iter().enumerate()
+        .map(|(i, (label, event, enabled))| {
+            let y = (top + 2 * i as i32) as f64;
+            let mut button = Button::new(&label, (HALF_X as f64, y), ((HALF_X + 2) as f64, y + 1.0), event);
+            button.set_enabled(enabled);
+            button
+        })
+        .collect()
+}
@@ -0,0 +1,439 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use crate::model::inlevel::unit::{Class, Unit};
+use crate::model::save::{self, SaveError};
+
+/// Version written into every campaign file. Bump it whenever the campaign changes shape.
+pub const CAMPAIGN_SAVE_VERSION: u32 = 2;
+
+/// Directory holding one directory per save slot.
+pub const SAVES_DIR: &str = "saves";
+
+/// How many save slots there are, numbered from 1.
+pub const NUM_SLOTS: u32 = 4;
+
+/// The slot a brand new campaign is kept in.
+pub const DEFAULT_SLOT: u32 = 1;
+
+const CAMPAIGN_FILE: &str = "campaign.json";
+
+/// The battle that was in progress when the slot was saved, if any.
+const BATTLE_FILE: &str = "battle.json";
+
+/// Score for clearing a level on the first turn, before survivors are counted.
+const BASE_SCORE: u32 = 1000;
+/// Score lost for every turn the level took after the first.
+const SCORE_PER_TURN: u32 = 50;
+/// Score for every unit that survived the level.
+const SCORE_PER_SURVIVOR: u32 = 100;
+
+///
+/// A cleared level, and the best result achieved on it.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LevelRecord {
+    pub score: u32,
+    /// Fewest turns it has been cleared in.
+    pub turns: u32,
+}
+
+///
+/// The player's progress through the levels, carried from one battle to the next.
+///
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Campaign {
+    /// The player's units, as they stood after the last battle they won.
+    roster: Vec<Unit>,
+    /// Best record of every cleared level, by level number.
+    cleared: BTreeMap<i32, LevelRecord>,
+    /// When the campaign was last saved, in seconds since the Unix epoch.
+    #[serde(default)]
+    saved_at: u64,
+    /// Seconds spent playing the campaign.
+    #[serde(default)]
+    play_time: f64,
+}
+
+///
+/// What a save slot holds, as shown in the save slot menu.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub enum SlotState {
+    Empty,
+    /// The slot has a campaign file that can't be read, and why.
+    Corrupted(String),
+    Saved(SlotInfo),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlotInfo {
+    /// Seconds since the Unix epoch.
+    pub saved_at: u64,
+    pub levels_cleared: usize,
+    pub total_score: u32,
+    /// Seconds.
+    pub play_time: f64,
+    /// Whether a battle in progress was saved along with the campaign.
+    pub has_battle: bool,
+}
+
+///
+/// # Returns
+/// * The directory a save slot's files live in.
+///
+pub fn slot_dir(slot: u32) -> PathBuf {
+    slot_dir_in(Path::new(SAVES_DIR), slot)
+}
+
+fn slot_dir_in(saves: &Path, slot: u32) -> PathBuf {
+    saves.join(format!("slot{}", slot))
+}
+
+///
+/// # Returns
+/// * Where the battle in progress is saved for a slot.
+///
+pub fn battle_path(slot: u32) -> PathBuf {
+    slot_dir(slot).join(BATTLE_FILE)
+}
+
+///
+/// Reads just enough of a slot to describe it.
+///
+pub fn peek_slot(slot: u32) -> SlotState {
+    peek_slot_in(Path::new(SAVES_DIR), slot)
+}
+
+fn peek_slot_in(saves: &Path, slot: u32) -> SlotState {
+    let dir = slot_dir_in(saves, slot);
+    match Campaign::load_from(&dir) {
+        Ok(campaign) => SlotState::Saved(SlotInfo {
+            saved_at: campaign.saved_at,
+            levels_cleared: campaign.cleared.len(),
+            total_score: campaign.total_score(),
+            play_time: campaign.play_time,
+            has_battle: dir.join(BATTLE_FILE).exists(),
+        }),
+        Err(SaveError::Io(e)) if e.kind() == io::ErrorKind::NotFound => SlotState::Empty,
+        Err(e) => SlotState::Corrupted(e.to_string()),
+    }
+}
+
+///
+/// # Returns
+/// * The slot that was saved to most recently, if any slot holds a readable campaign.
+///
+pub fn latest_slot() -> Option<u32> {
+    latest_slot_in(Path::new(SAVES_DIR))
+}
+
+fn latest_slot_in(saves: &Path) -> Option<u32> {
+    (1..=NUM_SLOTS)
+        .filter_map(|slot| match peek_slot_in(saves, slot) {
+            SlotState::Saved(info) => Some((info.saved_at, slot)),
+            _ => None,
+        })
+        .max()
+        .map(|(_, slot)| slot)
+}
+
+///
+/// Removes everything saved in a slot. Deleting an empty slot is not an error.
+///
+pub fn delete_slot(slot: u32) -> Result<(), SaveError> {
+    delete_slot_in(Path::new(SAVES_DIR), slot)
+}
+
+fn delete_slot_in(saves: &Path, slot: u32) -> Result<(), SaveError> {
+    match fs::remove_dir_all(slot_dir_in(saves, slot)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(SaveError::Io(e)),
+        _ => Ok(()),
+    }
+}
+
+///
+/// # Returns
+/// * The score for clearing a level in `turns` turns with `survivors` units left.
+///
+pub fn score(turns: u32, survivors: usize) -> u32 {
+    BASE_SCORE.saturating_sub(SCORE_PER_TURN * turns.saturating_sub(1))
+        + SCORE_PER_SURVIVOR * survivors as u32
+}
+
+impl Campaign {
+    ///
+    /// Starts a campaign with nothing cleared. The roster is filled in by the first level played.
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Reads the campaign saved in `slot`.
+    ///
+    pub fn load(slot: u32) -> Result<Self, SaveError> {
+        Self::load_from(&slot_dir(slot))
+    }
+
+    fn load_from(dir: &Path) -> Result<Self, SaveError> {
+        save::read_versioned(dir.join(CAMPAIGN_FILE), CAMPAIGN_SAVE_VERSION)
+    }
+
+    ///
+    /// Writes the campaign to `slot`, replacing whatever was there, and stamps it with the
+    /// current time.
+    ///
+    pub fn save(&mut self, slot: u32) -> Result<(), SaveError> {
+        self.save_to(&slot_dir(slot))
+    }
+
+    fn save_to(&mut self, dir: &Path) -> Result<(), SaveError> {
+        self.saved_at = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        save::write_versioned(self, CAMPAIGN_SAVE_VERSION, dir.join(CAMPAIGN_FILE))
+    }
+
+    ///
+    /// Counts `seconds` more of play towards the campaign.
+    ///
+    pub fn add_play_time(&mut self, seconds: f64) {
+        self.play_time += seconds;
+    }
+
+    pub fn get_roster(&self) -> &[Unit] {
+        &self.roster
+    }
+
+    ///
+    /// Makes `units` the roster, if there isn't one yet.
+    ///
+    pub fn adopt_roster(&mut self, units: Vec<Unit>) {
+        if self.roster.is_empty() {
+            self.roster = units;
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * Whether any unit in the roster is ready to be promoted.
+    ///
+    pub fn has_promotions(&self) -> bool {
+        self.roster.iter().any(Unit::can_promote)
+    }
+
+    ///
+    /// Promotes the roster unit at `index` into `class`.
+    ///
+    /// # Returns
+    /// * Whether it was promoted. See `Unit::can_promote_to`.
+    ///
+    pub fn promote(&mut self, index: usize, class: Class) -> bool {
+        self.roster.get_mut(index).is_some_and(|unit| unit.promote(class))
+    }
+
+    pub fn is_cleared(&self, level: i32) -> bool {
+        self.cleared.contains_key(&level)
+    }
+
+    pub fn get_record(&self, level: i32) -> Option<&LevelRecord> {
+        self.cleared.get(&level)
+    }
+
+    ///
+    /// # Returns
+    /// * Whether `level` can be played: the first level always can, the rest once the one
+    ///   before has been cleared.
+    ///
+    pub fn is_unlocked(&self, level: i32) -> bool {
+        level <= 1 || self.is_cleared(level - 1)
+    }
+
+    ///
+    /// Records a won battle. Survivors carry their experience and levels into the roster,
+    /// healed up for the next battle. Units that fell keep their state from before it.
+    ///
+    /// # Arguments
+    /// * `level`     - The level that was cleared.
+    /// * `turns`     - How many turns the battle took.
+    /// * `survivors` - The player's units still standing at the end.
+    ///
+    /// # Returns
+    /// * The score of this clear.
+    ///
+    pub fn record_victory(&mut self, level: i32, turns: u32, survivors: &[Unit]) -> u32 {
+        for survivor in survivors {
+            if let Some(unit) = self.roster.iter_mut().find(|u| u.get_name() == survivor.get_name()) {
+                *unit = survivor.clone();
+                unit.restore();
+            }
+        }
+        let score = score(turns, survivors.len());
+        let record = self.cleared.entry(level).or_insert(LevelRecord { score, turns });
+        record.score = record.score.max(score);
+        record.turns = record.turns.min(turns);
+        score
+    }
+
+    ///
+    /// # Returns
+    /// * The total of the best scores of every cleared level.
+    ///
+    pub fn total_score(&self) -> u32 {
+        self.cleared.values().map(|r| r.score).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    /// A directory in the temp directory, unique to this test run, that is removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            Self(env::temp_dir().join(format!("ranger-royale-{}-{}", std::process::id(), name)))
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn roster() -> Vec<Unit> {
+        vec![Unit::new("Ayla", Class::Swordsman, 0), Unit::new("Bren", Class::Archer, 0)]
+    }
+
+    #[test]
+    fn scores_fewer_turns_and_more_survivors_higher() {
+        assert_eq!(score(1, 0), BASE_SCORE);
+        assert_eq!(score(3, 2), BASE_SCORE - 2 * SCORE_PER_TURN + 2 * SCORE_PER_SURVIVOR);
+        assert_eq!(score(1000, 0), 0);
+    }
+
+    #[test]
+    fn unlocks_each_level_once_the_one_before_is_cleared() {
+        let mut campaign = Campaign::new();
+        assert!(campaign.is_unlocked(1));
+        assert!(!campaign.is_unlocked(2));
+        campaign.record_victory(1, 4, &[]);
+        assert!(campaign.is_unlocked(2));
+        assert!(!campaign.is_unlocked(3));
+    }
+
+    #[test]
+    fn keeps_the_best_record_of_each_level() {
+        let mut campaign = Campaign::new();
+        campaign.record_victory(1, 5, &[]);
+        campaign.record_victory(1, 8, &roster());
+        let record = campaign.get_record(1).unwrap();
+        assert_eq!(record.turns, 5);
+        assert_eq!(record.score, score(8, 2));
+        assert_eq!(campaign.total_score(), score(8, 2));
+    }
+
+    #[test]
+    fn carries_survivors_into_the_roster_healed() {
+        let mut campaign = Campaign::new();
+        campaign.adopt_roster(roster());
+        let mut survivor = roster().remove(0);
+        survivor.take_damage(5);
+        survivor.set_done(true);
+        campaign.record_victory(1, 3, &[survivor]);
+        let ayla = &campaign.get_roster()[0];
+        assert_eq!(ayla.get_hp(), ayla.get_max_hp());
+        assert!(!ayla.is_done());
+        assert_eq!(campaign.get_roster()[1], roster()[1]);
+    }
+
+    #[test]
+    fn keeps_the_first_roster_it_is_given() {
+        let mut campaign = Campaign::new();
+        campaign.adopt_roster(roster());
+        campaign.adopt_roster(vec![Unit::new("Cato", Class::Mage, 0)]);
+        assert_eq!(campaign.get_roster(), roster().as_slice());
+    }
+
+    #[test]
+    fn reads_back_what_it_saved() {
+        let dir = TempDir::new("campaign-round-trip");
+        let mut campaign = Campaign::new();
+        campaign.adopt_roster(roster());
+        campaign.record_victory(1, 3, &roster());
+        campaign.add_play_time(90.0);
+        campaign.save_to(&dir.0).unwrap();
+
+        let loaded = Campaign::load_from(&dir.0).unwrap();
+        assert_eq!(loaded.get_roster(), campaign.get_roster());
+        assert_eq!(loaded.get_record(1), campaign.get_record(1));
+        assert_eq!(loaded.play_time, 90.0);
+        assert_eq!(loaded.saved_at, campaign.saved_at);
+        assert!(loaded.saved_at > 0);
+    }
+
+    fn saved_campaign(saves: &Path, slot: u32, levels: i32) -> Campaign {
+        let mut campaign = Campaign::new();
+        for level in 1..=levels {
+            campaign.record_victory(level, 2, &[]);
+        }
+        campaign.add_play_time(30.0);
+        campaign.save_to(&slot_dir_in(saves, slot)).unwrap();
+        campaign
+    }
+
+    #[test]
+    fn describes_an_empty_slot() {
+        let saves = TempDir::new("slots-empty");
+        assert_eq!(peek_slot_in(&saves.0, 1), SlotState::Empty);
+        assert_eq!(latest_slot_in(&saves.0), None);
+    }
+
+    #[test]
+    fn describes_a_saved_slot() {
+        let saves = TempDir::new("slots-saved");
+        let campaign = saved_campaign(&saves.0, 2, 2);
+        fs::write(slot_dir_in(&saves.0, 2).join(BATTLE_FILE), "{}").unwrap();
+        assert_eq!(peek_slot_in(&saves.0, 2), SlotState::Saved(SlotInfo {
+            saved_at: campaign.saved_at,
+            levels_cleared: 2,
+            total_score: campaign.total_score(),
+            play_time: 30.0,
+            has_battle: true,
+        }));
+    }
+
+    #[test]
+    fn reports_a_corrupted_slot() {
+        let saves = TempDir::new("slots-corrupted");
+        let dir = slot_dir_in(&saves.0, 3);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(CAMPAIGN_FILE), "{ not json").unwrap();
+        assert!(matches!(peek_slot_in(&saves.0, 3), SlotState::Corrupted(_)));
+        assert_eq!(latest_slot_in(&saves.0), None);
+    }
+
+    #[test]
+    fn picks_the_most_recently_saved_slot() {
+        let saves = TempDir::new("slots-latest");
+        saved_campaign(&saves.0, 3, 1);
+        let mut newer = Campaign::load_from(&slot_dir_in(&saves.0, 3)).unwrap();
+        newer.saved_at += 10;
+        save::write_versioned(&newer, CAMPAIGN_SAVE_VERSION, slot_dir_in(&saves.0, 1).join(CAMPAIGN_FILE)).unwrap();
+        assert_eq!(latest_slot_in(&saves.0), Some(1));
+    }
+
+    #[test]
+    fn deletes_a_slot_and_shrugs_off_an_empty_one() {
+        let saves = TempDir::new("slots-delete");
+        saved_campaign(&saves.0, 1, 1);
+        delete_slot_in(&saves.0, 1).unwrap();
+        assert_eq!(peek_slot_in(&saves.0, 1), SlotState::Empty);
+        delete_slot_in(&saves.0, 1).unwrap();
+    }
+}
@@ -0,0 +1,65 @@
+use crate::model::button::Button;
+use crate::model::game::{GameEvent, UIElement};
+use crate::{HEIGHT, WIDTH};
+
+/// Width and height of a dialog, in grid cells.
+const DIALOG_SIZE: (i32, i32) = (9, 4);
+
+///
+/// A yes/no question shown over a scene. While it is open, its buttons are the only thing
+/// that can be clicked.
+///
+pub struct Dialog {
+    message: String,
+    top_left: (f64, f64),
+    bottom_right: (f64, f64),
+    confirm_button: Button,
+    cancel_button: Button,
+}
+
+impl Dialog {
+    ///
+    /// Creates a dialog centered on the screen.
+    ///
+    /// # Arguments
+    /// * `message`       - The question being asked.
+    /// * `confirm_label` - What the confirming button says, e.g. "Delete".
+    /// * `on_confirm`    - Sent when the player confirms. Cancelling sends `GameEvent::Cancel`.
+    ///
+    pub fn new(message: &str, confirm_label: &str, on_confirm: GameEvent) -> Self {
+        let x = (WIDTH - DIALOG_SIZE.0) / 2;
+        let y = (HEIGHT - DIALOG_SIZE.1) / 2;
+        let button_y = (y + DIALOG_SIZE.1 - 2) as f64;
+        let confirm_button = Button::new(confirm_label, ((x + 1) as f64, button_y),
+                                         ((x + 4) as f64, button_y + 1.0), on_confirm);
+        let cancel_button = Button::new("Cancel", ((x + DIALOG_SIZE.0 - 4) as f64, button_y),
+                                        ((x + DIALOG_SIZE.0 - 1) as f64, button_y + 1.0), GameEvent::Cancel);
+        Self {
+            message: message.to_string(),
+            top_left: (x as f64, y as f64),
+            bottom_right: ((x + DIALOG_SIZE.0) as f64, (y + DIALOG_SIZE.1) as f64),
+            confirm_button,
+            cancel_button,
+        }
+    }
+
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+
+    ///
+    /// # Returns
+    /// * The dialog's panel, as (top left, bottom right) in grid cells.
+    ///
+    pub fn get_area(&self) -> ((f64, f64), (f64, f64)) {
+        (self.top_left, self.bottom_right)
+    }
+
+    pub fn get_buttons(&self) -> [&Button; 2] {
+        [&self.confirm_button, &self.cancel_button]
+    }
+
+    pub fn get_ui_elements(&self) -> Vec<&dyn UIElement> {
+        vec![&self.confirm_button, &self.cancel_button]
+    }
+}
@@ -1,27 +1,165 @@
-use std::collections::{HashMap, HashSet};
-use piston_window::{Context, G2d, Glyphs};
-use crate::model::button::Button;
+use std::any::Any;
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use crate::layout::to_coord;
+use crate::model::campaign::{self, Campaign, DEFAULT_SLOT};
 use crate::model::game::GameEvent::SelectLevel;
-use crate::{WIDTH, HEIGHT, HALF_X, HALF_Y};
-use crate::model::inlevel::cell::Cell;
-use crate::view::scenedrawer::*;
-use crate::view::util::to_coord;
+use crate::model::inlevel::facing::Facing;
+use crate::model::inlevel::level::{LevelData, LevelError};
+use crate::model::inlevel::skill::Skill;
+use crate::model::inlevel::support::Support;
+use crate::model::inlevel::team;
+use crate::model::inlevel::unit::Class;
+use crate::model::interstitial::InterstitialScene;
+use crate::model::levelselect::LevelSelectScene;
+use crate::model::lobby::{LobbyScene, LobbyState};
+use crate::model::midlevel::{BattleOutcome, MidLevelScene, PLAYER_TEAM};
+use crate::model::net::{self, NetError, NetSession, NetUpdate, Role};
+use crate::model::options::OptionsScene;
+use crate::model::promotion::PromotionScene;
+use crate::model::reconnect::ReconnectScene;
+use crate::model::replay::{Replay, ReplayControl, ReplayPlayer, REPLAY_PATH};
+use crate::model::save::{self, SaveError, QUICKSAVE_PATH};
+use crate::model::saveslots::SaveSlotScene;
+use crate::model::start::StartScene;
+use crate::model::transition::{Transition, TransitionKind, TransitionStyle};
+use crate::settings::{self, Setting};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GameError {
     SceneSelectionError,
     SceneActivationError,
-    InvalidEvent
+    InvalidEvent,
+    /// A unit was told to do something the rules don't allow, e.g. move out of its range.
+    InvalidAction,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GameEvent {
     StartGame,
     SelectLevel(i32),
     EndLevel,
-    MoveUnit(i32, i32, i32, i32)
+    MoveUnit(i32, i32, i32, i32),
+    /// Centers the battle camera on the given map cell.
+    CenterCamera(i32, i32),
+    /// Scrolls the battle camera by the given number of cells.
+    MoveCamera(i32, i32),
+    /// Ends the current side's phase of the turn.
+    EndTurn,
+    /// Saves the battle in progress to the quicksave file.
+    SaveBattle,
+    /// Replaces the current battle with the one in the quicksave file.
+    LoadBattle,
+    /// Selects, or acts on, the map cell at the given position, as if it were clicked.
+    SelectCell(i32, i32),
+    /// The unit at the first position attacks the unit at the second.
+    Attack(i32, i32, i32, i32),
+    /// The unit at the given position ends its action where it stands, turned the given way.
+    Wait(i32, i32, Facing),
+    /// Starts picking which way the selected unit turns as it waits.
+    ChooseFacing,
+    /// The unit at the given position fights with the weapon in the given inventory slot
+    /// from now on. Doesn't end its action.
+    Equip(i32, i32, usize),
+    /// Has the selected unit equip the next weapon it can use.
+    CycleWeapon,
+    /// The unit at the first position uses a support on the ally at the second.
+    Support(i32, i32, i32, i32, Support),
+    /// Picks a support for the selected unit, whose targets the next click chooses between.
+    ChooseSupport(Support),
+    /// The unit at the first position uses an active skill aimed at the cell at the second.
+    UseSkill(i32, i32, i32, i32, Skill),
+    /// Picks an active skill for the selected unit, which the next click aims.
+    ChooseSkill(Skill),
+    /// Closes the open dialog, takes back the selected unit's move, or drops the selection.
+    Cancel,
+    /// Takes back the last order given this phase. Outside casual mode only moves can be
+    /// taken back.
+    UndoMove,
+    /// Takes back every order given this phase. Casual mode only.
+    RewindTurn,
+    /// Opens the save slot menu. From a battle, this pauses it.
+    OpenSaveSlots,
+    /// Leaves the save slot menu for wherever it was opened from.
+    CloseSaveSlots,
+    /// Asks the player to confirm doing something with a save slot.
+    SlotAction(SlotAction, u32),
+    /// Does something with a save slot, once the player has confirmed it.
+    ConfirmSlotAction(SlotAction, u32),
+    /// Opens the options scene.
+    OpenOptions,
+    /// Leaves the options scene for the title screen.
+    CloseOptions,
+    /// Moves a setting on to its next value.
+    ChangeSetting(Setting),
+    /// Ends the player's phase, once the player has confirmed it.
+    ConfirmEndTurn,
+    /// Shows or hides the squares enemies could attack next phase.
+    ToggleThreatMap,
+    /// Writes the replay of the battle so far to the replay file.
+    SaveReplay,
+    /// Plays back the battle in the replay file.
+    WatchReplay,
+    /// Opens level selection for a battle between people sharing the machine.
+    OpenHotSeat,
+    /// Starts a battle on the given level with every team played by a different person.
+    StartHotSeat(i32),
+    /// Shows the map to the next person, once they have the device.
+    BeginPhase,
+    /// Goes back to the title screen.
+    ReturnToTitle,
+    /// Opens the lobby, where hot seat and network battles are set up.
+    OpenLobby,
+    /// Waits for another machine to join on the given port.
+    HostGame(u16),
+    /// Connects to a machine hosting at the given address.
+    JoinGame(SocketAddr),
+    /// Starts a battle on the given level against the machine that joined. Host only.
+    StartNetworkBattle(i32),
+    /// Closes the connection to the other machine and goes back to the title screen.
+    LeaveNetwork,
+    /// Controls the replay being played back.
+    ReplayControl(ReplayControl),
+    /// Promotes the campaign roster's unit at the given index into the given class.
+    Promote(usize, Class),
+    /// Leaves the promotion scene for level selection.
+    FinishPromotion,
+}
+
+impl GameEvent {
+    ///
+    /// # Returns
+    /// * Whether this event is an order a battle logs for its replay, and so one that must
+    ///   reach the other machine in a network battle.
+    ///
+    pub fn is_order(&self) -> bool {
+        matches!(self, GameEvent::MoveUnit(..) | GameEvent::Attack(..) | GameEvent::Wait(..)
+            | GameEvent::Equip(..) | GameEvent::Support(..) | GameEvent::UseSkill(..) | GameEvent::ConfirmEndTurn | GameEvent::UndoMove | GameEvent::RewindTurn)
+    }
+}
+
+///
+/// What the selected unit is about to do once the player picks a cell for it.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Targeting {
+    Support(Support),
+    Skill(Skill),
+    /// Which way to turn before waiting.
+    Facing,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SlotAction {
+    Save,
+    Load,
+    Delete,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UIEvent {
     ClickStartButton,
@@ -32,6 +170,7 @@ pub enum UIEvent {
 ///
 pub trait UIElement {
 
+    #[allow(dead_code)]
     fn get_name(&self) -> String;
 
     ///
@@ -39,6 +178,7 @@ pub trait UIElement {
     /// # Returns
     /// * Whether the update was successful
     ///
+    #[allow(dead_code)]
     fn update(&self, event: UIEvent) -> Result<Option<GameEvent>, GameError>;
 
 
@@ -63,6 +203,16 @@ pub trait UIElement {
 
     fn click_event(&self) -> Option<GameEvent>;
 
+    ///
+    /// Like `click_event`, for elements that care where inside them the click landed.
+    ///
+    /// # Arguments
+    /// * `mouse_x`, `mouse_y` - Position of the click, in pixels.
+    ///
+    fn click_event_at(&self, _mouse_x: f64, _mouse_y: f64) -> Option<GameEvent> {
+        self.click_event()
+    }
+
     ///
     /// # Returns
     /// * The area that this object encompasses. Used for click events.
@@ -97,7 +247,8 @@ pub trait UIElement {
 }
 
 ///
-/// Represents one of the scenes that the game can be currently in.
+/// Represents one of the scenes that the game can be currently in. Scenes hold no drawing
+/// code; the view draws each kind of scene, so the model runs without a window.
 ///
 pub trait Scene {
 
@@ -126,288 +277,830 @@ pub trait Scene {
 
     fn get_ui_elements(&self) -> Vec<&dyn UIElement>;
 
-    fn render(&self, con: &Context, g: &mut G2d, glyphs: &mut Glyphs);
+    ///
+    /// # Returns
+    /// * This scene as `Any`, so the game can reach the concrete scene behind a `dyn Scene`.
+    ///
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
 }
 
-pub struct StartScene {
-    name: String,
-    start_button: Button,
-    activated: bool,
+/// Transition from the title screen to level selection.
+const START_TRANSITION: TransitionStyle = TransitionStyle { kind: TransitionKind::Crossfade, duration: 0.4 };
+/// Transition from level selection into a battle.
+const LEVEL_TRANSITION: TransitionStyle = TransitionStyle { kind: TransitionKind::FadeToBlack, duration: 0.8 };
+/// Transition into and out of the save slot menu.
+const MENU_TRANSITION: TransitionStyle = TransitionStyle { kind: TransitionKind::Crossfade, duration: 0.3 };
+/// Transition between a hot seat battle and the screen that hides it while the device is passed.
+const HANDOFF_TRANSITION: TransitionStyle = TransitionStyle { kind: TransitionKind::FadeToBlack, duration: 0.4 };
+/// Transition from a finished battle back to level selection.
+const END_TRANSITION: TransitionStyle = TransitionStyle { kind: TransitionKind::Wipe, duration: 0.6 };
+
+/// How many levels the campaign has.
+const NUM_LEVELS: i32 = 3;
+/// The battle royale's level, played in hot seat and network battles rather than the campaign.
+pub const ROYALE_LEVEL: i32 = 4;
+
+pub struct Game {
+    scenes: HashMap<String, Box<dyn Scene>>,
+    current_scene: String,
+    transition: Option<Transition>,
+    campaign: Campaign,
+    /// The save slot the campaign is kept in.
+    slot: u32,
+    /// The scene the save slot menu was opened from.
+    return_scene: String,
+    /// The replay being played back, if any. The battle it plays out is the Mid-Level scene.
+    replay: Option<ReplayPlayer>,
+    /// The connection to another machine, while hosting, joining or playing a network battle.
+    net: Option<NetSession>,
 }
 
-impl StartScene {
-    fn new() -> Self {
-        let start_button = Button::new("Start", (HALF_X as f64,
-             HALF_Y as f64), ((HALF_X + 2) as f64, (HALF_Y + 1) as f64),
-            GameEvent::StartGame);
-        Self { name: "Start".to_string(), start_button, activated: false }
+
+impl Game {
+    pub fn new() -> Self {
+        let mut scenes: HashMap<String, Box<dyn Scene>> = HashMap::new();
+        let mut start_scene = Box::new(StartScene::new());
+        start_scene.activate().unwrap();
+        scenes.insert("Start".to_string(), start_scene);
+        let slot = campaign::latest_slot().unwrap_or(DEFAULT_SLOT);
+        let campaign = match Campaign::load(slot) {
+            Ok(campaign) => campaign,
+            Err(SaveError::Io(e)) if e.kind() == io::ErrorKind::NotFound => Campaign::new(),
+            Err(e) => {
+                println!("Couldn't load campaign, starting a new one: {}", e);
+                Campaign::new()
+            }
+        };
+        scenes.insert("Level Selection".to_string(), Box::new(LevelSelectScene::new(NUM_LEVELS, &campaign)));
+        Self {
+            scenes,
+            current_scene: "Start".to_string(),
+            transition: None,
+            campaign,
+            slot,
+            return_scene: "Start".to_string(),
+            replay: None,
+            net: None,
+        }
     }
 
-    pub fn get_button(&self) -> &Button {
-        &self.start_button
+    #[allow(dead_code)]
+    pub fn get_campaign(&self) -> &Campaign {
+        &self.campaign
     }
-}
 
-impl Scene for StartScene {
-    fn get_name(&self) -> &str {
-        self.name.as_str()
+    ///
+    /// # Returns
+    /// * The battle scene, if a battle has been started.
+    ///
+    pub fn get_battle(&self) -> Option<&MidLevelScene> {
+        self.scenes.get("Mid-Level").and_then(|scene| scene.as_any().downcast_ref())
     }
 
-    fn activate(&mut self) -> Result<(), GameError> {
-        if self.activated {
-            return Err(GameError::SceneActivationError);
-        }
-        self.activated = true;
-        Ok(())
+    ///
+    /// Builds the battle for `level`, deploying the campaign's roster. The first level played
+    /// supplies the roster when the campaign doesn't have one yet.
+    ///
+    fn start_level(&mut self, level: i32) -> Result<MidLevelScene, LevelError> {
+        let data = LevelData::load(level)?;
+        let level_units = data.spawn_units().into_iter()
+            .map(|(_, unit)| unit)
+            .filter(|unit| unit.get_team() == PLAYER_TEAM)
+            .collect();
+        self.campaign.adopt_roster(level_units);
+        Ok(MidLevelScene::new(level, &data, rand::rng().random(), self.campaign.get_roster()))
     }
 
-    fn deactivate(&mut self) -> Result<(), GameError> {
-        if !self.activated {
-            return Err(GameError::SceneActivationError);
+    ///
+    /// # Returns
+    /// * The replay being played back, if any.
+    ///
+    pub fn get_replay(&self) -> Option<&ReplayPlayer> {
+        self.replay.as_ref()
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    ///
+    /// Starts playing back the replay file: its battle is rebuilt from the same seed and
+    /// roster, then the recorded events are fed back in as time passes.
+    ///
+    fn watch_replay(&mut self) {
+        let replay = match Replay::load(REPLAY_PATH) {
+            Ok(replay) => replay,
+            Err(e) => {
+                println!("Couldn't load replay: {}", e);
+                return;
+            }
+        };
+        let data = match LevelData::load(replay.level) {
+            Ok(data) => data,
+            Err(e) => {
+                println!("Couldn't load level {} for replay: {}", replay.level, e);
+                return;
+            }
+        };
+        let mut battle = MidLevelScene::new(replay.level, &data, replay.seed, &replay.roster);
+        battle.set_casual(replay.casual);
+        if !replay.sides.is_empty() {
+            battle.set_sides(replay.sides.clone());
+        }
+        self.replay = Some(ReplayPlayer::new(replay));
+        if let Err(e) = self.insert_battle(battle) {
+            println!("Failed to switch to Mid-Level scene: {:?}", e);
+            self.replay = None;
         }
-        self.activated = false;
-        Ok(())
     }
 
-    fn receive_event(&mut self, event: &GameEvent) {
-        if !self.activated {
-            println!("StartScene received event while deactivated: {:?}", event);
+    ///
+    /// Leaves the replay being played back for the title screen.
+    ///
+    fn stop_replay(&mut self) {
+        if self.replay.take().is_none() {
             return;
         }
-        todo!()
+        if let Err(e) = self.switch_scene("Start".to_string(), MENU_TRANSITION) {
+            println!("Failed to leave replay: {:?}", e);
+        }
     }
 
-    fn get_ui_elements(&self) -> Vec<&dyn UIElement> {
-        vec![&self.start_button]
+    ///
+    /// Writes the replay of the battle in progress, or the last one played.
+    ///
+    fn save_replay(&self) {
+        let Some(battle) = self.get_battle() else { return };
+        match battle.to_replay().save(REPLAY_PATH) {
+            Ok(()) => println!("Saved replay to {}", REPLAY_PATH),
+            Err(e) => println!("Couldn't save replay: {}", e),
+        }
     }
 
-    fn render(&self, con: &Context, g: &mut G2d, glyphs: &mut Glyphs) {
-        if !self.activated {
-            println!("StartScene rendered while not activated");
+    ///
+    /// Opens the save slot menu over the current scene, which it goes back to when closed.
+    ///
+    fn open_save_slots(&mut self) {
+        if self.current_scene == "Save Slots" {
             return;
         }
-        draw_start(&self, con, g, glyphs);
+        self.return_scene = self.current_scene.clone();
+        let paused = self.return_scene == "Mid-Level";
+        let result = self.insert_scene("Save Slots", Box::new(SaveSlotScene::new(paused)))
+            .and_then(|()| self.switch_scene("Save Slots".to_string(), MENU_TRANSITION));
+        if let Err(e) = result {
+            println!("Failed to switch to Save Slots scene: {:?}", e);
+        }
     }
-}
 
-pub struct LevelSelectScene {
-    name: String,
-    activated: bool,
-    level_buttons: Vec<Button>,
-}
-
-impl LevelSelectScene {
-    pub fn new(num_levels: i32) -> Self {
-        if num_levels < 1 {
-            panic!("Need at least one level");
-        }
-
-        // GRID-BASED MARGINS 
-        // Side margins = 25% of WIDTH
-        let side_margin: i32 = ((WIDTH as f64) * 0.25).round() as i32;
-        // Top & bottom margins = 20% of HEIGHT
-        let vert_margin: i32 = ((HEIGHT as f64) * 0.20).round() as i32;
-
-        // How many grid-rows remain for buttons + gaps?
-        let usable_rows = HEIGHT - 2 * vert_margin;
-        // How many grid-cols for each button?
-        let button_cols = WIDTH - 2 * side_margin;
-
-        // ─── VERTICAL GAPS ───
-        // Reserve 25% of usable_rows for gaps
-        let total_gap_rows: i32 =
-            ((usable_rows as f64) * 0.25).round() as i32;
-        // Distribute those rows into (num_levels - 1) gaps, rounding up so each gap is >=1 when possible
-        let gap_rows = if num_levels > 1 {
-            (total_gap_rows + (num_levels - 2)) / (num_levels - 1)
-        } else {
-            0
-        };
-
-        // ─── BUTTON HEIGHT ───
-        // Remaining rows divided evenly among buttons
-        let button_rows: i32 = ((usable_rows - gap_rows * (num_levels - 1)) as f64
-            / num_levels as f64)
-            .round() as i32;
-
-        // ─── BUILD THE BUTTONS ──
-        let mut level_buttons = Vec::with_capacity(num_levels as usize);
-        let mut current_y = vert_margin;
-
-        for i in 0..num_levels {
-            let top_left     = (side_margin      as f64, current_y       as f64);
-            let bottom_right = ( (side_margin + button_cols) as f64,
-                                 (current_y   + button_rows)   as f64 );
+    ///
+    /// Rereads the slots shown in the save slot menu after one of them changed.
+    ///
+    fn refresh_save_slots(&mut self) {
+        if self.current_scene != "Save Slots" {
+            return;
+        }
+        let scene = SaveSlotScene::new(self.return_scene == "Mid-Level");
+        if let Err(e) = self.insert_scene("Save Slots", Box::new(scene)) {
+            println!("Failed to refresh Save Slots scene: {:?}", e);
+        }
+    }
 
-            level_buttons.push(
-                Button::new(
-                    &format!("Level {}", i + 1),
-                    top_left,
-                    bottom_right,
-                    SelectLevel(i + 1)
-                )
-            );
+    ///
+    /// Saves the campaign to `slot`, and makes it the slot victories are saved to. The paused
+    /// battle, if any, is saved with it.
+    ///
+    fn save_to_slot(&mut self, slot: u32) -> Result<(), SaveError> {
+        self.campaign.save(slot)?;
+        self.slot = slot;
+        let battle_file = campaign::battle_path(slot);
+        let paused_battle = if self.return_scene == "Mid-Level" { self.get_battle() } else { None };
+        match paused_battle {
+            Some(battle) => save::save_battle(battle, battle_file),
+            None => match std::fs::remove_file(battle_file) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(SaveError::Io(e)),
+                _ => Ok(()),
+            },
+        }
+    }
 
-            // advance past this button + one gap
-            current_y += button_rows + gap_rows;
+    ///
+    /// Makes the campaign in `slot` the current one. Play resumes in the battle saved with it,
+    /// or at level selection if there is none.
+    ///
+    fn load_slot(&mut self, slot: u32) -> Result<(), SaveError> {
+        let loaded = Campaign::load(slot)?;
+        let battle_file = campaign::battle_path(slot);
+        let battle = if battle_file.exists() { Some(save::load_battle(battle_file)?) } else { None };
+        self.campaign = loaded;
+        self.slot = slot;
+        let level_select = LevelSelectScene::new(NUM_LEVELS, &self.campaign);
+        if let Err(e) = self.insert_scene("Level Selection", Box::new(level_select)) {
+            println!("Failed to refresh Level Selection scene: {:?}", e);
         }
+        match battle {
+            Some(battle) => if let Err(e) = self.insert_battle(battle) {
+                println!("Failed to switch to Mid-Level scene: {:?}", e);
+            },
+            None => if let Err(e) = self.switch_scene("Level Selection".to_string(), MENU_TRANSITION) {
+                println!("Failed to switch to Level Selection scene: {:?}", e);
+            },
+        }
+        Ok(())
+    }
 
-        Self {
-            name: "Level Selection".to_string(),
-            level_buttons,
-            activated: false,
+    ///
+    /// Moves `setting` on to its next value, puts it into effect and saves it. Once saved,
+    /// whatever was wrong with the settings file has been written over.
+    ///
+    fn change_setting(&mut self, setting: Setting) {
+        let mut changed = settings::current().clone();
+        changed.cycle(setting);
+        match changed.save() {
+            Ok(()) => changed.warnings.clear(),
+            Err(e) => println!("Couldn't save settings: {}", e),
+        }
+        settings::set_current(changed);
+        if self.current_scene == "Options" {
+            if let Err(e) = self.insert_scene("Options", Box::new(OptionsScene::new())) {
+                println!("Failed to refresh Options scene: {:?}", e);
+            }
         }
     }
 
-    pub fn get_buttons(&self) -> &Vec<Button> {
-        &self.level_buttons
+    ///
+    /// Carries out a confirmed save slot action.
+    ///
+    fn slot_action(&mut self, action: SlotAction, slot: u32) {
+        let result = match action {
+            SlotAction::Save => self.save_to_slot(slot),
+            SlotAction::Load => self.load_slot(slot),
+            SlotAction::Delete => campaign::delete_slot(slot),
+        };
+        match result {
+            Ok(()) => println!("{:?} slot {}: done", action, slot),
+            Err(e) => println!("Couldn't {:?} slot {}: {}", action, slot, e),
+        }
+        self.refresh_save_slots();
     }
-}
 
-impl Scene for LevelSelectScene {
-    fn get_name(&self) -> &str {
-        self.name.as_str()
+    ///
+    /// Wraps up the finished battle: a victory is recorded in the campaign and saved, then
+    /// level selection is shown again with whatever the campaign has unlocked, after the
+    /// promotion scene if any unit is ready to be promoted. A hot seat or network battle names
+    /// its winner instead, and leaves the campaign alone.
+    ///
+    fn end_level(&mut self) {
+        let Some(battle) = self.get_battle() else {
+            println!("Tried to end a level without a battle");
+            return;
+        };
+        let Some(outcome) = battle.get_outcome() else {
+            println!("Tried to end level {} before the battle was decided", battle.get_level());
+            return;
+        };
+        if battle.is_hot_seat() || battle.is_network_battle() {
+            let message = match battle.get_winning_side() {
+                Some(side) => format!("{} wins!", side.name),
+                None => "No one wins".to_string(),
+            };
+            let team = battle.get_winner();
+            self.save_replay();
+            self.show_interstitial(InterstitialScene::new(&message, team, "Continue", GameEvent::ReturnToTitle));
+            return;
+        }
+        let (level, turns, survivors) = (battle.get_level(), battle.get_turn(), battle.get_survivors());
+        self.save_replay();
+        match outcome {
+            BattleOutcome::Victory => {
+                let score = self.campaign.record_victory(level, turns, &survivors);
+                println!("Cleared level {} in {} turns, scoring {}", level, turns, score);
+                if let Err(e) = self.campaign.save(self.slot) {
+                    println!("Couldn't save campaign: {}", e);
+                }
+            }
+            BattleOutcome::Defeat => println!("Defeated on level {}", level),
+        }
+        let level_select = LevelSelectScene::new(NUM_LEVELS, &self.campaign);
+        let mut result = self.insert_scene("Level Selection", Box::new(level_select));
+        let next = if self.campaign.has_promotions() { "Promotion" } else { "Level Selection" };
+        if next == "Promotion" {
+            let promotion = PromotionScene::new(&self.campaign);
+            result = result.and_then(|()| self.insert_scene("Promotion", Box::new(promotion)));
+        }
+        let result = result.and_then(|()| self.switch_scene(next.to_string(), END_TRANSITION));
+        if let Err(e) = result {
+            println!("Failed to switch to {} scene: {:?}", next, e);
+        }
     }
 
-    fn activate(&mut self) -> Result<(), GameError> {
-        if self.activated {
-            return Err(GameError::SceneActivationError);
+    ///
+    /// Promotes the roster unit at `index` into `class` and saves the campaign. The promotion
+    /// scene is laid out again for whoever is left to promote.
+    ///
+    fn promote(&mut self, index: usize, class: Class) {
+        if !self.campaign.promote(index, class) {
+            println!("Couldn't promote roster unit {} to {}", index, class.get_name());
+            return;
+        }
+        println!("Promoted {} to {}", self.campaign.get_roster()[index].get_name(), class.get_name());
+        if let Err(e) = self.campaign.save(self.slot) {
+            println!("Couldn't save campaign: {}", e);
+        }
+        if self.current_scene == "Promotion" {
+            if let Err(e) = self.insert_scene("Promotion", Box::new(PromotionScene::new(&self.campaign))) {
+                println!("Failed to refresh Promotion scene: {:?}", e);
+            }
         }
-        self.activated = true;
-        Ok(())
     }
 
-    fn deactivate(&mut self) -> Result<(), GameError> {
-        if !self.activated {
-            return Err(GameError::SceneActivationError);
+    ///
+    /// Starts a battle on `level` with each of its teams played by a different person.
+    ///
+    fn start_hot_seat(&mut self, level: i32) {
+        let mut battle = match MidLevelScene::load(level, &[]) {
+            Ok(battle) => battle,
+            Err(e) => {
+                println!("Couldn't load level {}: {}", level, e);
+                return;
+            }
+        };
+        let teams: Vec<usize> = battle.get_sides().iter().map(|side| side.team).collect();
+        if teams.len() < 2 {
+            println!("Level {} needs at least two teams for a hot seat battle", level);
+            return;
+        }
+        battle.set_sides(team::hot_seat(&teams));
+        if let Err(e) = self.insert_battle(battle) {
+            println!("Failed to switch to Mid-Level scene: {:?}", e);
         }
-        self.activated = false;
-        Ok(())
     }
 
-    fn receive_event(&mut self, event: &GameEvent) {
-        todo!()
+    ///
+    /// Shows the lobby as of `state`, opening it if it isn't open already.
+    ///
+    fn show_lobby(&mut self, state: LobbyState) {
+        let open = self.current_scene == "Lobby";
+        let mut result = self.insert_scene("Lobby", Box::new(LobbyScene::new(&state, NUM_LEVELS)));
+        if !open {
+            result = result.and_then(|()| self.switch_scene("Lobby".to_string(), MENU_TRANSITION));
+        }
+        if let Err(e) = result {
+            println!("Failed to switch to Lobby scene: {:?}", e);
+        }
     }
 
-    fn get_ui_elements(&self) -> Vec<&dyn UIElement> {
-        self.level_buttons
-            .iter()
-            .map(|btn| btn as &dyn UIElement)
-            .collect()
+    fn host_game(&mut self, port: u16) {
+        match NetSession::host(port) {
+            Ok(session) => {
+                println!("Hosting on {}", session.get_address());
+                self.net = Some(session);
+                self.show_lobby(LobbyState::Hosting(port));
+            }
+            Err(e) => self.show_lobby(LobbyState::Idle(Some(format!("Couldn't host on port {}: {}", port, e)))),
+        }
     }
 
-    fn render(&self, con: &Context, g: &mut G2d, glyphs: &mut Glyphs) {
-        draw_level_selection(&self, con, g, glyphs);
+    fn join_game(&mut self, address: SocketAddr) {
+        self.net = Some(NetSession::join(address));
+        self.show_lobby(LobbyState::Joining(address));
     }
-}
 
-pub struct MidLevelScene {
-    name: String,
-    grid: Vec<Vec<Cell>>,
-    player_turn: bool,
-    activated: bool,
-}
+    ///
+    /// Starts a battle on `level` against the guest, once one has joined. Host only.
+    ///
+    fn start_network_battle(&mut self, level: i32) {
+        let Some(session) = self.net.as_mut().filter(|s| s.get_role() == Role::Host && s.is_connected()) else {
+            println!("Can't start a network battle without a guest");
+            return;
+        };
+        let casual = settings::current().casual_mode;
+        let battle = match net::build_battle(Role::Host, level, rand::rng().random(), casual, &[]) {
+            Ok(battle) => battle,
+            Err(e) => {
+                println!("Couldn't start the network battle: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = session.send_start(&battle) {
+            println!("Couldn't send the battle to the guest: {}", e);
+            return;
+        }
+        if let Err(e) = self.insert_battle(battle) {
+            println!("Failed to switch to Mid-Level scene: {:?}", e);
+        }
+    }
 
-impl MidLevelScene {
-    fn new() -> Self {
-        Self { name: "Mid-Level".to_string(), grid: vec![], player_turn: true, activated: false }
+    ///
+    /// Closes the connection to the other machine, if any, and goes back to the title screen.
+    ///
+    fn leave_network(&mut self) {
+        self.net = None;
+        if let Err(e) = self.switch_scene("Start".to_string(), MENU_TRANSITION) {
+            println!("Failed to switch to Start scene: {:?}", e);
+        }
     }
-}
 
-impl Scene for MidLevelScene {
-    fn get_name(&self) -> &str {
-        self.name.as_str()
+    ///
+    /// # Returns
+    /// * Whether a network battle is being played, or waiting for the connection to come back.
+    ///
+    fn in_network_battle(&self) -> bool {
+        self.net.is_some() && ["Mid-Level", "Reconnect"].contains(&self.current_scene.as_str())
+            && self.get_battle().is_some_and(|battle| battle.is_network_battle() && battle.get_outcome().is_none())
     }
 
-    fn activate(&mut self) -> Result<(), GameError> {
-        if self.activated {
-            return Err(GameError::SceneActivationError);
+    ///
+    /// Acts on what happened on the connection to the other machine.
+    ///
+    fn handle_net_update(&mut self, update: NetUpdate) {
+        let in_battle = self.in_network_battle();
+        let Some(role) = self.net.as_ref().map(NetSession::get_role) else { return };
+        match update {
+            // The guest waits for the host to send the battle again.
+            NetUpdate::Connected if in_battle => if role == Role::Host {
+                self.resume_network_battle();
+            },
+            NetUpdate::Connected => self.show_lobby(LobbyState::Ready(role)),
+            NetUpdate::Start { level, seed, casual, events } => {
+                match net::build_battle(Role::Guest, level, seed, casual, &events) {
+                    Ok(battle) => {
+                        if let Some(session) = self.net.as_mut() {
+                            session.begin(&battle);
+                        }
+                        if let Err(e) = self.insert_battle(battle) {
+                            println!("Failed to switch to Mid-Level scene: {:?}", e);
+                        }
+                    }
+                    Err(e) => self.network_failed(e),
+                }
+            }
+            NetUpdate::Order(event) => self.apply_remote_order(event),
+            NetUpdate::Lost(e) => {
+                println!("Lost the connection to the other player: {}", e);
+                if in_battle {
+                    self.show_reconnect();
+                } else if self.current_scene == "Lobby" {
+                    let session = self.net.as_ref().unwrap();
+                    self.show_lobby(match role {
+                        Role::Host => LobbyState::Hosting(session.get_address().port()),
+                        Role::Guest => LobbyState::Joining(session.get_address()),
+                    });
+                }
+            }
+            NetUpdate::Desync(turn) => self.network_failed(NetError::Desync(turn)),
         }
-        self.activated = true;
-        Ok(())
     }
 
-    fn deactivate(&mut self) -> Result<(), GameError> {
-        if !self.activated {
-            return Err(GameError::SceneActivationError);
+    ///
+    /// Sends the guest that just reconnected the battle so far, and carries on with it. Host only.
+    ///
+    fn resume_network_battle(&mut self) {
+        let battle = self.scenes.get("Mid-Level").and_then(|scene| scene.as_any().downcast_ref::<MidLevelScene>());
+        let (Some(session), Some(battle)) = (self.net.as_mut(), battle) else { return };
+        if let Err(e) = session.send_start(battle) {
+            println!("Couldn't send the battle to the guest: {}", e);
+            return;
+        }
+        if let Err(e) = self.switch_scene("Mid-Level".to_string(), MENU_TRANSITION) {
+            println!("Failed to return to Mid-Level scene: {:?}", e);
         }
-        self.activated = false;
-        Ok(())
     }
 
-    fn receive_event(&mut self, event: &GameEvent) {
-        todo!()
+    ///
+    /// Carries out an order from the other machine, if this machine's battle allows it.
+    ///
+    fn apply_remote_order(&mut self, event: GameEvent) {
+        let battle = self.scenes.get_mut("Mid-Level").and_then(|scene| scene.as_any_mut().downcast_mut::<MidLevelScene>());
+        let (Some(session), Some(battle)) = (self.net.as_mut(), battle) else {
+            println!("Got {:?} from the other player outside a battle", event);
+            return;
+        };
+        let result = session.apply_order(battle, event);
+        let decided = battle.get_outcome().is_some();
+        match result {
+            Err(e) => self.network_failed(e),
+            Ok(()) if decided => self.end_level(),
+            Ok(()) => {}
+        }
     }
 
-    fn get_ui_elements(&self) -> Vec<&dyn UIElement> {
-        todo!()
+    ///
+    /// Sends the other machine any orders just given here. See `NetSession::sync`.
+    ///
+    fn sync_network(&mut self) {
+        let battle = self.scenes.get("Mid-Level").and_then(|scene| scene.as_any().downcast_ref::<MidLevelScene>());
+        let (Some(session), Some(battle)) = (self.net.as_mut(), battle) else { return };
+        if !battle.is_network_battle() {
+            return;
+        }
+        if let Err(e) = session.sync(battle) {
+            self.network_failed(e);
+        }
     }
 
-    fn render(&self, con: &Context, g: &mut G2d, glyphs: &mut Glyphs) {
-        todo!()
+    ///
+    /// Waits for a lost connection to come back, or stops the network battle for good if the
+    /// two machines can no longer agree on it.
+    ///
+    fn network_failed(&mut self, error: NetError) {
+        match error {
+            NetError::Disconnected | NetError::Io(_) => {
+                if let Some(session) = self.net.as_mut() {
+                    session.disconnect();
+                }
+                self.show_reconnect();
+            }
+            error => {
+                println!("Network battle stopped: {}", error);
+                let message = format!("Network battle stopped: {}", error);
+                self.show_interstitial(InterstitialScene::new(&message, None, "Leave", GameEvent::LeaveNetwork));
+            }
+        }
     }
 
-}
-
-pub struct Game {
-    scenes: HashMap<String, Box<dyn Scene>>,
-    current_scene: String,
-}
+    fn show_reconnect(&mut self) {
+        let Some(session) = self.net.as_ref() else { return };
+        let scene = ReconnectScene::new(session);
+        let open = self.current_scene == "Reconnect";
+        let mut result = self.insert_scene("Reconnect", Box::new(scene));
+        if !open {
+            result = result.and_then(|()| self.switch_scene("Reconnect".to_string(), MENU_TRANSITION));
+        }
+        if let Err(e) = result {
+            println!("Failed to switch to Reconnect scene: {:?}", e);
+        }
+    }
 
+    ///
+    /// Hides the battle behind a screen asking for the device to be passed to whoever plays next.
+    ///
+    fn hand_off(&mut self) {
+        let Some(battle) = self.get_battle() else { return };
+        let side = battle.get_active_side();
+        let message = format!("Pass the device to {}", side.name);
+        let team = Some(side.team);
+        self.show_interstitial(InterstitialScene::new(&message, team, "Ready", GameEvent::BeginPhase));
+    }
 
-impl Game {
-    pub fn new() -> Self {
-        let mut scenes: HashMap<String, Box<dyn Scene>> = HashMap::new();
-        let mut start_scene = Box::new(StartScene::new());
-        start_scene.activate().unwrap();
-        scenes.insert("Start".to_string(), start_scene);
-        scenes.insert("Level Selection".to_string(), Box::new(LevelSelectScene::new(3)));
-        scenes.insert("Mid-Level".to_string(), Box::new(MidLevelScene::new()));
-        Self { scenes, current_scene: "Start".to_string() }
+    fn show_interstitial(&mut self, scene: InterstitialScene) {
+        let result = self.insert_scene("Interstitial", Box::new(scene))
+            .and_then(|()| self.switch_scene("Interstitial".to_string(), HANDOFF_TRANSITION));
+        if let Err(e) = result {
+            println!("Failed to switch to Interstitial scene: {:?}", e);
+        }
     }
 
-    pub fn switch_scene(&mut self, scene_name: String) -> Result<(), GameError> {
+    ///
+    /// Makes `scene_name` the current scene. The previous scene keeps rendering, and stays
+    /// activated, until the transition finishes.
+    ///
+    pub fn switch_scene(&mut self, scene_name: String, style: TransitionStyle) -> Result<(), GameError> {
         if self.current_scene == scene_name {
             return Err(GameError::SceneActivationError)
         }
-        self.current_scene = scene_name;
-        self.scenes.get_mut(self.current_scene.as_str()).unwrap().activate().expect("TODO: panic message");
+        if !self.scenes.contains_key(scene_name.as_str()) {
+            return Err(GameError::SceneSelectionError)
+        }
+        self.finish_transition()?;
+        let previous = std::mem::replace(&mut self.current_scene, scene_name);
+        self.scenes.get_mut(self.current_scene.as_str()).unwrap().activate()?;
         println!("Switching scene {}", self.current_scene);
+        self.transition = Some(Transition::new(style, &previous, &self.current_scene));
+        if style.kind == TransitionKind::Cut || style.duration <= 0.0 {
+            self.finish_transition()?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Makes `battle` the battle scene and shows it. If a battle was already being shown,
+    /// it is replaced on the spot.
+    ///
+    fn insert_battle(&mut self, battle: MidLevelScene) -> Result<(), GameError> {
+        self.insert_scene("Mid-Level", Box::new(battle))?;
+        if self.current_scene == "Mid-Level" {
+            return Ok(());
+        }
+        self.switch_scene("Mid-Level".to_string(), LEVEL_TRANSITION)
+    }
+
+    ///
+    /// Stores `scene` under `name`, replacing the scene stored there, if any. A running
+    /// transition is finished first, so that the scene it leaves is deactivated before it can
+    /// be replaced. A scene replacing the current one is activated in its place.
+    ///
+    fn insert_scene(&mut self, name: &str, mut scene: Box<dyn Scene>) -> Result<(), GameError> {
+        self.finish_transition()?;
+        if name == self.current_scene {
+            scene.activate()?;
+        }
+        self.scenes.insert(name.to_string(), scene);
+        Ok(())
+    }
+
+    ///
+    /// Ends the running transition, if any, deactivating the scene being left.
+    ///
+    fn finish_transition(&mut self) -> Result<(), GameError> {
+        if let Some(transition) = self.transition.take() {
+            self.scenes.get_mut(transition.get_from()).unwrap().deactivate()?;
+        }
         Ok(())
     }
 
+    ///
+    /// Advances time-based state, such as scene transitions.
+    ///
+    /// # Arguments
+    /// * `dt` - Seconds elapsed since the last update.
+    ///
+    pub fn update(&mut self, dt: f64) {
+        if self.is_campaign_scene(&self.current_scene) {
+            self.campaign.add_play_time(dt);
+        }
+        let updates = self.net.as_mut().map_or_else(Vec::new, |session| session.poll(dt));
+        for update in updates {
+            self.handle_net_update(update);
+        }
+        if !self.in_transition() {
+            let due = self.replay.as_mut().map_or_else(Vec::new, |replay| replay.advance(dt));
+            for event in due {
+                self.receive_event(&event);
+            }
+        }
+        let speed = settings::current().animation_speed;
+        let finished = self.transition.as_mut().is_some_and(|t| t.advance(dt * speed));
+        if finished {
+            if let Err(e) = self.finish_transition() {
+                println!("Failed to finish scene transition: {:?}", e);
+            }
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * Whether the scene named `name` is part of playing the campaign: one of its battles, or
+    ///   a menu between them. Hot seat and network battles, replays, the title screen and the
+    ///   options aren't. The save slot menu counts when it was opened from the campaign.
+    ///
+    fn is_campaign_scene(&self, name: &str) -> bool {
+        match name {
+            "Level Selection" | "Promotion" => true,
+            "Save Slots" => self.is_campaign_scene(&self.return_scene),
+            "Mid-Level" => !self.is_replaying() && self.get_battle()
+                .is_some_and(|battle| !battle.is_hot_seat() && !battle.is_network_battle()),
+            _ => false,
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * Whether a scene transition is playing. Input is ignored while it does.
+    ///
+    pub fn in_transition(&self) -> bool {
+        self.transition.is_some()
+    }
+
+    pub fn get_transition(&self) -> Option<&Transition> {
+        self.transition.as_ref()
+    }
+
     pub fn receive_event(&mut self, event: &GameEvent) {
         match event {
             GameEvent::StartGame => {
                 if self.current_scene != "Start" { panic!(); }
-                if let Err(e) = self.switch_scene("Level Selection".to_string()) {
+                if let Err(e) = self.switch_scene("Level Selection".to_string(), START_TRANSITION) {
                     println!("Failed to switch to Mid-Level scene: {:?}", e);
                     panic!();
                 }
             }
             SelectLevel(level) => {
                 println!("Selected level: {:?}", level);
-                if let Err(e) = self.switch_scene("Level Selection".to_string()) {
+                if !self.campaign.is_unlocked(*level) {
+                    println!("Level {} is still locked", level);
+                    return;
+                }
+                let scene = match self.start_level(*level) {
+                    Ok(scene) => scene,
+                    Err(e) => {
+                        println!("Couldn't load level {}: {}", level, e);
+                        return;
+                    }
+                };
+                if let Err(e) = self.insert_battle(scene) {
                     println!("Failed to switch to Mid-Level scene: {:?}", e);
-                    panic!();
                 }
-                todo!()
             },
-            GameEvent::EndLevel => {
-                todo!()
+            GameEvent::LoadBattle | GameEvent::OpenSaveSlots if self.net.is_some() => {
+                println!("Can't use saves during a network battle");
+            }
+            GameEvent::LoadBattle => {
+                let scene = match save::load_battle(QUICKSAVE_PATH) {
+                    Ok(scene) => scene,
+                    Err(e) => {
+                        println!("Couldn't load battle: {}", e);
+                        return;
+                    }
+                };
+                if let Err(e) = self.insert_battle(scene) {
+                    println!("Failed to switch to Mid-Level scene: {:?}", e);
+                }
+            }
+            GameEvent::EndLevel => self.end_level(),
+            GameEvent::OpenSaveSlots => self.open_save_slots(),
+            GameEvent::CloseSaveSlots => {
+                if let Err(e) = self.switch_scene(self.return_scene.clone(), MENU_TRANSITION) {
+                    println!("Failed to leave Save Slots scene: {:?}", e);
+                }
+            }
+            GameEvent::ConfirmSlotAction(action, slot) => self.slot_action(*action, *slot),
+            GameEvent::OpenOptions => {
+                let result = self.insert_scene("Options", Box::new(OptionsScene::new()))
+                    .and_then(|()| self.switch_scene("Options".to_string(), MENU_TRANSITION));
+                if let Err(e) = result {
+                    println!("Failed to switch to Options scene: {:?}", e);
+                }
+            }
+            GameEvent::CloseOptions => {
+                if let Err(e) = self.switch_scene("Start".to_string(), MENU_TRANSITION) {
+                    println!("Failed to leave Options scene: {:?}", e);
+                }
+            }
+            GameEvent::ChangeSetting(setting) => self.change_setting(*setting),
+            GameEvent::Promote(index, class) => self.promote(*index, *class),
+            GameEvent::FinishPromotion => {
+                if let Err(e) = self.switch_scene("Level Selection".to_string(), MENU_TRANSITION) {
+                    println!("Failed to leave Promotion scene: {:?}", e);
+                }
+            }
+            GameEvent::OpenHotSeat => {
+                let result = self.insert_scene("Hot Seat", Box::new(LevelSelectScene::hot_seat(NUM_LEVELS)))
+                    .and_then(|()| self.switch_scene("Hot Seat".to_string(), START_TRANSITION));
+                if let Err(e) = result {
+                    println!("Failed to switch to Hot Seat scene: {:?}", e);
+                }
+            }
+            GameEvent::StartHotSeat(level) => self.start_hot_seat(*level),
+            GameEvent::BeginPhase => {
+                self.get_scene_mut("Mid-Level").receive_event(event);
+                if let Err(e) = self.switch_scene("Mid-Level".to_string(), HANDOFF_TRANSITION) {
+                    println!("Failed to return to Mid-Level scene: {:?}", e);
+                }
+            }
+            GameEvent::ReturnToTitle => {
+                self.net = None;
+                if let Err(e) = self.switch_scene("Start".to_string(), END_TRANSITION) {
+                    println!("Failed to switch to Start scene: {:?}", e);
+                }
+            }
+            GameEvent::OpenLobby => self.show_lobby(LobbyState::Idle(None)),
+            GameEvent::HostGame(port) => self.host_game(*port),
+            GameEvent::JoinGame(address) => self.join_game(*address),
+            GameEvent::StartNetworkBattle(level) => self.start_network_battle(*level),
+            GameEvent::LeaveNetwork => self.leave_network(),
+            GameEvent::SaveReplay => self.save_replay(),
+            GameEvent::WatchReplay => self.watch_replay(),
+            GameEvent::ReplayControl(ReplayControl::Stop) => self.stop_replay(),
+            GameEvent::ReplayControl(control) => {
+                let stepped = self.replay.as_mut().and_then(|replay| replay.control(*control));
+                if let Some(event) = stepped {
+                    self.receive_event(&event);
+                }
             }
             _ => {
-                panic!()
+                let current = self.current_scene.clone();
+                let could_order = event.is_order()
+                    || matches!(event, GameEvent::SelectCell(..) | GameEvent::EndTurn | GameEvent::CycleWeapon);
+                if current == "Mid-Level" && self.net.is_some() && could_order
+                    && self.get_battle().is_some_and(|battle| battle.is_network_battle() && !battle.is_local_phase()) {
+                    println!("Waiting for the other player's orders");
+                    return;
+                }
+                self.get_scene_mut(current.as_str()).receive_event(event);
+                if current == "Mid-Level" {
+                    self.sync_network();
+                }
+                let decided = self.get_battle().is_some_and(|battle| battle.get_outcome().is_some());
+                // A replayed battle only shows how it ended; it doesn't count towards the campaign.
+                if current == "Mid-Level" && decided && !self.is_replaying() {
+                    self.end_level();
+                } else if current == "Mid-Level" && !self.is_replaying()
+                    && self.get_battle().is_some_and(|battle| battle.needs_handoff()) {
+                    self.hand_off();
+                }
             }
         }
     }
 
-    pub fn get_current_scene(&self) -> &Box<dyn Scene> {
+    pub fn get_current_scene(&self) -> &dyn Scene {
         self.get_scene(self.current_scene.as_str())
     }
 
-    pub fn get_scene(&self, key: &str) -> &Box<dyn Scene> {
-        self.scenes.get(key).unwrap()
+    pub fn get_scene(&self, key: &str) -> &dyn Scene {
+        self.scenes.get(key).unwrap().as_ref()
     }
 
     pub fn get_scene_mut(&mut self, key: &str) -> &mut Box<dyn Scene> {
@@ -415,22 +1108,7 @@ impl Game {
     }
 
     pub fn click_event(&mut self, mouse_x: f64, mouse_y: f64) -> Option<usize> {
-        for (index, element) in self.get_ui_elements().iter().enumerate() {
-            { // Debug
-                println!("Checking element {}", element.get_name());
-                println!("Container: {}, {}, {}, {}", element.get_container_coords().0,
-                         element.get_container_coords().1, element.get_container_coords().2,
-                         element.get_container_coords().3);
-            }
-            
-            if element.cursor_in_container(mouse_x, mouse_y) {
-                println!("IN CONTAINER");
-                return Some(index);
-            } else {
-                println!("Missed container");
-            }
-        }
-        None
+        self.get_ui_elements().iter().position(|element| element.cursor_in_container(mouse_x, mouse_y))
     }
 
     pub fn get_ui_elements(&self) -> Vec<&dyn UIElement> {
@@ -440,8 +1118,51 @@ impl Game {
     pub fn get_ui_element(&self, idx: usize) -> Option<&dyn UIElement> {
         self.get_ui_elements().get(idx).copied()
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_both_scenes_until_the_transition_ends() {
+        let mut game = Game::new();
+        game.switch_scene("Level Selection".to_string(), START_TRANSITION).unwrap();
+        assert!(game.in_transition());
+        game.update(START_TRANSITION.duration / 2.0);
+        assert_eq!(game.get_transition().map(Transition::get_from), Some("Start"));
+        game.update(START_TRANSITION.duration);
+        assert!(!game.in_transition());
+    }
+
+    #[test]
+    fn rejects_switching_to_the_current_or_an_unknown_scene() {
+        let mut game = Game::new();
+        assert!(matches!(game.switch_scene("Start".to_string(), START_TRANSITION),
+                         Err(GameError::SceneActivationError)));
+        assert!(matches!(game.switch_scene("Nowhere".to_string(), START_TRANSITION),
+                         Err(GameError::SceneSelectionError)));
+    }
+
+    #[test]
+    fn replacing_the_scene_being_left_finishes_the_transition_first() {
+        let mut game = Game::new();
+        game.switch_scene("Level Selection".to_string(), START_TRANSITION).unwrap();
+        game.insert_scene("Start", Box::new(StartScene::new())).unwrap();
+        assert!(!game.in_transition());
+        // The new Start scene was never activated, so switching back to it, and finishing
+        // that transition, both work.
+        game.switch_scene("Start".to_string(), START_TRANSITION).unwrap();
+        game.finish_transition().unwrap();
+    }
 
-    pub fn render_scene(&self, con: &Context, g: &mut G2d, glyphs: &mut Glyphs) {
-        self.get_current_scene().render(con, g, glyphs);
+    #[test]
+    fn a_scene_replacing_the_current_one_is_activated() {
+        let mut game = Game::new();
+        let campaign = Campaign::new();
+        game.switch_scene("Level Selection".to_string(), START_TRANSITION).unwrap();
+        game.insert_scene("Level Selection", Box::new(LevelSelectScene::new(NUM_LEVELS, &campaign))).unwrap();
+        assert!(matches!(game.scenes.get_mut("Level Selection").unwrap().activate(),
+                         Err(GameError::SceneActivationError)));
     }
 }
@@ -0,0 +1,203 @@
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::model::game::{GameEvent, Scene};
+use crate::model::midlevel::{BattleOutcome, MidLevelScene};
+use crate::model::inlevel::ai;
+use crate::model::inlevel::combat::Fight;
+use crate::model::inlevel::level::{LevelData, LevelError};
+use crate::model::inlevel::unit::Unit;
+use crate::model::net::{self, NetError, NetSession, NetUpdate, Role};
+
+/// Turns a headless battle may last before it is called a draw, so a stalemate can't run forever.
+pub const MAX_TURNS: u32 = 100;
+
+/// How long a headless network battle waits between looks at the connection.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How long a finished network battle waits for the other side's last state hash.
+const FINAL_HASH_TIMEOUT: Duration = Duration::from_secs(5);
+
+///
+/// How a battle played without a window turned out.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct BattleResult {
+    pub level: i32,
+    pub seed: u64,
+    /// `None` if neither side had won after `MAX_TURNS` turns.
+    pub outcome: Option<BattleOutcome>,
+    /// The turn the battle ended on.
+    pub turns: u32,
+    /// The player's units still standing at the end.
+    pub survivors: Vec<Unit>,
+    /// Every fight in the battle, in order.
+    pub fights: Vec<Fight>,
+}
+
+impl BattleResult {
+    ///
+    /// # Returns
+    /// * A one line summary of the battle, as printed by `--headless`.
+    ///
+    pub fn describe(&self) -> String {
+        let outcome = match self.outcome {
+            Some(BattleOutcome::Victory) => "Victory",
+            Some(BattleOutcome::Defeat) => "Defeat",
+            None => "Draw",
+        };
+        format!("Level {} (seed {}): {} on turn {} with {} survivor(s)",
+                self.level, self.seed, outcome, self.turns, self.survivors.len())
+    }
+}
+
+///
+/// Plays a battle to the end with the computer in charge of every team, the player's
+/// included. The battle is driven only through `GameEvent`s, as it would be by a player,
+/// so nothing here needs a window.
+///
+/// # Arguments
+/// * `level`  - The level file to fight on.
+/// * `seed`   - Seed for the battle's random outcomes. The same seed plays out the same way.
+/// * `roster` - The player's units, deployed as in `MidLevelScene::new`.
+///
+/// # Returns
+/// * How the battle ended, or why the level couldn't be loaded.
+///
+pub fn run_battle(level: i32, seed: u64, roster: &[Unit]) -> Result<BattleResult, LevelError> {
+    Ok(play_battle(level, &LevelData::load(level)?, seed, roster, false))
+}
+
+///
+/// Like `run_battle`, on a level that is already loaded.
+///
+/// # Arguments
+/// * `quiet` - Whether to keep fights from being printed, e.g. while simulating many battles.
+///
+pub fn play_battle(level: i32, data: &LevelData, seed: u64, roster: &[Unit], quiet: bool) -> BattleResult {
+    let mut scene = MidLevelScene::new(level, data, seed, roster);
+    scene.set_quiet(quiet);
+    while scene.get_outcome().is_none() && scene.get_turn() <= MAX_TURNS {
+        play_human_phase(&mut scene);
+        if scene.get_outcome().is_none() {
+            scene.receive_event(&GameEvent::ConfirmEndTurn);
+        }
+    }
+    BattleResult {
+        level,
+        seed,
+        outcome: scene.get_outcome(),
+        turns: scene.get_turn(),
+        survivors: scene.get_survivors(),
+        fights: scene.get_fights().to_vec(),
+    }
+}
+
+///
+/// Plays a network battle with the computer giving this machine's orders, so that two
+/// processes can play each other without a window, e.g. on localhost.
+///
+/// # Arguments
+/// * `session` - This machine's end of the connection. A host waits for a guest to join;
+///   a guest keeps trying to reach the host.
+/// * `level`   - The level to fight on. Host only; the guest plays whatever the host sends.
+/// * `seed`    - Seed for the battle's random outcomes. Host only.
+///
+/// # Returns
+/// * The battle as it ended, once both sides have agreed on how it ended, or why it couldn't
+///   be played to the end.
+///
+pub fn play_network_battle(session: &mut NetSession, level: i32, seed: u64) -> Result<MidLevelScene, NetError> {
+    let mut battle = None;
+    while battle.is_none() {
+        for update in session.poll(POLL_INTERVAL.as_secs_f64()) {
+            match update {
+                NetUpdate::Connected if session.get_role() == Role::Host => {
+                    let started = net::build_battle(Role::Host, level, seed, false, &[])?;
+                    session.send_start(&started)?;
+                    battle = Some(started);
+                }
+                NetUpdate::Start { level, seed, casual, events } => {
+                    let started = net::build_battle(Role::Guest, level, seed, casual, &events)?;
+                    session.begin(&started);
+                    battle = Some(started);
+                }
+                NetUpdate::Lost(e) => println!("{}; waiting for the other player", e),
+                _ => {}
+            }
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    let mut battle = battle.unwrap();
+
+    while battle.get_outcome().is_none() && battle.get_turn() <= MAX_TURNS {
+        if battle.is_local_phase() {
+            play_human_phase(&mut battle);
+            if battle.get_outcome().is_none() {
+                battle.receive_event(&GameEvent::ConfirmEndTurn);
+            }
+            session.sync(&battle)?;
+            continue;
+        }
+        for update in session.poll(POLL_INTERVAL.as_secs_f64()) {
+            match update {
+                NetUpdate::Order(event) => session.apply_order(&mut battle, event)?,
+                NetUpdate::Lost(e) => return Err(e),
+                NetUpdate::Desync(turn) => return Err(NetError::Desync(turn)),
+                _ => {}
+            }
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+
+    // Both sides hashed the battle as it ended; wait to hear that the other got the same.
+    let deadline = Instant::now() + FINAL_HASH_TIMEOUT;
+    while !session.is_in_step() && Instant::now() < deadline {
+        for update in session.poll(POLL_INTERVAL.as_secs_f64()) {
+            match update {
+                NetUpdate::Desync(turn) => return Err(NetError::Desync(turn)),
+                NetUpdate::Lost(e) => return Err(e),
+                _ => {}
+            }
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    if !session.is_in_step() {
+        println!("The other player never confirmed how the battle ended");
+    }
+    Ok(battle)
+}
+
+///
+/// Has the computer order every unit of the side whose phase it is, as it would for a
+/// computer side, but through the same events a person's clicks turn into.
+///
+fn play_human_phase(scene: &mut MidLevelScene) {
+    let team = scene.get_active_team();
+    while let Some(pos) = ai::next_unit(scene, team) {
+        if scene.get_outcome().is_some() {
+            return;
+        }
+        let mut at = pos;
+        for event in ai::plan_unit(scene, pos) {
+            scene.receive_event(&event);
+            if let GameEvent::MoveUnit(_, _, x, y) = event {
+                at = (x, y);
+            }
+        }
+        // A plan that fell through leaves the unit ready; have it wait so the phase goes on.
+        if let Some(stuck) = [at, pos].into_iter().find(|&p| is_ready(scene, team, p)) {
+            let facing = scene.get_cell(stuck.0, stuck.1).and_then(|cell| cell.get_unit()).unwrap().get_facing();
+            scene.receive_event(&GameEvent::Wait(stuck.0, stuck.1, facing));
+            if is_ready(scene, team, stuck) {
+                println!("Unit at {:?} couldn't act; ending the phase", stuck);
+                return;
+            }
+        }
+    }
+}
+
+fn is_ready(scene: &MidLevelScene, team: usize, pos: (i32, i32)) -> bool {
+    scene.get_cell(pos.0, pos.1)
+        .and_then(|cell| cell.get_unit())
+        .is_some_and(|unit| unit.get_team() == team && !unit.is_done())
+}
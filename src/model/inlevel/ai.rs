@@ -0,0 +1,245 @@
+use crate::model::game::GameEvent;
+use crate::model::midlevel::MidLevelScene;
+use crate::model::inlevel::combat;
+use crate::model::inlevel::effect::StackRule;
+use crate::model::inlevel::facing::{Facing, Flank};
+use crate::model::inlevel::pathing::{self, distance};
+use crate::model::inlevel::sight;
+use crate::model::inlevel::skill::{Primitive, Skill};
+use crate::model::inlevel::support::Support;
+use crate::model::inlevel::unit::Unit;
+
+/// Score bonus for an attack that is expected to defeat its target.
+const KILL_BONUS: u32 = 100;
+/// Score for each point of HP a heal is expected to restore, weighed against an attack's
+/// expected damage times its hit chance.
+const HEAL_SCORE: u32 = 100;
+/// Score for each harmful effect a cure takes away.
+const CURE_SCORE: u32 = 300;
+/// Score for shielding an ally with an enemy close by.
+const BARRIER_SCORE: u32 = 250;
+/// How close, in steps, an enemy has to be for an ally to be worth a barrier.
+const BARRIER_THREAT_DISTANCE: i32 = 3;
+/// Score for each boon a skill gives a unit with an enemy close by.
+const BUFF_SCORE: u32 = 150;
+/// Score for each hindrance a skill puts on an enemy, before any damage it does over time.
+const HINDER_SCORE: u32 = 150;
+
+/// An action being considered: how good it is, where to act from, and the order to give there.
+struct Plan {
+    score: u32,
+    from: (i32, i32),
+    order: GameEvent,
+}
+
+///
+/// # Returns
+/// * How much using `support` on `target` is worth, as `plan_unit` weighs it.
+///
+fn support_score(support: Support, user: &Unit, target: &Unit, enemies_near: bool) -> u32 {
+    match support {
+        Support::Heal => HEAL_SCORE * Support::heal_amount(user).min(target.get_max_hp() - target.get_hp()),
+        Support::Cure => CURE_SCORE * target.get_effects().iter().filter(|e| e.kind.is_harmful()).count() as u32,
+        Support::Barrier if enemies_near => BARRIER_SCORE,
+        Support::Barrier => 0,
+    }
+}
+
+///
+/// # Returns
+/// * How much `primitive`, from `user`, doing something to `target` is worth, as
+///   `plan_unit` weighs it. Skills always hit, so damage counts in full.
+///
+fn primitive_score(primitive: &Primitive, user: &Unit, target: &Unit, enemies_near: bool) -> u32 {
+    match *primitive {
+        Primitive::Damage { .. } => {
+            let damage = primitive.damage(user, target);
+            100 * damage + if damage >= target.get_hp() { 100 * KILL_BONUS } else { 0 }
+        }
+        Primitive::Heal { .. } => HEAL_SCORE * primitive.healing(user).min(target.get_max_hp() - target.get_hp()),
+        Primitive::Inflict { kind, turns } => {
+            let has_it = target.get_effects().iter().any(|e| e.kind.is_like(&kind) && e.kind.potency() >= kind.potency());
+            match kind.is_harmful() {
+                _ if has_it && kind.stack_rule() == StackRule::Refresh => 0,
+                true => HINDER_SCORE + 50 * kind.damage() * turns,
+                false if enemies_near => BUFF_SCORE,
+                false => 0,
+            }
+        }
+    }
+}
+
+///
+/// # Returns
+/// * The position of the next unit of `team` that still has to act this phase, if any.
+///   Units are taken in reading order so that the same battle always plays out the same way.
+///
+pub fn next_unit(scene: &MidLevelScene, team: usize) -> Option<(i32, i32)> {
+    scene.get_cells()
+        .find(|cell| cell.get_unit().is_some_and(|u| u.get_team() == team && !u.is_done()))
+        .map(|cell| cell.get_position())
+}
+
+///
+/// Decides what the unit at `pos` does this phase: attack the most damaging target it can
+/// reach, from where it risks the least back, or help an ally or use a skill if that is
+/// worth more, or otherwise walk towards the closest enemy and wait facing it.
+///
+/// # Returns
+/// * The events that carry the plan out, in order. The last one always finishes the unit's
+///   action.
+///
+pub fn plan_unit(scene: &MidLevelScene, pos: (i32, i32)) -> Vec<GameEvent> {
+    let Some(unit) = scene.get_cell(pos.0, pos.1).and_then(|c| c.get_unit()) else {
+        return vec![];
+    };
+    let team = unit.get_team();
+    let destinations = pathing::destinations(scene.get_grid(), pos, unit.movement(), team);
+    let move_costs = pathing::reachable(scene.get_grid(), pos, unit.movement(), team);
+    let enemies: Vec<(i32, i32)> = scene.get_cells()
+        .filter(|c| c.get_unit().is_some_and(|u| u.get_team() != team))
+        .map(|c| c.get_position())
+        .collect();
+
+    let allies: Vec<(i32, i32)> = scene.get_cells()
+        .filter(|c| c.get_unit().is_some_and(|u| u.get_team() == team) && c.get_position() != pos)
+        .map(|c| c.get_position())
+        .collect();
+
+    let mut best: Option<Plan> = None;
+    for &dest in destinations.iter() {
+        // The unit as it would be once it has moved here, to see what it could still afford.
+        let mut mover = unit.clone();
+        mover.tire_from_move(move_costs.get(&dest).copied().unwrap_or(0));
+        for &target in enemies.iter().filter(|&&e| sight::can_strike(scene.get_grid(), &mover, dest, e)) {
+            let cell = scene.get_cell(target.0, target.1).unwrap();
+            let defender = cell.get_unit().unwrap();
+            let terrain = *scene.get_cell(dest.0, dest.1).unwrap().get_terrain();
+            let flank = defender.get_facing().flank(target, dest);
+            let Some(odds) = combat::forecast(&mover, defender, terrain, *cell.get_terrain(), flank) else { continue };
+            let mut score = odds.damage * odds.hit;
+            if odds.damage >= defender.get_hp() {
+                score += KILL_BONUS * odds.hit;
+            } else if flank.allows_counter() && sight::can_strike(scene.get_grid(), defender, target, dest) {
+                // Rather strike from where the target can't strike back.
+                let counter = combat::forecast(defender, &mover, *cell.get_terrain(), terrain, Flank::Front)
+                    .map_or(0, |back| back.damage * back.hit);
+                score = score.saturating_sub(counter / 2);
+            }
+            if best.as_ref().is_none_or(|plan| score > plan.score) {
+                let order = GameEvent::Attack(dest.0, dest.1, target.0, target.1);
+                best = Some(Plan { score, from: dest, order });
+            }
+        }
+        for support in Support::known_by(unit.get_class()) {
+            for &target in allies.iter() {
+                let ally = scene.get_cell(target.0, target.1).unwrap().get_unit().unwrap();
+                if !support.can_use(&mover, ally, distance(dest, target)) {
+                    continue;
+                }
+                let enemies_near = enemies.iter().any(|&e| distance(e, target) <= BARRIER_THREAT_DISTANCE);
+                let score = support_score(support, &mover, ally, enemies_near);
+                if score > 0 && best.as_ref().is_none_or(|plan| score > plan.score) {
+                    let order = GameEvent::Support(dest.0, dest.1, target.0, target.1, support);
+                    best = Some(Plan { score, from: dest, order });
+                }
+            }
+        }
+        for skill in Skill::known_by(unit).into_iter().filter(|skill| skill.usable_by(&mover)) {
+            let aims = skill.aim_cells(dest).into_iter()
+                .filter(|&(x, y)| scene.get_cell(x, y).is_some() && sight::has_line_of_sight(scene.get_grid(), dest, (x, y)));
+            for center in aims {
+                let mut score = 0;
+                for spot in skill.area_cells(center) {
+                    let target = match spot {
+                        _ if spot == dest => Some(&mover),
+                        _ if spot == pos => None,
+                        (x, y) => scene.get_cell(x, y).and_then(|c| c.get_unit()),
+                    };
+                    let Some(target) = target.filter(|target| skill.affects(&mover, target)) else { continue };
+                    let enemies_near = enemies.iter().any(|&e| distance(e, spot) <= BARRIER_THREAT_DISTANCE);
+                    score += skill.effects().iter()
+                        .map(|primitive| primitive_score(primitive, &mover, target, enemies_near))
+                        .sum::<u32>();
+                }
+                if score > 0 && best.as_ref().is_none_or(|plan| score > plan.score) {
+                    let order = GameEvent::UseSkill(dest.0, dest.1, center.0, center.1, skill);
+                    best = Some(Plan { score, from: dest, order });
+                }
+            }
+        }
+    }
+
+    let mut events = Vec::new();
+    if let Some(Plan { from, order, .. }) = best {
+        if from != pos {
+            events.push(GameEvent::MoveUnit(pos.0, pos.1, from.0, from.1));
+        }
+        events.push(order);
+        return events;
+    }
+
+    let closest_enemy = enemies.iter().min_by_key(|&&e| distance(pos, e));
+    // In a battle royale, stay inside the zone as it will be next turn, or head back in.
+    let (zone, next_turn) = (scene.get_zone(), scene.get_turn() + 1);
+    let outside = |d: (i32, i32)| zone.is_some_and(|zone| !zone.contains(d, next_turn));
+    let dest = match (closest_enemy, zone) {
+        (_, Some(zone)) if outside(pos) =>
+            *destinations.iter().min_by_key(|&&d| distance(d, zone.get_center())).unwrap_or(&pos),
+        (Some(&enemy), _) => *destinations.iter().min_by_key(|&&d| (outside(d), distance(d, enemy))).unwrap_or(&pos),
+        (None, _) => pos,
+    };
+    if dest != pos {
+        events.push(GameEvent::MoveUnit(pos.0, pos.1, dest.0, dest.1));
+    }
+    // Face whoever is now closest, so as not to be caught from the side or behind.
+    let facing = enemies.iter().min_by_key(|&&e| distance(dest, e))
+        .and_then(|&enemy| Facing::towards(dest, enemy))
+        .unwrap_or(unit.get_facing());
+    events.push(GameEvent::Wait(dest.0, dest.1, facing));
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::inlevel::level::LevelData;
+
+    /// A Bishop with a tome, four plains from where Fire reaches an enemy. Moving that far is
+    /// the only way to strike, and costs one stamina more than a free move.
+    const LEVEL: &str = r#"
+        name = "Long Walk"
+        terrain = ["PPPPPPP"]
+
+        [[units]]
+        name = "Caster"
+        class = "Bishop"
+        team = 1
+        position = [0, 0]
+        weapons = ["Fire"]
+
+        [[units]]
+        name = "Target"
+        class = "Swordsman"
+        team = 0
+        position = [6, 0]
+    "#;
+
+    fn plan_with_stamina(stamina: u32) -> Vec<GameEvent> {
+        let mut scene = MidLevelScene::new(1, &LevelData::parse(LEVEL).unwrap(), 0, &[]);
+        scene.get_unit_mut((0, 0)).unwrap().set_stamina(stamina);
+        plan_unit(&scene, (0, 0))
+    }
+
+    #[test]
+    fn strikes_when_stamina_is_left_after_moving() {
+        assert_eq!(plan_with_stamina(3), vec![GameEvent::MoveUnit(0, 0, 4, 0), GameEvent::Attack(4, 0, 6, 0)]);
+    }
+
+    #[test]
+    fn does_not_plan_a_strike_moving_leaves_no_stamina_for() {
+        let plan = plan_with_stamina(2);
+        assert!(!plan.iter().any(|event| matches!(event, GameEvent::Attack(..))), "{:?}", plan);
+        assert!(matches!(plan.last(), Some(GameEvent::Wait(..))));
+    }
+}
@@ -1,5 +1,5 @@
+use serde::{Deserialize, Serialize};
 use crate::model::game::{GameError, GameEvent, UIElement, UIEvent};
-use crate::model::inlevel::unit;
 use crate::model::inlevel::unit::Unit;
 
 pub enum CellError {
@@ -7,55 +7,185 @@ pub enum CellError {
     CellNotOccupied
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Terrain {
     Plains,
     Forest,
     Mountain
 }
+
+impl Terrain {
+    ///
+    /// # Returns
+    /// * The terrain a level file character stands for, if any.
+    ///
+    pub fn from_symbol(symbol: char) -> Option<Terrain> {
+        match symbol {
+            'P' => Some(Terrain::Plains),
+            'F' => Some(Terrain::Forest),
+            'M' => Some(Terrain::Mountain),
+            _ => None
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * How many movement points it takes to enter a cell of this terrain.
+    ///
+    pub fn move_cost(&self) -> u32 {
+        match self {
+            Terrain::Plains => 1,
+            Terrain::Forest => 2,
+            Terrain::Mountain => 3,
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * How much harder, in percent, a unit standing on this terrain is to hit.
+    ///
+    pub fn avoid_bonus(&self) -> i32 {
+        match self {
+            Terrain::Plains => 0,
+            Terrain::Forest => 15,
+            Terrain::Mountain => 25,
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * Extra stamina a unit standing on this terrain gets back as its side's phase begins.
+    ///
+    pub fn stamina_bonus(&self) -> u32 {
+        match self {
+            Terrain::Plains => 0,
+            Terrain::Forest => 1,
+            Terrain::Mountain => 0,
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * How high a cell of this terrain stands when its level doesn't say otherwise.
+    ///
+    pub fn elevation(&self) -> u32 {
+        match self {
+            Terrain::Plains => 0,
+            Terrain::Forest => 0,
+            Terrain::Mountain => 1,
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * Whether units can't see past a cell of this terrain, to strike what lies beyond it.
+    ///
+    pub fn blocks_sight(&self) -> bool {
+        match self {
+            Terrain::Plains => false,
+            Terrain::Forest | Terrain::Mountain => true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct Cell {
     terrain: Terrain,
+    /// How high the cell stands. Ranged weapons reach further from higher ground.
+    #[serde(default)]
+    elevation: u32,
     unit: Option<Unit>,
+    position: (i32, i32),
     top_left: (f64, f64),
     bottom_right: (f64, f64),
 }
 
 impl Cell {
-    pub fn new(terrain: Terrain, top_left: (f64, f64), bottom_right: (f64, f64)) -> Cell {
-        Cell { terrain, unit: None, top_left, bottom_right }
+    ///
+    /// Creates a cell at `position` on the map, as high as its terrain usually stands. It
+    /// starts out drawn at the same place on screen, until the camera moves.
+    ///
+    pub fn new(terrain: Terrain, position: (i32, i32)) -> Cell {
+        let top_left = (position.0 as f64, position.1 as f64);
+        let bottom_right = (top_left.0 + 1.0, top_left.1 + 1.0);
+        Cell { terrain, elevation: terrain.elevation(), unit: None, position, top_left, bottom_right }
     }
-    
+
     pub fn has_unit(&self) -> bool {
         self.unit.is_some()
     }
-    
+
+    pub fn get_unit(&self) -> Option<&Unit> {
+        self.unit.as_ref()
+    }
+
+    pub fn get_unit_mut(&mut self) -> Option<&mut Unit> {
+        self.unit.as_mut()
+    }
+
+    ///
+    /// Removes the unit from this cell.
+    ///
+    /// # Returns
+    /// * The unit that was here, if any.
+    ///
+    pub fn take_unit(&mut self) -> Option<Unit> {
+        self.unit.take()
+    }
+
     pub fn set_unit(&mut self, unit: Unit) -> Result<(), CellError> {
-        if (self.has_unit()) {
+        if self.has_unit() {
             return Err(CellError::CellOccupied);
         }
         self.unit = Some(unit);
         Ok(())
     }
-    
+
     pub fn remove_unit(&mut self) -> Result<(), CellError> {
-        if (!self.has_unit()) {
+        if !self.has_unit() {
             return Err(CellError::CellNotOccupied);
         }
         self.unit = None;
         Ok(())
     }
-    
+
     pub fn get_terrain(&self) -> &Terrain {
         &self.terrain
     }
+
+    pub fn get_elevation(&self) -> u32 {
+        self.elevation
+    }
+
+    pub fn set_elevation(&mut self, elevation: u32) {
+        self.elevation = elevation;
+    }
+
+    ///
+    /// # Returns
+    /// * Where this cell is on the map, in grid coordinates.
+    ///
+    pub fn get_position(&self) -> (i32, i32) {
+        self.position
+    }
+
+    ///
+    /// Moves where this cell is drawn and clicked, given the map cell shown at the
+    /// top left of the screen.
+    ///
+    pub fn scroll_to(&mut self, camera: (i32, i32)) {
+        self.top_left = ((self.position.0 - camera.0) as f64, (self.position.1 - camera.1) as f64);
+        self.bottom_right = (self.top_left.0 + 1.0, self.top_left.1 + 1.0);
+    }
 }
 
 impl UIElement for Cell {
     fn get_name(&self) -> String {
-        format!("Cell at {}, {}", self.top_left.0, self.top_left.1)
+        format!("Cell at {}, {}", self.position.0, self.position.1)
     }
 
-    fn update(&self, event: UIEvent) -> Result<Option<GameEvent>, GameError> {
-        todo!()
+    fn update(&self, _event: UIEvent) -> Result<Option<GameEvent>, GameError> {
+        Err(GameError::InvalidEvent)
     }
 
     fn is_selectable(&self) -> bool {
@@ -71,6 +201,6 @@ impl UIElement for Cell {
     }
 
     fn click_event(&self) -> Option<GameEvent> {
-        todo!()
+        Some(GameEvent::SelectCell(self.position.0, self.position.1))
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,256 @@
+use rand::Rng;
+use crate::model::inlevel::cell::Terrain;
+use crate::model::inlevel::facing::Flank;
+use crate::model::inlevel::skill;
+use crate::model::inlevel::unit::{Class, Stat, Unit};
+/// How much a critical hit multiplies damage by.
+const CRIT_MULTIPLIER: u32 = 2;
+/// How much less likely, in percent, an exhausted unit is to hit.
+const EXHAUSTED_HIT_PENALTY: i32 = 20;
+/// How much more likely, in percent, an exhausted unit is to be hit.
+const EXHAUSTED_AVOID_PENALTY: i32 = 10;
+/// Experience for taking part in a fight and surviving.
+pub const XP_PER_COMBAT: u32 = 10;
+/// Extra experience for defeating the other unit.
+pub const XP_PER_KILL: u32 = 30;
+
+///
+/// What one unit attacking another is expected to do, before any dice are rolled.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Forecast {
+    pub damage: u32,
+    /// Chance to hit, in percent.
+    pub hit: u32,
+    /// Chance to land a critical hit, in percent, once the attack hits.
+    pub crit: u32,
+}
+
+///
+/// One swing in a fight, as it was rolled.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Strike {
+    /// Whether the unit that started the fight made this strike (rather than countering).
+    pub by_initiator: bool,
+    pub hit: bool,
+    pub crit: bool,
+    pub damage: u32,
+    /// Whether the striker's weapon broke with this strike.
+    pub broke: bool,
+}
+
+///
+/// Everything that happened in a fight.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CombatReport {
+    pub strikes: Vec<Strike>,
+    pub attacker_killed: bool,
+    pub defender_killed: bool,
+    pub attacker_levels: u32,
+    pub defender_levels: u32,
+}
+
+///
+/// A fight as it happened in a battle: who took part, and how it went.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fight {
+    pub attacker: Class,
+    pub attacker_team: usize,
+    pub defender: Class,
+    pub defender_team: usize,
+    pub report: CombatReport,
+}
+
+///
+/// # Returns
+/// * The damage, hit and crit chances of `attacker` striking `defender` with the weapon it
+///   fights with, counting the passive skills either has for where it stands. `None` if it
+///   has no weapon it can use.
+///
+/// # Arguments
+/// * `attacker_terrain`, `defender_terrain` - What each unit is standing on.
+/// * `flank` - Where the strike lands on the defender, given the way it is facing.
+///
+pub fn forecast(attacker: &Unit, defender: &Unit, attacker_terrain: Terrain, defender_terrain: Terrain,
+                flank: Flank) -> Option<Forecast> {
+    let weapon = attacker.get_weapon()?;
+    let damage_type = weapon.damage_type();
+    let damage = (attacker.effective_stat(damage_type.damage_stat()) + weapon.get_might())
+        .saturating_sub(defender.effective_stat(damage_type.protection_stat()));
+    let (attacker_bonus, defender_bonus) =
+        (skill::terrain_bonus(attacker, attacker_terrain), skill::terrain_bonus(defender, defender_terrain));
+    let mut hit = weapon.get_hit() as i32 + 3 * attacker.effective_stat(Stat::Precision) as i32
+        - 2 * defender.effective_stat(Stat::Agility) as i32 - defender_terrain.avoid_bonus()
+        + attacker_bonus.hit - defender_bonus.avoid + flank.hit_bonus(defender.effective_stat(Stat::Agility));
+    if attacker.is_exhausted() {
+        hit -= EXHAUSTED_HIT_PENALTY;
+    }
+    if defender.is_exhausted() {
+        hit += EXHAUSTED_AVOID_PENALTY;
+    }
+    let crit = weapon.get_crit() as i32 + 2 * attacker.effective_stat(Stat::Precision) as i32
+        - defender.effective_stat(Stat::Agility) as i32 + attacker_bonus.crit
+        + flank.crit_bonus(defender.effective_stat(Stat::Agility));
+    Some(Forecast { damage, hit: hit.clamp(0, 100) as u32, crit: crit.clamp(0, 100) as u32 })
+}
+
+///
+/// # Returns
+/// * Whether `unit` can strike something `distance` steps away with the weapon it fights
+///   with. `Unit::attack_range` decides whether it can strike at all, e.g. with the stamina
+///   it has left.
+///
+/// # Arguments
+/// * `high_ground` - How much further a ranged weapon reaches from where the unit stands.
+///   Weapons that only strike next to the unit don't gain from it. See `sight::high_ground_bonus`.
+///
+pub fn in_range(unit: &Unit, distance: i32, high_ground: i32) -> bool {
+    unit.attack_range().is_some_and(|(min_range, max_range)| {
+        let max_range = if max_range > 1 { max_range + high_ground } else { max_range };
+        (min_range..=max_range).contains(&distance)
+    })
+}
+
+fn strike<R: Rng>(striker: &mut Unit, target: &mut Unit, striker_terrain: Terrain, target_terrain: Terrain,
+                  flank: Flank, by_initiator: bool, rng: &mut R) -> Strike {
+    let odds = forecast(striker, target, striker_terrain, target_terrain, flank)
+        .unwrap_or(Forecast { damage: 0, hit: 0, crit: 0 });
+    let hit = rng.random_range(0..100) < odds.hit;
+    let crit = hit && rng.random_range(0..100) < odds.crit;
+    let damage = match (hit, crit) {
+        (false, _) => 0,
+        (true, false) => target.take_damage(odds.damage),
+        (true, true) => target.take_damage(odds.damage * CRIT_MULTIPLIER),
+    };
+    if let Some(weapon) = striker.get_weapon() {
+        striker.spend_stamina(weapon.get_kind().stamina_cost());
+    }
+    let broke = striker.wear_weapon().is_some();
+    Strike { by_initiator, hit, crit, damage, broke }
+}
+
+///
+/// Plays out `attacker` attacking `defender`. The defender strikes back if it survives, was
+/// attacked from the front, and can strike the attacker from where it stands. Survivors gain
+/// experience, and may level up.
+///
+/// # Arguments
+/// * `attacker_terrain`, `defender_terrain` - What each unit is standing on.
+/// * `defender_reaches` - Whether the defender can strike the attacker from where it stands.
+///   See `sight::can_strike`.
+/// * `flank` - Where the attack lands on the defender. The attacker faces its target, so a
+///   strike back always lands on its front.
+/// * `rng` - The battle's random number generator. Every roll comes from it.
+///
+pub fn resolve<R: Rng>(attacker: &mut Unit, defender: &mut Unit, attacker_terrain: Terrain,
+                       defender_terrain: Terrain, defender_reaches: bool, flank: Flank, rng: &mut R) -> CombatReport {
+    let mut report = CombatReport::default();
+    report.strikes.push(strike(attacker, defender, attacker_terrain, defender_terrain, flank, true, rng));
+    if defender.is_alive() && flank.allows_counter() && defender_reaches {
+        report.strikes.push(strike(defender, attacker, defender_terrain, attacker_terrain, Flank::Front, false, rng));
+    }
+    report.attacker_killed = !attacker.is_alive();
+    report.defender_killed = !defender.is_alive();
+
+    if attacker.is_alive() {
+        let xp = XP_PER_COMBAT + if report.defender_killed { XP_PER_KILL } else { 0 };
+        report.attacker_levels = attacker.gain_experience(xp, rng);
+    }
+    if defender.is_alive() {
+        let xp = XP_PER_COMBAT + if report.attacker_killed { XP_PER_KILL } else { 0 };
+        report.defender_levels = defender.gain_experience(xp, rng);
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    /// Fights one swordsman against another, from `flank`, over a spread of seeds.
+    fn fights(flank: Flank) -> Vec<(CombatReport, bool)> {
+        (0..20).map(|seed| {
+            let mut attacker = Unit::new("Attacker", Class::Swordsman, 0);
+            let mut defender = Unit::new("Defender", Class::Swordsman, 1);
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let report = resolve(&mut attacker, &mut defender, Terrain::Plains, Terrain::Plains, true, flank, &mut rng);
+            (report, defender.is_alive())
+        }).collect()
+    }
+
+    #[test]
+    fn a_surviving_defender_strikes_back_from_the_front() {
+        let fights = fights(Flank::Front);
+        assert!(fights.iter().any(|&(_, survived)| survived));
+        for (report, survived) in fights {
+            assert_eq!(report.strikes.len(), if survived { 2 } else { 1 });
+            assert!(report.strikes.iter().skip(1).all(|strike| !strike.by_initiator));
+        }
+    }
+
+    #[test]
+    fn attacks_from_the_side_or_rear_are_not_returned() {
+        for flank in [Flank::Side, Flank::Rear] {
+            for (report, _) in fights(flank) {
+                assert_eq!(report.strikes.len(), 1, "{:?}", flank);
+                assert!(report.strikes[0].by_initiator);
+            }
+        }
+    }
+
+    #[test]
+    fn an_archer_cannot_hit_what_stands_next_to_it() {
+        let archer = Unit::new("Archer", Class::Archer, 0);
+        assert!(!in_range(&archer, 1, 0));
+        assert!(in_range(&archer, 2, 0));
+        assert!(in_range(&archer, 3, 0));
+        assert!(!in_range(&archer, 4, 0));
+    }
+
+    #[test]
+    fn high_ground_lengthens_only_ranged_weapons() {
+        let archer = Unit::new("Archer", Class::Archer, 0);
+        let swordsman = Unit::new("Swordsman", Class::Swordsman, 0);
+        assert!(in_range(&archer, 4, 1));
+        assert!(!in_range(&swordsman, 2, 1));
+    }
+
+    #[test]
+    fn a_caster_out_of_stamina_is_out_of_range() {
+        let mut mage = Unit::new("Mage", Class::Mage, 0);
+        assert!(in_range(&mage, 2, 0));
+        mage.set_stamina(0);
+        assert!(!in_range(&mage, 2, 0));
+    }
+
+    #[test]
+    fn a_defender_that_cannot_reach_does_not_strike_back() {
+        for seed in 0..20 {
+            let mut attacker = Unit::new("Archer", Class::Archer, 0);
+            let mut defender = Unit::new("Defender", Class::Swordsman, 1);
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let report = resolve(&mut attacker, &mut defender, Terrain::Plains, Terrain::Plains, false, Flank::Front, &mut rng);
+            assert_eq!(report.strikes.len(), 1);
+        }
+    }
+
+    #[test]
+    fn physical_damage_goes_through_defense_and_magical_through_resistance() {
+        let swordsman = Unit::new("Swordsman", Class::Swordsman, 0);
+        let mage = Unit::new("Mage", Class::Mage, 0);
+        let target = Unit::new("Target", Class::Knight, 1);
+        let damage = |attacker: &Unit| forecast(attacker, &target, Terrain::Plains, Terrain::Plains, Flank::Front)
+            .unwrap().damage;
+        let sword = swordsman.get_weapon().unwrap().get_might();
+        let fire = mage.get_weapon().unwrap().get_might();
+        assert_eq!(damage(&swordsman), (swordsman.get_stat(Stat::Strength) + sword)
+            .saturating_sub(target.get_stat(Stat::Defense)));
+        assert_eq!(damage(&mage), (mage.get_stat(Stat::Magic) + fire)
+            .saturating_sub(target.get_stat(Stat::Resistance)));
+    }
+}
@@ -0,0 +1,65 @@
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use crate::model::inlevel::facing::Facing;
+use crate::model::inlevel::unit::Unit;
+
+///
+/// An order carried out during the current phase, holding what is needed to take it back.
+/// See `MidLevelScene::undo`.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Command {
+    /// The unit now at `to` moved there from `from`, with `stamina` and facing `facing` beforehand.
+    Move { from: (i32, i32), to: (i32, i32), stamina: u32, facing: Facing },
+    /// The unit at `from` attacked the one at `target`. Both units, and the battle's random
+    /// number generator, are kept as they were beforehand, so taking the attack back and
+    /// making it again rolls the same way.
+    Attack {
+        from: (i32, i32),
+        target: (i32, i32),
+        attacker: Box<Unit>,
+        defender: Box<Unit>,
+        rng: Box<ChaCha8Rng>,
+    },
+    /// The unit at `pos` waited. `moved` is whether it had moved beforehand, and `facing`
+    /// the way it was looking.
+    Wait { pos: (i32, i32), moved: bool, facing: Facing },
+    /// The unit at `from` used a support on the one at `target`. Both units and the random
+    /// number generator are kept as they were beforehand, as for an attack.
+    Support {
+        from: (i32, i32),
+        target: (i32, i32),
+        user: Box<Unit>,
+        ally: Box<Unit>,
+        rng: Box<ChaCha8Rng>,
+    },
+    /// A unit used an active skill. `units` holds the user and every unit in the skill's area,
+    /// by position, as they were beforehand, along with the random number generator and how
+    /// many fights the battle had recorded.
+    Skill {
+        units: Vec<((i32, i32), Unit)>,
+        rng: Box<ChaCha8Rng>,
+        #[serde(default)]
+        fights: usize,
+    },
+    /// The unit at `pos` equipped another weapon. `unit` is how it was beforehand.
+    Equip { pos: (i32, i32), unit: Box<Unit> },
+}
+
+impl Command {
+    ///
+    /// # Returns
+    /// * Whether this command is a move, the only kind that can be taken back outside casual mode.
+    ///
+    pub fn is_move(&self) -> bool {
+        matches!(self, Command::Move { .. })
+    }
+
+    ///
+    /// # Returns
+    /// * Whether this command is a change of weapon, which doesn't commit the unit to anything.
+    ///
+    pub fn is_equip(&self) -> bool {
+        matches!(self, Command::Equip { .. })
+    }
+}
@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+use crate::model::inlevel::unit::Stat;
+
+/// Resistance a barrier adds while it lasts.
+pub const BARRIER_RESISTANCE: u32 = 5;
+/// How much less far a slowed unit can move.
+pub const SLOW_MOVE_PENALTY: u32 = 2;
+
+///
+/// When in its side's phase an effect acts on a unit and wears down by one.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tick {
+    PhaseStart,
+    PhaseEnd,
+}
+
+///
+/// What happens when an effect is put on a unit that already has one like it. See
+/// `EffectKind::is_like`.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackRule {
+    /// The unit keeps one, with the longer duration and the stronger potency of the two.
+    Refresh,
+    /// The new one is added alongside, and each acts on its own.
+    Stack,
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EffectKind {
+    /// Raises Resistance by `BARRIER_RESISTANCE`.
+    Barrier,
+    /// Raises a stat by the given amount. Given by skills such as Guard.
+    Buff(Stat, u32),
+    /// Lowers a stat by the given amount, down to 0.
+    Debuff(Stat, u32),
+    /// Takes the given HP away as each of the unit's phases begins.
+    Poison(u32),
+    /// Takes the given HP away as each of the unit's phases ends. Burns stack.
+    Burn(u32),
+    /// The unit can't act in its phase.
+    Stun,
+    /// The unit moves `SLOW_MOVE_PENALTY` less far.
+    Slow,
+}
+
+impl EffectKind {
+    pub fn get_name(&self) -> String {
+        match self {
+            EffectKind::Barrier => "Barrier".to_string(),
+            EffectKind::Buff(stat, amount) => format!("{:?} +{}", stat, amount),
+            EffectKind::Debuff(stat, amount) => format!("{:?} -{}", stat, amount),
+            EffectKind::Poison(damage) => format!("Poison {}", damage),
+            EffectKind::Burn(damage) => format!("Burn {}", damage),
+            EffectKind::Stun => "Stun".to_string(),
+            EffectKind::Slow => "Slow".to_string(),
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * Whether the effect hurts the unit it is on, and so is something Cure takes away.
+    ///
+    pub fn is_harmful(&self) -> bool {
+        match self {
+            EffectKind::Barrier | EffectKind::Buff(..) => false,
+            EffectKind::Debuff(..) | EffectKind::Poison(_) | EffectKind::Burn(_)
+            | EffectKind::Stun | EffectKind::Slow => true,
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * When the effect acts and wears down. Stuns and poison act as the phase begins, and
+    ///   burns as it ends. Boons wear down as the phase begins, so they last through the
+    ///   other sides' phases, and hindrances as it ends, so they last through the unit's own.
+    ///
+    pub fn tick(&self) -> Tick {
+        match self {
+            EffectKind::Barrier | EffectKind::Buff(..) | EffectKind::Poison(_) | EffectKind::Stun => Tick::PhaseStart,
+            EffectKind::Debuff(..) | EffectKind::Burn(_) | EffectKind::Slow => Tick::PhaseEnd,
+        }
+    }
+
+    pub fn stack_rule(&self) -> StackRule {
+        match self {
+            EffectKind::Burn(_) => StackRule::Stack,
+            _ => StackRule::Refresh,
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * Whether `other` is the same effect as this one, potency aside. Buffs and debuffs
+    ///   are only alike when they change the same stat.
+    ///
+    pub fn is_like(&self, other: &EffectKind) -> bool {
+        match (self, other) {
+            (EffectKind::Buff(a, _), EffectKind::Buff(b, _))
+            | (EffectKind::Debuff(a, _), EffectKind::Debuff(b, _)) => a == b,
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * How strong the effect is, for keeping the stronger of two alike ones.
+    ///
+    pub fn potency(&self) -> u32 {
+        match *self {
+            EffectKind::Buff(_, amount) | EffectKind::Debuff(_, amount) => amount,
+            EffectKind::Poison(damage) | EffectKind::Burn(damage) => damage,
+            EffectKind::Barrier | EffectKind::Stun | EffectKind::Slow => 0,
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * How much the effect changes `stat` by while it lasts.
+    ///
+    pub fn stat_modifier(&self, stat: Stat) -> i32 {
+        match (self, stat) {
+            (EffectKind::Barrier, Stat::Resistance) => BARRIER_RESISTANCE as i32,
+            (EffectKind::Buff(changed, amount), _) if *changed == stat => *amount as i32,
+            (EffectKind::Debuff(changed, amount), _) if *changed == stat => -(*amount as i32),
+            _ => 0,
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * The HP the effect takes away each time it acts.
+    ///
+    pub fn damage(&self) -> u32 {
+        match *self {
+            EffectKind::Poison(damage) | EffectKind::Burn(damage) => damage,
+            _ => 0,
+        }
+    }
+}
+
+///
+/// Something lasting for a while on a unit. Each effect acts, and wears down, once per phase
+/// of the unit's side, as that phase begins or ends. See `EffectKind::tick`.
+///
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Effect {
+    pub kind: EffectKind,
+    /// How many more times the effect acts before wearing off.
+    pub turns: u32,
+}
@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+
+/// How much more likely, in percent, an attack from the side is to hit.
+const SIDE_HIT_BONUS: i32 = 15;
+/// How much more likely, in percent, an attack from the side is to crit.
+const SIDE_CRIT_BONUS: i32 = 5;
+/// How much more likely, in percent, an attack from behind is to hit.
+const REAR_HIT_BONUS: i32 = 30;
+/// How much more likely, in percent, an attack from behind is to crit.
+const REAR_CRIT_BONUS: i32 = 15;
+
+///
+/// Which way a unit is looking. Units turn to face where they last moved, or whom they last
+/// attacked, and can be turned when told to wait.
+///
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Facing {
+    North,
+    East,
+    #[default]
+    South,
+    West,
+}
+
+impl Facing {
+    pub const ALL: [Facing; 4] = [Facing::North, Facing::East, Facing::South, Facing::West];
+
+    pub fn get_name(&self) -> &str {
+        match self {
+            Facing::North => "North",
+            Facing::East => "East",
+            Facing::South => "South",
+            Facing::West => "West",
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * The step one cell ahead, in map coordinates, where y grows southwards.
+    ///
+    pub fn offset(&self) -> (i32, i32) {
+        match self {
+            Facing::North => (0, -1),
+            Facing::East => (1, 0),
+            Facing::South => (0, 1),
+            Facing::West => (-1, 0),
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * The way a unit at `from` would look to face `to`, along whichever axis `to` is
+    ///   further away on, or east or west if both are as far. `None` if they are the same cell.
+    ///
+    pub fn towards(from: (i32, i32), to: (i32, i32)) -> Option<Facing> {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        match (dx, dy) {
+            (0, 0) => None,
+            _ if dx.abs() >= dy.abs() => Some(if dx > 0 { Facing::East } else { Facing::West }),
+            _ => Some(if dy > 0 { Facing::South } else { Facing::North }),
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * Where an attack from `attacker` lands on a unit at `pos` looking this way. Cells as
+    ///   far ahead as they are to the side count as in front, and cells as far behind as
+    ///   they are to the side count as the side.
+    ///
+    pub fn flank(&self, pos: (i32, i32), attacker: (i32, i32)) -> Flank {
+        let (dx, dy) = (attacker.0 - pos.0, attacker.1 - pos.1);
+        let (fx, fy) = self.offset();
+        let ahead = dx * fx + dy * fy;
+        let across = (dx * fy - dy * fx).abs();
+        if ahead >= across {
+            Flank::Front
+        } else if -ahead > across {
+            Flank::Rear
+        } else {
+            Flank::Side
+        }
+    }
+}
+
+///
+/// Where an attack comes from, relative to the way the unit it is aimed at is looking.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flank {
+    Front,
+    Side,
+    Rear,
+}
+
+impl Flank {
+    pub fn get_name(&self) -> &str {
+        match self {
+            Flank::Front => "Front",
+            Flank::Side => "Side",
+            Flank::Rear => "Rear",
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * How much more likely, in percent, an attack from here is to hit. Each point of the
+    ///   defender's Agility takes 2 off, as it turns to meet the blow, down to no bonus at all.
+    ///
+    pub fn hit_bonus(&self, defender_agility: u32) -> i32 {
+        let bonus = match self {
+            Flank::Front => 0,
+            Flank::Side => SIDE_HIT_BONUS,
+            Flank::Rear => REAR_HIT_BONUS,
+        };
+        (bonus - 2 * defender_agility as i32).max(0)
+    }
+
+    ///
+    /// # Returns
+    /// * How much more likely, in percent, an attack from here is to crit. Each point of the
+    ///   defender's Agility takes 1 off, down to no bonus at all.
+    ///
+    pub fn crit_bonus(&self, defender_agility: u32) -> i32 {
+        let bonus = match self {
+            Flank::Front => 0,
+            Flank::Side => SIDE_CRIT_BONUS,
+            Flank::Rear => REAR_CRIT_BONUS,
+        };
+        (bonus - defender_agility as i32).max(0)
+    }
+
+    ///
+    /// # Returns
+    /// * Whether a unit attacked from here can strike back. Only attacks from the front can
+    ///   be returned.
+    ///
+    pub fn allows_counter(&self) -> bool {
+        *self == Flank::Front
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flank_follows_the_way_the_unit_looks() {
+        let pos = (5, 5);
+        assert_eq!(Facing::North.flank(pos, (5, 4)), Flank::Front);
+        assert_eq!(Facing::North.flank(pos, (6, 5)), Flank::Side);
+        assert_eq!(Facing::North.flank(pos, (4, 5)), Flank::Side);
+        assert_eq!(Facing::North.flank(pos, (5, 6)), Flank::Rear);
+        assert_eq!(Facing::East.flank(pos, (7, 5)), Flank::Front);
+        assert_eq!(Facing::East.flank(pos, (3, 5)), Flank::Rear);
+    }
+
+    #[test]
+    fn diagonals_count_towards_the_front_and_side() {
+        // As far ahead as to the side is the front, as far behind as to the side is the side.
+        assert_eq!(Facing::South.flank((0, 0), (2, 2)), Flank::Front);
+        assert_eq!(Facing::South.flank((0, 0), (-2, -2)), Flank::Side);
+        assert_eq!(Facing::South.flank((0, 0), (1, -3)), Flank::Rear);
+    }
+
+    #[test]
+    fn only_the_front_allows_a_counter() {
+        assert!(Flank::Front.allows_counter());
+        assert!(!Flank::Side.allows_counter());
+        assert!(!Flank::Rear.allows_counter());
+    }
+}
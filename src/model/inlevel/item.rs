@@ -0,0 +1,298 @@
+use serde::{Deserialize, Serialize};
+use crate::model::inlevel::unit::{Class, Stat};
+
+/// How many items a unit can carry.
+pub const INVENTORY_SLOTS: usize = 5;
+
+///
+/// Whether a weapon hurts by force or by magic, which decides the stats that go into its damage.
+///
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DamageType {
+    Physical,
+    Magical,
+}
+
+impl DamageType {
+    ///
+    /// # Returns
+    /// * The stat the wielder adds to the weapon's might.
+    ///
+    pub fn damage_stat(&self) -> Stat {
+        match self {
+            DamageType::Physical => Stat::Strength,
+            DamageType::Magical => Stat::Magic,
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * The stat the target takes off the damage.
+    ///
+    pub fn protection_stat(&self) -> Stat {
+        match self {
+            DamageType::Physical => Stat::Defense,
+            DamageType::Magical => Stat::Resistance,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WeaponKind {
+    Sword,
+    Bow,
+    Tome,
+    Staff,
+    Lance,
+    Dagger,
+    /// Dark magic, a harder-hitting kind of tome.
+    Dark,
+}
+
+impl WeaponKind {
+    pub fn get_name(&self) -> &str {
+        match self {
+            WeaponKind::Sword => "Sword",
+            WeaponKind::Bow => "Bow",
+            WeaponKind::Tome => "Tome",
+            WeaponKind::Staff => "Staff",
+            WeaponKind::Lance => "Lance",
+            WeaponKind::Dagger => "Dagger",
+            WeaponKind::Dark => "Dark",
+        }
+    }
+
+    pub fn damage_type(&self) -> DamageType {
+        match self {
+            WeaponKind::Sword | WeaponKind::Bow | WeaponKind::Lance | WeaponKind::Dagger => DamageType::Physical,
+            WeaponKind::Tome | WeaponKind::Staff | WeaponKind::Dark => DamageType::Magical,
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * The stamina each strike with a weapon of this kind takes. Casting from a tome tires
+    ///   the caster out.
+    ///
+    pub fn stamina_cost(&self) -> u32 {
+        match self {
+            WeaponKind::Tome | WeaponKind::Dark => 2,
+            WeaponKind::Sword | WeaponKind::Bow | WeaponKind::Staff | WeaponKind::Lance | WeaponKind::Dagger => 0,
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * Whether units of `class` can fight with weapons of this kind. Any unit can carry them.
+    ///   Advanced classes keep their base class's kind and learn another.
+    ///
+    pub fn usable_by(&self, class: Class) -> bool {
+        let classes: &[Class] = match self {
+            WeaponKind::Sword => &[Class::Swordsman, Class::Knight, Class::Duelist, Class::Ranger],
+            WeaponKind::Bow => &[Class::Archer, Class::Ranger, Class::Sniper],
+            WeaponKind::Tome => &[Class::Mage, Class::Sage, Class::Warlock, Class::Bishop],
+            WeaponKind::Staff => &[Class::Cleric, Class::Sage, Class::Bishop, Class::Oracle],
+            WeaponKind::Lance => &[Class::Knight],
+            WeaponKind::Dagger => &[Class::Duelist, Class::Sniper],
+            WeaponKind::Dark => &[Class::Warlock, Class::Oracle],
+        };
+        classes.contains(&class)
+    }
+}
+
+/// One entry of the weapon table. See `Weapon::named`.
+struct WeaponSpec {
+    name: &'static str,
+    kind: WeaponKind,
+    might: u32,
+    hit: u32,
+    crit: u32,
+    min_range: i32,
+    max_range: i32,
+    uses: u32,
+}
+
+const WEAPONS: [WeaponSpec; 15] = [
+    WeaponSpec { name: "Iron Sword", kind: WeaponKind::Sword, might: 5, hit: 70, crit: 0, min_range: 1, max_range: 1, uses: 40 },
+    WeaponSpec { name: "Steel Sword", kind: WeaponKind::Sword, might: 8, hit: 60, crit: 0, min_range: 1, max_range: 1, uses: 30 },
+    WeaponSpec { name: "Killing Edge", kind: WeaponKind::Sword, might: 6, hit: 65, crit: 25, min_range: 1, max_range: 1, uses: 20 },
+    WeaponSpec { name: "Iron Bow", kind: WeaponKind::Bow, might: 5, hit: 70, crit: 0, min_range: 2, max_range: 3, uses: 40 },
+    WeaponSpec { name: "Steel Bow", kind: WeaponKind::Bow, might: 7, hit: 60, crit: 0, min_range: 2, max_range: 3, uses: 30 },
+    WeaponSpec { name: "Longbow", kind: WeaponKind::Bow, might: 5, hit: 55, crit: 0, min_range: 2, max_range: 4, uses: 20 },
+    WeaponSpec { name: "Fire", kind: WeaponKind::Tome, might: 5, hit: 75, crit: 0, min_range: 1, max_range: 2, uses: 40 },
+    WeaponSpec { name: "Thunder", kind: WeaponKind::Tome, might: 7, hit: 65, crit: 5, min_range: 1, max_range: 2, uses: 30 },
+    WeaponSpec { name: "Oak Staff", kind: WeaponKind::Staff, might: 3, hit: 80, crit: 0, min_range: 1, max_range: 1, uses: 40 },
+    WeaponSpec { name: "Holy Staff", kind: WeaponKind::Staff, might: 5, hit: 75, crit: 5, min_range: 1, max_range: 1, uses: 25 },
+    WeaponSpec { name: "Iron Lance", kind: WeaponKind::Lance, might: 7, hit: 70, crit: 0, min_range: 1, max_range: 1, uses: 40 },
+    WeaponSpec { name: "Javelin", kind: WeaponKind::Lance, might: 5, hit: 60, crit: 0, min_range: 1, max_range: 2, uses: 20 },
+    WeaponSpec { name: "Iron Dagger", kind: WeaponKind::Dagger, might: 3, hit: 85, crit: 10, min_range: 1, max_range: 2, uses: 40 },
+    WeaponSpec { name: "Flux", kind: WeaponKind::Dark, might: 8, hit: 65, crit: 0, min_range: 1, max_range: 2, uses: 35 },
+    WeaponSpec { name: "Ruin", kind: WeaponKind::Dark, might: 6, hit: 60, crit: 20, min_range: 1, max_range: 2, uses: 25 },
+];
+
+///
+/// A weapon a unit carries. It wears down by one use with every strike made with it, hit or
+/// miss, and breaks when it has none left.
+///
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Weapon {
+    name: String,
+    kind: WeaponKind,
+    /// Damage the weapon adds to its wielder's damage stat.
+    might: u32,
+    /// Chance to hit, in percent, before stats and terrain are applied.
+    hit: u32,
+    /// Chance to land a critical hit, in percent, before stats are applied.
+    crit: u32,
+    /// The closest and furthest, in steps, the weapon can strike at.
+    min_range: i32,
+    max_range: i32,
+    uses: u32,
+    max_uses: u32,
+}
+
+impl Weapon {
+    ///
+    /// # Returns
+    /// * A new copy of the weapon called `name` in the weapon table, ignoring case, if there is one.
+    ///
+    pub fn named(name: &str) -> Option<Weapon> {
+        WEAPONS.iter().find(|spec| spec.name.eq_ignore_ascii_case(name)).map(|spec| Weapon {
+            name: spec.name.to_string(),
+            kind: spec.kind,
+            might: spec.might,
+            hit: spec.hit,
+            crit: spec.crit,
+            min_range: spec.min_range,
+            max_range: spec.max_range,
+            uses: spec.uses,
+            max_uses: spec.uses,
+        })
+    }
+
+    ///
+    /// # Returns
+    /// * The weapon units of `class` start out with. Advanced classes start with a weapon of
+    ///   the kind they learn.
+    ///
+    pub fn starting(class: Class) -> Weapon {
+        let name = match class {
+            Class::Swordsman | Class::Ranger => "Iron Sword",
+            Class::Archer => "Iron Bow",
+            Class::Mage | Class::Bishop => "Fire",
+            Class::Cleric | Class::Sage => "Oak Staff",
+            Class::Knight => "Iron Lance",
+            Class::Duelist | Class::Sniper => "Iron Dagger",
+            Class::Warlock | Class::Oracle => "Flux",
+        };
+        Weapon::named(name).unwrap()
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_kind(&self) -> WeaponKind {
+        self.kind
+    }
+
+    pub fn damage_type(&self) -> DamageType {
+        self.kind.damage_type()
+    }
+
+    pub fn get_might(&self) -> u32 {
+        self.might
+    }
+
+    pub fn get_hit(&self) -> u32 {
+        self.hit
+    }
+
+    pub fn get_crit(&self) -> u32 {
+        self.crit
+    }
+
+    pub fn get_uses(&self) -> u32 {
+        self.uses
+    }
+
+    pub fn get_max_uses(&self) -> u32 {
+        self.max_uses
+    }
+
+    ///
+    /// # Returns
+    /// * The closest and furthest, in steps, the weapon can strike at.
+    ///
+    pub fn get_range(&self) -> (i32, i32) {
+        (self.min_range, self.max_range)
+    }
+
+    pub fn is_broken(&self) -> bool {
+        self.uses == 0
+    }
+
+    ///
+    /// Uses the weapon up by one strike.
+    ///
+    pub fn wear(&mut self) {
+        self.uses = self.uses.saturating_sub(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_weapons_by_name_ignoring_case() {
+        assert_eq!(Weapon::named("steel bow").unwrap().get_name(), "Steel Bow");
+        assert!(Weapon::named("Wooden Spoon").is_none());
+    }
+
+    #[test]
+    fn gives_every_class_a_starting_weapon_it_can_use() {
+        for class in Class::ALL {
+            assert!(Weapon::starting(class).get_kind().usable_by(class), "{:?}", class);
+        }
+    }
+
+    #[test]
+    fn keeps_bows_from_striking_adjacent_cells() {
+        assert_eq!(Weapon::named("Iron Bow").unwrap().get_range(), (2, 3));
+        assert_eq!(Weapon::named("Longbow").unwrap().get_range(), (2, 4));
+        assert_eq!(Weapon::named("Fire").unwrap().get_range(), (1, 2));
+        assert_eq!(Weapon::named("Iron Sword").unwrap().get_range(), (1, 1));
+    }
+
+    #[test]
+    fn lets_advanced_classes_keep_their_base_kind_and_learn_another() {
+        assert!(WeaponKind::Sword.usable_by(Class::Knight));
+        assert!(WeaponKind::Lance.usable_by(Class::Knight));
+        assert!(!WeaponKind::Lance.usable_by(Class::Swordsman));
+        assert!(!WeaponKind::Bow.usable_by(Class::Mage));
+    }
+
+    #[test]
+    fn hurts_with_strength_or_magic_against_defense_or_resistance() {
+        assert_eq!(Weapon::named("Iron Sword").unwrap().damage_type(), DamageType::Physical);
+        assert_eq!(Weapon::named("Flux").unwrap().damage_type(), DamageType::Magical);
+        assert_eq!(DamageType::Physical.damage_stat(), Stat::Strength);
+        assert_eq!(DamageType::Physical.protection_stat(), Stat::Defense);
+        assert_eq!(DamageType::Magical.damage_stat(), Stat::Magic);
+        assert_eq!(DamageType::Magical.protection_stat(), Stat::Resistance);
+    }
+
+    #[test]
+    fn breaks_once_its_uses_run_out() {
+        let mut weapon = Weapon::named("Killing Edge").unwrap();
+        for _ in 1..weapon.get_max_uses() {
+            weapon.wear();
+        }
+        assert!(!weapon.is_broken());
+        weapon.wear();
+        assert!(weapon.is_broken());
+        weapon.wear();
+        assert_eq!(weapon.get_uses(), 0);
+    }
+}
@@ -0,0 +1,308 @@
+use std::fmt;
+use serde::Deserialize;
+use crate::assets::{self, AssetError};
+use crate::model::inlevel::cell::Terrain;
+use crate::model::inlevel::item::{Weapon, INVENTORY_SLOTS};
+use crate::model::inlevel::unit::{Class, Unit};
+use crate::model::inlevel::zone::ZoneSchedule;
+
+#[derive(Debug)]
+pub enum LevelError {
+    Asset(AssetError),
+    Parse(toml::de::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelError::Asset(e) => write!(f, "{}", e),
+            LevelError::Parse(e) => write!(f, "malformed level file: {}", e),
+            LevelError::Invalid(why) => write!(f, "invalid level: {}", why),
+        }
+    }
+}
+
+///
+/// A unit placed on the map when the level starts.
+///
+#[derive(Debug, Clone, Deserialize)]
+pub struct UnitPlacement {
+    pub name: String,
+    pub class: Class,
+    pub team: usize,
+    pub position: (i32, i32),
+    /// The names of the weapons the unit carries, first equipped. Its class's starting
+    /// weapon when left out.
+    #[serde(default)]
+    pub weapons: Vec<String>,
+}
+
+///
+/// The contents of a level file: the terrain of every cell, the starting units and, for a
+/// battle royale, how its safe zone shrinks.
+///
+#[derive(Debug, Clone, Deserialize)]
+pub struct LevelData {
+    pub name: String,
+    /// One string per row, one character per cell. See `Terrain::from_symbol`.
+    pub terrain: Vec<String>,
+    /// How high each cell stands, one digit per cell, laid out like `terrain`. When left
+    /// out, every cell stands as high as its terrain usually does. See `Terrain::elevation`.
+    #[serde(default)]
+    pub elevation: Vec<String>,
+    #[serde(default)]
+    pub units: Vec<UnitPlacement>,
+    /// Makes the level a battle royale. See `Zone`.
+    #[serde(default)]
+    pub zone: Option<ZoneSchedule>,
+}
+
+impl LevelData {
+    ///
+    /// Loads the file for the given level number from the `levels` assets.
+    ///
+    pub fn load(level: i32) -> Result<LevelData, LevelError> {
+        let contents = assets::load_string(&format!("levels/level{}.toml", level))
+            .map_err(LevelError::Asset)?;
+        Self::parse(&contents)
+    }
+
+    pub fn parse(contents: &str) -> Result<LevelData, LevelError> {
+        let data: LevelData = toml::from_str(contents).map_err(LevelError::Parse)?;
+        data.validate()?;
+        Ok(data)
+    }
+
+    fn validate(&self) -> Result<(), LevelError> {
+        let width = self.width();
+        if self.terrain.is_empty() || width == 0 {
+            return Err(LevelError::Invalid("the map has no cells".to_string()));
+        }
+        if self.terrain.iter().any(|row| row.chars().count() as i32 != width) {
+            return Err(LevelError::Invalid("every terrain row must be the same length".to_string()));
+        }
+        if let Some(row) = self.terrain.iter().find(|row| row.chars().any(|c| Terrain::from_symbol(c).is_none())) {
+            return Err(LevelError::Invalid(format!("unknown terrain symbol in row \"{}\"", row)));
+        }
+        if !self.elevation.is_empty() {
+            if self.elevation.len() != self.terrain.len()
+                || self.elevation.iter().any(|row| row.chars().count() as i32 != width) {
+                return Err(LevelError::Invalid("the elevation map must be the same size as the terrain".to_string()));
+            }
+            if let Some(row) = self.elevation.iter().find(|row| row.chars().any(|c| !c.is_ascii_digit())) {
+                return Err(LevelError::Invalid(format!("elevation row \"{}\" must be digits only", row)));
+            }
+        }
+        if self.zone.is_some_and(|zone| zone.shrink_every == 0) {
+            return Err(LevelError::Invalid("the zone must shrink every 1 turn or more".to_string()));
+        }
+        for unit in self.units.iter() {
+            let (x, y) = unit.position;
+            if x < 0 || y < 0 || x >= width || y >= self.height() {
+                return Err(LevelError::Invalid(format!("{} is placed outside the map", unit.name)));
+            }
+            if unit.weapons.len() > INVENTORY_SLOTS {
+                return Err(LevelError::Invalid(format!("{} carries more than {} weapons", unit.name, INVENTORY_SLOTS)));
+            }
+            if let Some(weapon) = unit.weapons.iter().find(|name| Weapon::named(name).is_none()) {
+                return Err(LevelError::Invalid(format!("{} carries an unknown weapon \"{}\"", unit.name, weapon)));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn width(&self) -> i32 {
+        self.terrain.first().map_or(0, |row| row.chars().count() as i32)
+    }
+
+    pub fn height(&self) -> i32 {
+        self.terrain.len() as i32
+    }
+
+    ///
+    /// # Returns
+    /// * The terrain at `(x, y)`. The level must have been validated.
+    ///
+    pub fn terrain_at(&self, x: i32, y: i32) -> Terrain {
+        let symbol = self.terrain[y as usize].chars().nth(x as usize).unwrap();
+        Terrain::from_symbol(symbol).unwrap()
+    }
+
+    ///
+    /// # Returns
+    /// * How high the cell at `(x, y)` stands. The level must have been validated.
+    ///
+    pub fn elevation_at(&self, x: i32, y: i32) -> u32 {
+        match self.elevation.get(y as usize) {
+            Some(row) => row.chars().nth(x as usize).and_then(|c| c.to_digit(10)).unwrap(),
+            None => self.terrain_at(x, y).elevation(),
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * Freshly created units for every placement, with where they start.
+    ///
+    pub fn spawn_units(&self) -> Vec<((i32, i32), Unit)> {
+        self.units.iter()
+            .map(|u| {
+                let mut unit = Unit::new(&u.name, u.class, u.team);
+                if !u.weapons.is_empty() {
+                    unit.set_inventory(u.weapons.iter().filter_map(|name| Weapon::named(name)).collect());
+                }
+                (u.position, unit)
+            })
+            .collect()
+    }
+
+    ///
+    /// Swaps the level's units for two sides made up of the given classes, keeping its map
+    /// and starting positions. The first side takes the player's starting positions as
+    /// team 0, the second takes every other team's as team 1.
+    ///
+    /// # Returns
+    /// * The level with the new units, or an error if a side has more units than the level
+    ///   has starting positions for it.
+    ///
+    pub fn with_sides(&self, sides: [&[Class]; 2]) -> Result<LevelData, LevelError> {
+        let mut units = Vec::new();
+        for (team, classes) in sides.iter().enumerate() {
+            let positions: Vec<(i32, i32)> = self.units.iter()
+                .filter(|u| (u.team == 0) == (team == 0))
+                .map(|u| u.position)
+                .collect();
+            if classes.len() > positions.len() {
+                return Err(LevelError::Invalid(format!("{} has room for only {} units on team {}",
+                                                       self.name, positions.len(), team)));
+            }
+            units.extend(classes.iter().zip(positions).enumerate().map(|(i, (&class, position))| {
+                UnitPlacement {
+                    name: format!("{} {}", class.get_name(), i + 1),
+                    class,
+                    team,
+                    position,
+                    weapons: vec![],
+                }
+            }));
+        }
+        Ok(LevelData { units, ..self.clone() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Why `contents` isn't a valid level, or an empty string if it is.
+    fn problem(contents: &str) -> String {
+        match LevelData::parse(contents) {
+            Ok(_) => String::new(),
+            Err(LevelError::Invalid(why)) => why,
+            Err(e) => panic!("expected an invalid level, got {}", e),
+        }
+    }
+
+    #[test]
+    fn accepts_a_well_formed_level() {
+        assert_eq!(problem(r#"
+            name = "Field"
+            terrain = ["PFM", "PPP"]
+
+            [[units]]
+            name = "Scout"
+            class = "Swordsman"
+            team = 0
+            position = [2, 1]
+        "#), "");
+    }
+
+    #[test]
+    fn rejects_maps_that_are_empty_or_uneven() {
+        assert_eq!(problem(r#"name = "Empty"
+            terrain = []"#), "the map has no cells");
+        assert_eq!(problem(r#"name = "Uneven"
+            terrain = ["PPP", "PP"]"#), "every terrain row must be the same length");
+    }
+
+    #[test]
+    fn rejects_unknown_terrain() {
+        assert_eq!(problem(r#"name = "Swamp"
+            terrain = ["PSP"]"#), "unknown terrain symbol in row \"PSP\"");
+    }
+
+    #[test]
+    fn rejects_units_placed_outside_the_map() {
+        assert_eq!(problem(r#"
+            name = "Field"
+            terrain = ["PPP"]
+
+            [[units]]
+            name = "Stray"
+            class = "Swordsman"
+            team = 0
+            position = [3, 0]
+        "#), "Stray is placed outside the map");
+    }
+
+    #[test]
+    fn rejects_a_zone_that_never_shrinks() {
+        assert_eq!(problem(r#"
+            name = "Arena"
+            terrain = ["PPP"]
+
+            [zone]
+            start_radius = 3
+            shrink_every = 0
+            shrink_by = 1
+            damage = 2
+        "#), "the zone must shrink every 1 turn or more");
+    }
+
+    #[test]
+    fn rejects_unknown_weapons_and_overfull_inventories() {
+        assert_eq!(problem(r#"
+            name = "Armory"
+            terrain = ["PPP"]
+
+            [[units]]
+            name = "Smith"
+            class = "Swordsman"
+            team = 0
+            position = [0, 0]
+            weapons = ["Iron Sword", "Rubber Sword"]
+        "#), "Smith carries an unknown weapon \"Rubber Sword\"");
+        assert_eq!(problem(r#"
+            name = "Armory"
+            terrain = ["PPP"]
+
+            [[units]]
+            name = "Hoarder"
+            class = "Swordsman"
+            team = 0
+            position = [0, 0]
+            weapons = ["Iron Sword", "Iron Sword", "Iron Sword", "Iron Sword", "Iron Sword", "Iron Sword"]
+        "#), format!("Hoarder carries more than {} weapons", INVENTORY_SLOTS));
+    }
+
+    #[test]
+    fn rejects_elevation_maps_that_do_not_fit_the_terrain() {
+        assert_eq!(problem(r#"name = "Hills"
+            terrain = ["PPP", "PPP"]
+            elevation = ["000"]"#), "the elevation map must be the same size as the terrain");
+        assert_eq!(problem(r#"name = "Hills"
+            terrain = ["PPP"]
+            elevation = ["0x1"]"#), "elevation row \"0x1\" must be digits only");
+    }
+
+    #[test]
+    fn elevation_defaults_to_the_terrain() {
+        let data = LevelData::parse(r#"name = "Hills"
+            terrain = ["PM"]"#).unwrap();
+        assert_eq!(data.elevation_at(1, 0), Terrain::Mountain.elevation());
+        let data = LevelData::parse(r#"name = "Hills"
+            terrain = ["PM"]
+            elevation = ["20"]"#).unwrap();
+        assert_eq!((data.elevation_at(0, 0), data.elevation_at(1, 0)), (2, 0));
+    }
+}
@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+use crate::model::game::{GameError, GameEvent, UIElement, UIEvent};
+
+///
+/// An overview of the whole battle map, drawn in a corner of the screen.
+/// Each map cell is shown as one small block; clicking a block centers the camera on it.
+///
+#[derive(Serialize, Deserialize)]
+pub struct Minimap {
+    top_left: (f64, f64),
+    bottom_right: (f64, f64),
+    map_width: i32,
+    map_height: i32,
+}
+
+impl Minimap {
+    ///
+    /// Creates a minimap for a `map_width` by `map_height` map, occupying the given
+    /// screen area (in grid units).
+    ///
+    pub fn new(top_left: (f64, f64), bottom_right: (f64, f64), map_width: i32, map_height: i32) -> Self {
+        Self { top_left, bottom_right, map_width, map_height }
+    }
+
+    ///
+    /// # Returns
+    /// * The size in pixels of the block each map cell is drawn as. Blocks are square
+    ///   so the map keeps its shape.
+    ///
+    pub fn block_size(&self) -> f64 {
+        let (x1, y1, x2, y2) = self.get_container_coords();
+        ((x2 - x1) / self.map_width as f64).min((y2 - y1) / self.map_height as f64)
+    }
+
+    ///
+    /// # Returns
+    /// * The pixel position of the top left corner of the map inside the minimap.
+    ///   The map is centered when it doesn't fill the minimap in one direction.
+    ///
+    pub fn origin(&self) -> (f64, f64) {
+        let (x1, y1, x2, y2) = self.get_container_coords();
+        let block = self.block_size();
+        (x1 + ((x2 - x1) - block * self.map_width as f64) / 2.0,
+         y1 + ((y2 - y1) - block * self.map_height as f64) / 2.0)
+    }
+
+    ///
+    /// # Returns
+    /// * The pixel rectangle `[x, y, w, h]` map cell `(x, y)` is drawn in.
+    ///
+    pub fn block_rect(&self, x: i32, y: i32) -> [f64; 4] {
+        let block = self.block_size();
+        let (ox, oy) = self.origin();
+        [ox + x as f64 * block, oy + y as f64 * block, block, block]
+    }
+
+    ///
+    /// # Returns
+    /// * The pixel rectangle covering `width` by `height` map cells starting at map cell `(x, y)`.
+    ///
+    pub fn area_rect(&self, x: i32, y: i32, width: i32, height: i32) -> [f64; 4] {
+        let block = self.block_size();
+        let [px, py, _, _] = self.block_rect(x, y);
+        [px, py, width as f64 * block, height as f64 * block]
+    }
+
+    ///
+    /// # Returns
+    /// * The map cell under the given pixel position, clamped to the map.
+    ///
+    pub fn map_cell_at(&self, mouse_x: f64, mouse_y: f64) -> (i32, i32) {
+        let block = self.block_size();
+        let (ox, oy) = self.origin();
+        let x = ((mouse_x - ox) / block).floor() as i32;
+        let y = ((mouse_y - oy) / block).floor() as i32;
+        (x.clamp(0, self.map_width - 1), y.clamp(0, self.map_height - 1))
+    }
+
+    ///
+    /// # Returns
+    /// * Whether the screen cell `(x, y)` (in grid units) lies under the minimap.
+    ///
+    pub fn covers(&self, x: i32, y: i32) -> bool {
+        let (x1, y1, x2, y2) = self.get_container();
+        (x as f64) >= x1 && (x as f64) < x2 && (y as f64) >= y1 && (y as f64) < y2
+    }
+}
+
+impl UIElement for Minimap {
+    fn get_name(&self) -> String {
+        "Minimap".to_string()
+    }
+
+    fn update(&self, _event: UIEvent) -> Result<Option<GameEvent>, GameError> {
+        Err(GameError::InvalidEvent)
+    }
+
+    fn is_selectable(&self) -> bool {
+        false
+    }
+
+    fn get_top_left(&self) -> (f64, f64) {
+        self.top_left
+    }
+
+    fn get_bottom_right(&self) -> (f64, f64) {
+        self.bottom_right
+    }
+
+    fn click_event(&self) -> Option<GameEvent> {
+        None
+    }
+
+    fn click_event_at(&self, mouse_x: f64, mouse_y: f64) -> Option<GameEvent> {
+        let (x, y) = self.map_cell_at(mouse_x, mouse_y);
+        Some(GameEvent::CenterCamera(x, y))
+    }
+}
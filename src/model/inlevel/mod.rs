@@ -1,2 +1,16 @@
+pub mod ai;
 pub mod cell;
-mod unit;
+pub mod combat;
+pub mod command;
+pub mod effect;
+pub mod facing;
+pub mod item;
+pub mod level;
+pub mod minimap;
+pub mod pathing;
+pub mod sight;
+pub mod skill;
+pub mod support;
+pub mod team;
+pub mod unit;
+pub mod zone;
@@ -0,0 +1,160 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use crate::model::inlevel::cell::Cell;
+
+const NEIGHBOURS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+///
+/// # Returns
+/// * The number of orthogonal steps between two cells.
+///
+pub fn distance(a: (i32, i32), b: (i32, i32)) -> i32 {
+    (a.0 - b.0).abs() + (a.1 - b.1).abs()
+}
+
+///
+/// # Returns
+/// * Every cell from `min` to `max` steps away from `center`, whether on the map or not.
+///
+pub fn within_range(center: (i32, i32), (min, max): (i32, i32)) -> impl Iterator<Item = (i32, i32)> {
+    (-max..=max)
+        .flat_map(move |dy| (-max..=max).map(move |dx| (center.0 + dx, center.1 + dy)))
+        .filter(move |&cell| (min..=max).contains(&distance(center, cell)))
+}
+
+///
+/// # Returns
+/// * The cell at `(x, y)`, if it is on the map.
+///
+pub fn cell_at(grid: &[Vec<Cell>], (x, y): (i32, i32)) -> Option<&Cell> {
+    if x < 0 || y < 0 {
+        return None;
+    }
+    grid.get(y as usize).and_then(|row| row.get(x as usize))
+}
+
+///
+/// Finds every cell a unit of `team` at `from` can get to with `movement` points, paying
+/// each cell's terrain cost to enter it. Units can pass through allies but not through
+/// units of other teams.
+///
+/// # Returns
+/// * Each reachable cell with the cheapest cost to get there. Occupied cells the unit can
+///   only pass through are included; see `destinations`.
+///
+pub fn reachable(grid: &[Vec<Cell>], from: (i32, i32), movement: u32, team: usize) -> HashMap<(i32, i32), u32> {
+    let mut costs: HashMap<(i32, i32), u32> = HashMap::new();
+    let mut frontier = BinaryHeap::new();
+    costs.insert(from, 0);
+    frontier.push(Reverse((0, from)));
+
+    while let Some(Reverse((cost, pos))) = frontier.pop() {
+        if costs.get(&pos).is_some_and(|&best| best < cost) {
+            continue;
+        }
+        for (dx, dy) in NEIGHBOURS {
+            let next = (pos.0 + dx, pos.1 + dy);
+            let Some(cell) = cell_at(grid, next) else { continue };
+            if cell.get_unit().is_some_and(|u| u.get_team() != team) {
+                continue;
+            }
+            let next_cost = cost + cell.get_terrain().move_cost();
+            if next_cost > movement || costs.get(&next).is_some_and(|&best| best <= next_cost) {
+                continue;
+            }
+            costs.insert(next, next_cost);
+            frontier.push(Reverse((next_cost, next)));
+        }
+    }
+    costs
+}
+
+///
+/// # Returns
+/// * The cells a unit at `from` can end its move on: every reachable cell that is empty,
+///   plus the one it is standing on.
+///
+pub fn destinations(grid: &[Vec<Cell>], from: (i32, i32), movement: u32, team: usize) -> Vec<(i32, i32)> {
+    let mut cells: Vec<(i32, i32)> = reachable(grid, from, movement, team)
+        .into_keys()
+        .filter(|&pos| pos == from || cell_at(grid, pos).is_some_and(|c| !c.has_unit()))
+        .collect();
+    cells.sort();
+    cells
+}
+
+///
+/// # Returns
+/// * The cell a unit of `team` at `from` steps onto `to` from, at the end of one of its
+///   cheapest paths there, or `None` if it can't get there or is already there. Ties go to
+///   the first neighbour in a fixed order, so the same move always ends the same way.
+///
+pub fn last_step(grid: &[Vec<Cell>], from: (i32, i32), to: (i32, i32), movement: u32, team: usize) -> Option<(i32, i32)> {
+    if from == to {
+        return None;
+    }
+    let costs = reachable(grid, from, movement, team);
+    let cost = *costs.get(&to)?;
+    let enter = cell_at(grid, to)?.get_terrain().move_cost();
+    NEIGHBOURS.iter()
+        .map(|(dx, dy)| (to.0 - dx, to.1 - dy))
+        .find(|prev| costs.get(prev).is_some_and(|&c| c + enter == cost))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::inlevel::cell::Terrain;
+    use crate::model::inlevel::unit::{Class, Unit};
+
+    /// A grid from level-file rows, e.g. `["PFP"]`, with a unit of the given team on each
+    /// of `units`' cells.
+    fn grid(rows: &[&str], units: &[((i32, i32), usize)]) -> Vec<Vec<Cell>> {
+        let mut grid: Vec<Vec<Cell>> = rows.iter().enumerate()
+            .map(|(y, row)| row.chars().enumerate()
+                .map(|(x, symbol)| Cell::new(Terrain::from_symbol(symbol).unwrap(), (x as i32, y as i32)))
+                .collect())
+            .collect();
+        for &((x, y), team) in units {
+            assert!(grid[y as usize][x as usize].set_unit(Unit::new("Unit", Class::Swordsman, team)).is_ok());
+        }
+        grid
+    }
+
+    #[test]
+    fn reachable_pays_each_cells_terrain_cost() {
+        let costs = reachable(&grid(&["PFMP"], &[]), (0, 0), 6, 0);
+        assert_eq!((costs[&(0, 0)], costs[&(1, 0)], costs[&(2, 0)], costs[&(3, 0)]), (0, 2, 5, 6));
+        assert!(!reachable(&grid(&["PFMP"], &[]), (0, 0), 5, 0).contains_key(&(3, 0)));
+    }
+
+    #[test]
+    fn enemies_block_the_way() {
+        let costs = reachable(&grid(&["PPP"], &[((1, 0), 1)]), (0, 0), 5, 0);
+        assert!(!costs.contains_key(&(1, 0)) && !costs.contains_key(&(2, 0)));
+        let costs = reachable(&grid(&["PPP", "PPP"], &[((1, 0), 1)]), (0, 0), 5, 0);
+        assert_eq!(costs.get(&(2, 0)), Some(&4));
+    }
+
+    #[test]
+    fn allies_can_be_passed_but_not_stopped_on() {
+        let grid = grid(&["PPP"], &[((1, 0), 0)]);
+        assert_eq!(reachable(&grid, (0, 0), 5, 0).get(&(2, 0)), Some(&2));
+        assert_eq!(destinations(&grid, (0, 0), 5, 0), vec![(0, 0), (2, 0)]);
+    }
+
+    #[test]
+    fn last_step_comes_from_the_cheapest_path() {
+        let open = grid(&["PPP", "PPP"], &[]);
+        assert_eq!(last_step(&open, (0, 0), (2, 0), 5, 0), Some((1, 0)));
+        let blocked = grid(&["PPP", "PPP"], &[((1, 0), 1)]);
+        assert_eq!(last_step(&blocked, (0, 0), (2, 0), 5, 0), Some((2, 1)));
+    }
+
+    #[test]
+    fn last_step_needs_somewhere_to_go() {
+        let grid = grid(&["PPP"], &[((1, 0), 1)]);
+        assert_eq!(last_step(&grid, (0, 0), (0, 0), 5, 0), None);
+        assert_eq!(last_step(&grid, (0, 0), (2, 0), 5, 0), None);
+    }
+}
@@ -0,0 +1,125 @@
+use crate::model::inlevel::cell::Cell;
+use crate::model::inlevel::combat;
+use crate::model::inlevel::pathing::{self, cell_at, distance};
+use crate::model::inlevel::unit::Unit;
+
+/// The most extra range high ground can give a ranged weapon.
+pub const MAX_HIGH_GROUND_BONUS: i32 = 2;
+
+///
+/// # Returns
+/// * The cells a straight line from `from` to `to` passes through, both ends included, as
+///   Bresenham's algorithm draws it. The line is always drawn from the lesser end, so it is
+///   the same whichever way it is asked for.
+///
+pub fn ray(from: (i32, i32), to: (i32, i32)) -> Vec<(i32, i32)> {
+    let (start, end) = if from <= to { (from, to) } else { (to, from) };
+    let (dx, dy) = ((end.0 - start.0).abs(), -(end.1 - start.1).abs());
+    let (step_x, step_y) = ((end.0 - start.0).signum(), (end.1 - start.1).signum());
+    let mut error = dx + dy;
+    let mut cell = start;
+    let mut cells = vec![cell];
+    while cell != end {
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            cell.0 += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            cell.1 += step_y;
+        }
+        cells.push(cell);
+    }
+    if start != from {
+        cells.reverse();
+    }
+    cells
+}
+
+///
+/// # Returns
+/// * Whether a unit at `from` can see `to`: no cell the line between them passes through,
+///   ends aside, has terrain that blocks sight. See `Terrain::blocks_sight`.
+///
+pub fn has_line_of_sight(grid: &[Vec<Cell>], from: (i32, i32), to: (i32, i32)) -> bool {
+    let cells = ray(from, to);
+    cells.iter().skip(1).take(cells.len().saturating_sub(2))
+        .all(|&pos| cell_at(grid, pos).is_none_or(|cell| !cell.get_terrain().blocks_sight()))
+}
+
+///
+/// # Returns
+/// * How much further a ranged weapon at `from` reaches towards `to`: one step for each level
+///   of elevation `from` is above `to`, up to `MAX_HIGH_GROUND_BONUS`.
+///
+pub fn high_ground_bonus(grid: &[Vec<Cell>], from: (i32, i32), to: (i32, i32)) -> i32 {
+    let elevation = |pos| cell_at(grid, pos).map_or(0, |cell| cell.get_elevation() as i32);
+    (elevation(from) - elevation(to)).clamp(0, MAX_HIGH_GROUND_BONUS)
+}
+
+///
+/// # Returns
+/// * Whether `unit`, standing at `from`, can strike `target` with the weapon it fights with:
+///   the weapon reaches, counting high ground, and nothing blocks the unit's sight.
+///
+pub fn can_strike(grid: &[Vec<Cell>], unit: &Unit, from: (i32, i32), target: (i32, i32)) -> bool {
+    combat::in_range(unit, distance(from, target), high_ground_bonus(grid, from, target))
+        && has_line_of_sight(grid, from, target)
+}
+
+///
+/// # Returns
+/// * Every cell on the map `unit`, standing at `from`, could strike. See `can_strike`.
+///
+pub fn strikable_cells(grid: &[Vec<Cell>], unit: &Unit, from: (i32, i32)) -> Vec<(i32, i32)> {
+    let Some((min_range, max_range)) = unit.attack_range() else { return vec![] };
+    pathing::within_range(from, (min_range, max_range + MAX_HIGH_GROUND_BONUS))
+        .filter(|&pos| cell_at(grid, pos).is_some() && can_strike(grid, unit, from, pos))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::inlevel::cell::Terrain;
+
+    /// A grid from level-file rows, e.g. `["PFP"]`.
+    fn grid(rows: &[&str]) -> Vec<Vec<Cell>> {
+        rows.iter().enumerate()
+            .map(|(y, row)| row.chars().enumerate()
+                .map(|(x, symbol)| Cell::new(Terrain::from_symbol(symbol).unwrap(), (x as i32, y as i32)))
+                .collect())
+            .collect()
+    }
+
+    #[test]
+    fn ray_is_the_same_both_ways() {
+        for &(from, to) in &[((0, 0), (5, 2)), ((1, 4), (6, 0)), ((3, 3), (3, -2)), ((-2, 1), (4, 4))] {
+            let mut back = ray(to, from);
+            back.reverse();
+            assert_eq!(ray(from, to), back, "{:?} to {:?}", from, to);
+        }
+    }
+
+    #[test]
+    fn ray_runs_from_end_to_end() {
+        assert_eq!(ray((0, 0), (3, 0)), vec![(0, 0), (1, 0), (2, 0), (3, 0)]);
+        assert_eq!(ray((2, 2), (2, 2)), vec![(2, 2)]);
+        let cells = ray((4, 3), (0, 1));
+        assert_eq!((cells[0], cells[cells.len() - 1]), ((4, 3), (0, 1)));
+    }
+
+    #[test]
+    fn forests_and_mountains_block_sight() {
+        assert!(has_line_of_sight(&grid(&["PPP"]), (0, 0), (2, 0)));
+        assert!(!has_line_of_sight(&grid(&["PFP"]), (0, 0), (2, 0)));
+        assert!(!has_line_of_sight(&grid(&["PMP"]), (2, 0), (0, 0)));
+    }
+
+    #[test]
+    fn the_ends_of_the_line_do_not_block_sight() {
+        assert!(has_line_of_sight(&grid(&["FPM"]), (0, 0), (2, 0)));
+        assert!(has_line_of_sight(&grid(&["FM"]), (0, 0), (1, 0)));
+    }
+}
@@ -0,0 +1,535 @@
+use serde::{Deserialize, Serialize};
+use crate::model::inlevel::cell::Terrain;
+use crate::model::inlevel::effect::{Effect, EffectKind};
+use crate::model::inlevel::item::DamageType;
+use crate::model::inlevel::pathing;
+use crate::model::inlevel::unit::{Class, Stat, Unit, PROMOTION_LEVEL};
+
+/// Experience for using an active skill.
+pub const XP_PER_SKILL: u32 = 15;
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Skill {
+    Guard,
+    Volley,
+    Fireball,
+    Sanctuary,
+    StunningBlow,
+    Venom,
+    Weaken,
+    Rally,
+    Flurry,
+    Deadeye,
+    Meteor,
+    Curse,
+    Blessing,
+    Foresight,
+    WoodlandArcher,
+    MountainStance,
+    Pathfinder,
+}
+
+///
+/// Which units an active skill does something to.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Targets {
+    /// Units of other teams.
+    Enemies,
+    /// Units of the user's own team, the user included.
+    Allies,
+}
+
+///
+/// The cells an active skill reaches around the cell it is aimed at.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Area {
+    /// Just that cell.
+    Single,
+    /// Every cell up to this many steps from it.
+    Burst(i32),
+}
+
+///
+/// One thing an active skill does to each unit it reaches. Skills never miss.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Primitive {
+    /// Deals `power` plus the user's damage stat for `damage_type`, less the target's
+    /// protection against it.
+    Damage { power: u32, damage_type: DamageType },
+    /// Restores `power` plus half the user's Magic in HP.
+    Heal { power: u32 },
+    /// Puts an effect of `kind` on the target for `turns`. See `Unit::add_effect`.
+    Inflict { kind: EffectKind, turns: u32 },
+}
+
+///
+/// Bonuses a passive skill gives its unit while it stands on `terrain`.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TerrainBonus {
+    pub hit: i32,
+    pub crit: i32,
+    pub avoid: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkillKind {
+    /// Used instead of attacking, for `cost` stamina, on a cell `range` steps away. A range of
+    /// (0, 0) aims it at the user's own cell.
+    Active {
+        cost: u32,
+        range: (i32, i32),
+        targets: Targets,
+        area: Area,
+        effects: &'static [Primitive],
+    },
+    /// Always on while the unit stands on `terrain`.
+    Passive { terrain: Terrain, bonus: TerrainBonus },
+}
+
+/// One entry of the skill table. See `Skill::spec`.
+pub struct SkillSpec {
+    pub skill: Skill,
+    pub name: &'static str,
+    pub class: Class,
+    /// The level a unit of `class` learns the skill at. Advanced classes also know the skills
+    /// of their base class.
+    pub level: u32,
+    pub kind: SkillKind,
+}
+
+const SKILLS: [SkillSpec; 17] = [
+    SkillSpec {
+        skill: Skill::Guard,
+        name: "Guard",
+        class: Class::Swordsman,
+        level: 1,
+        kind: SkillKind::Active {
+            cost: 2,
+            range: (0, 0),
+            targets: Targets::Allies,
+            area: Area::Single,
+            effects: &[Primitive::Inflict { kind: EffectKind::Buff(Stat::Defense, 5), turns: 1 },
+                       Primitive::Inflict { kind: EffectKind::Buff(Stat::Resistance, 3), turns: 1 }],
+        },
+    },
+    SkillSpec {
+        skill: Skill::Volley,
+        name: "Volley",
+        class: Class::Archer,
+        level: 2,
+        kind: SkillKind::Active {
+            cost: 4,
+            range: (2, 3),
+            targets: Targets::Enemies,
+            area: Area::Burst(1),
+            effects: &[Primitive::Damage { power: 2, damage_type: DamageType::Physical },
+                       Primitive::Inflict { kind: EffectKind::Slow, turns: 1 }],
+        },
+    },
+    SkillSpec {
+        skill: Skill::Fireball,
+        name: "Fireball",
+        class: Class::Mage,
+        level: 1,
+        kind: SkillKind::Active {
+            cost: 5,
+            range: (1, 2),
+            targets: Targets::Enemies,
+            area: Area::Burst(1),
+            effects: &[Primitive::Damage { power: 3, damage_type: DamageType::Magical },
+                       Primitive::Inflict { kind: EffectKind::Burn(2), turns: 2 }],
+        },
+    },
+    SkillSpec {
+        skill: Skill::Sanctuary,
+        name: "Sanctuary",
+        class: Class::Cleric,
+        level: 2,
+        kind: SkillKind::Active {
+            cost: 5,
+            range: (0, 0),
+            targets: Targets::Allies,
+            area: Area::Burst(1),
+            effects: &[Primitive::Heal { power: 3 }],
+        },
+    },
+    SkillSpec {
+        skill: Skill::StunningBlow,
+        name: "Stunning Blow",
+        class: Class::Swordsman,
+        level: 3,
+        kind: SkillKind::Active {
+            cost: 4,
+            range: (1, 1),
+            targets: Targets::Enemies,
+            area: Area::Single,
+            effects: &[Primitive::Damage { power: 2, damage_type: DamageType::Physical },
+                       Primitive::Inflict { kind: EffectKind::Stun, turns: 1 }],
+        },
+    },
+    SkillSpec {
+        skill: Skill::Venom,
+        name: "Venom",
+        class: Class::Mage,
+        level: 3,
+        kind: SkillKind::Active {
+            cost: 3,
+            range: (1, 2),
+            targets: Targets::Enemies,
+            area: Area::Single,
+            effects: &[Primitive::Inflict { kind: EffectKind::Poison(3), turns: 3 }],
+        },
+    },
+    SkillSpec {
+        skill: Skill::Weaken,
+        name: "Weaken",
+        class: Class::Cleric,
+        level: 3,
+        kind: SkillKind::Active {
+            cost: 3,
+            range: (1, 2),
+            targets: Targets::Enemies,
+            area: Area::Single,
+            effects: &[Primitive::Inflict { kind: EffectKind::Debuff(Stat::Strength, 4), turns: 2 },
+                       Primitive::Inflict { kind: EffectKind::Debuff(Stat::Magic, 4), turns: 2 }],
+        },
+    },
+    SkillSpec {
+        skill: Skill::Rally,
+        name: "Rally",
+        class: Class::Knight,
+        level: PROMOTION_LEVEL,
+        kind: SkillKind::Active {
+            cost: 4,
+            range: (0, 0),
+            targets: Targets::Allies,
+            area: Area::Burst(1),
+            effects: &[Primitive::Inflict { kind: EffectKind::Buff(Stat::Defense, 3), turns: 2 }],
+        },
+    },
+    SkillSpec {
+        skill: Skill::Flurry,
+        name: "Flurry",
+        class: Class::Duelist,
+        level: PROMOTION_LEVEL,
+        kind: SkillKind::Active {
+            cost: 4,
+            range: (1, 1),
+            targets: Targets::Enemies,
+            area: Area::Single,
+            effects: &[Primitive::Damage { power: 4, damage_type: DamageType::Physical },
+                       Primitive::Damage { power: 4, damage_type: DamageType::Physical }],
+        },
+    },
+    SkillSpec {
+        skill: Skill::Deadeye,
+        name: "Deadeye",
+        class: Class::Sniper,
+        level: PROMOTION_LEVEL,
+        kind: SkillKind::Active {
+            cost: 5,
+            range: (3, 5),
+            targets: Targets::Enemies,
+            area: Area::Single,
+            effects: &[Primitive::Damage { power: 8, damage_type: DamageType::Physical }],
+        },
+    },
+    SkillSpec {
+        skill: Skill::Meteor,
+        name: "Meteor",
+        class: Class::Sage,
+        level: PROMOTION_LEVEL,
+        kind: SkillKind::Active {
+            cost: 7,
+            range: (2, 4),
+            targets: Targets::Enemies,
+            area: Area::Burst(1),
+            effects: &[Primitive::Damage { power: 5, damage_type: DamageType::Magical }],
+        },
+    },
+    SkillSpec {
+        skill: Skill::Curse,
+        name: "Curse",
+        class: Class::Warlock,
+        level: PROMOTION_LEVEL,
+        kind: SkillKind::Active {
+            cost: 4,
+            range: (1, 3),
+            targets: Targets::Enemies,
+            area: Area::Burst(1),
+            effects: &[Primitive::Inflict { kind: EffectKind::Debuff(Stat::Defense, 3), turns: 2 },
+                       Primitive::Inflict { kind: EffectKind::Debuff(Stat::Resistance, 3), turns: 2 }],
+        },
+    },
+    SkillSpec {
+        skill: Skill::Blessing,
+        name: "Blessing",
+        class: Class::Bishop,
+        level: PROMOTION_LEVEL,
+        kind: SkillKind::Active {
+            cost: 6,
+            range: (0, 0),
+            targets: Targets::Allies,
+            area: Area::Burst(2),
+            effects: &[Primitive::Heal { power: 5 }],
+        },
+    },
+    SkillSpec {
+        skill: Skill::Foresight,
+        name: "Foresight",
+        class: Class::Oracle,
+        level: PROMOTION_LEVEL,
+        kind: SkillKind::Active {
+            cost: 3,
+            range: (0, 2),
+            targets: Targets::Allies,
+            area: Area::Single,
+            effects: &[Primitive::Inflict { kind: EffectKind::Buff(Stat::Agility, 6), turns: 2 },
+                       Primitive::Inflict { kind: EffectKind::Buff(Stat::Precision, 4), turns: 2 }],
+        },
+    },
+    SkillSpec {
+        skill: Skill::WoodlandArcher,
+        name: "Woodland Archer",
+        class: Class::Archer,
+        level: 1,
+        kind: SkillKind::Passive { terrain: Terrain::Forest, bonus: TerrainBonus { hit: 15, crit: 10, avoid: 0 } },
+    },
+    SkillSpec {
+        skill: Skill::MountainStance,
+        name: "Mountain Stance",
+        class: Class::Swordsman,
+        level: 3,
+        kind: SkillKind::Passive { terrain: Terrain::Mountain, bonus: TerrainBonus { hit: 10, crit: 0, avoid: 10 } },
+    },
+    SkillSpec {
+        skill: Skill::Pathfinder,
+        name: "Pathfinder",
+        class: Class::Ranger,
+        level: PROMOTION_LEVEL,
+        kind: SkillKind::Passive { terrain: Terrain::Forest, bonus: TerrainBonus { hit: 0, crit: 0, avoid: 20 } },
+    },
+];
+
+impl Primitive {
+    ///
+    /// # Returns
+    /// * The damage `user` would deal to `target` with this, if it deals damage.
+    ///
+    pub fn damage(&self, user: &Unit, target: &Unit) -> u32 {
+        match *self {
+            Primitive::Damage { power, damage_type } => (power + user.effective_stat(damage_type.damage_stat()))
+                .saturating_sub(target.effective_stat(damage_type.protection_stat())),
+            _ => 0,
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * The HP this would restore for `user`, before it is capped by the target's missing HP.
+    ///
+    pub fn healing(&self, user: &Unit) -> u32 {
+        match *self {
+            Primitive::Heal { power } => power + user.effective_stat(Stat::Magic) / 2,
+            _ => 0,
+        }
+    }
+
+    ///
+    /// Does this to `target`, as `user` did it.
+    ///
+    /// # Returns
+    /// * The HP lost or restored, or the turns of the effect given.
+    ///
+    pub fn apply(&self, user: &Unit, target: &mut Unit) -> u32 {
+        match *self {
+            Primitive::Damage { .. } => target.take_damage(self.damage(user, target)),
+            Primitive::Heal { .. } => target.heal(self.healing(user)),
+            Primitive::Inflict { kind, turns } => {
+                target.add_effect(Effect { kind, turns });
+                turns
+            }
+        }
+    }
+}
+
+impl Skill {
+    pub fn spec(&self) -> &'static SkillSpec {
+        SKILLS.iter().find(|spec| spec.skill == *self).unwrap()
+    }
+
+    pub fn get_name(&self) -> &str {
+        self.spec().name
+    }
+
+    ///
+    /// # Returns
+    /// * Every skill `unit` has learned, active and passive, in table order.
+    ///
+    pub fn known_by(unit: &Unit) -> Vec<Skill> {
+        let class = unit.get_class();
+        SKILLS.iter()
+            .filter(|spec| spec.class == class || spec.class == class.base_class())
+            .filter(|spec| unit.get_level() >= spec.level)
+            .map(|spec| spec.skill)
+            .collect()
+    }
+
+    pub fn is_active(&self) -> bool {
+        matches!(self.spec().kind, SkillKind::Active { .. })
+    }
+
+    ///
+    /// # Returns
+    /// * The stamina using the skill takes. Passive skills take none.
+    ///
+    pub fn cost(&self) -> u32 {
+        match self.spec().kind {
+            SkillKind::Active { cost, .. } => cost,
+            SkillKind::Passive { .. } => 0,
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * Whether `user` knows this active skill and has the stamina for it.
+    ///
+    pub fn usable_by(&self, user: &Unit) -> bool {
+        self.is_active() && Skill::known_by(user).contains(self) && user.get_stamina() >= self.cost()
+    }
+
+    ///
+    /// # Returns
+    /// * The cells `user` at `from` could aim this skill at, whether on the map or not.
+    ///
+    pub fn aim_cells(&self, from: (i32, i32)) -> Vec<(i32, i32)> {
+        match self.spec().kind {
+            SkillKind::Active { range, .. } => pathing::within_range(from, range).collect(),
+            SkillKind::Passive { .. } => vec![],
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * The cells the skill reaches when aimed at `center`, whether on the map or not.
+    ///
+    pub fn area_cells(&self, center: (i32, i32)) -> Vec<(i32, i32)> {
+        match self.spec().kind {
+            SkillKind::Active { area: Area::Burst(radius), .. } => pathing::within_range(center, (0, radius)).collect(),
+            _ => vec![center],
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * Whether the skill does anything to `target` when `user` uses it.
+    ///
+    pub fn affects(&self, user: &Unit, target: &Unit) -> bool {
+        match self.spec().kind {
+            SkillKind::Active { targets: Targets::Enemies, .. } => target.get_team() != user.get_team(),
+            SkillKind::Active { targets: Targets::Allies, .. } => target.get_team() == user.get_team(),
+            SkillKind::Passive { .. } => false,
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * The primitives the skill is made of. Passive skills have none.
+    ///
+    pub fn effects(&self) -> &'static [Primitive] {
+        match self.spec().kind {
+            SkillKind::Active { effects, .. } => effects,
+            SkillKind::Passive { .. } => &[],
+        }
+    }
+}
+
+///
+/// # Returns
+/// * What `unit`'s passive skills add while it stands on `terrain`, all together.
+///
+pub fn terrain_bonus(unit: &Unit, terrain: Terrain) -> TerrainBonus {
+    let mut total = TerrainBonus::default();
+    for skill in Skill::known_by(unit) {
+        if let SkillKind::Passive { terrain: on, bonus } = skill.spec().kind {
+            if on == terrain {
+                total.hit += bonus.hit;
+                total.crit += bonus.crit;
+                total.avoid += bonus.avoid;
+            }
+        }
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn unit_at_level(class: Class, level: u32) -> Unit {
+        let mut unit = Unit::new("Learner", class, 0);
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        while unit.get_level() < level {
+            unit.gain_experience(1, &mut rng);
+        }
+        unit
+    }
+
+    #[test]
+    fn learns_skills_at_their_level() {
+        assert_eq!(Skill::known_by(&unit_at_level(Class::Swordsman, 1)), vec![Skill::Guard]);
+        assert_eq!(Skill::known_by(&unit_at_level(Class::Swordsman, 3)),
+                   vec![Skill::Guard, Skill::StunningBlow, Skill::MountainStance]);
+        assert_eq!(Skill::known_by(&unit_at_level(Class::Archer, 1)), vec![Skill::WoodlandArcher]);
+        assert_eq!(Skill::known_by(&unit_at_level(Class::Archer, 2)), vec![Skill::Volley, Skill::WoodlandArcher]);
+    }
+
+    #[test]
+    fn advanced_classes_keep_their_base_class_skills() {
+        let mut unit = unit_at_level(Class::Swordsman, PROMOTION_LEVEL);
+        assert!(unit.promote(Class::Knight));
+        assert_eq!(Skill::known_by(&unit),
+                   vec![Skill::Guard, Skill::StunningBlow, Skill::Rally, Skill::MountainStance]);
+    }
+
+    #[test]
+    fn needs_the_stamina_to_use_an_active_skill() {
+        let mut mage = unit_at_level(Class::Mage, 1);
+        assert!(Skill::Fireball.usable_by(&mage));
+        mage.set_stamina(Skill::Fireball.cost() - 1);
+        assert!(!Skill::Fireball.usable_by(&mage));
+        assert!(!Skill::WoodlandArcher.usable_by(&unit_at_level(Class::Archer, 1)));
+    }
+
+    #[test]
+    fn passive_skills_only_help_on_their_terrain() {
+        let archer = unit_at_level(Class::Archer, 1);
+        assert_eq!(terrain_bonus(&archer, Terrain::Forest), TerrainBonus { hit: 15, crit: 10, avoid: 0 });
+        assert_eq!(terrain_bonus(&archer, Terrain::Plains), TerrainBonus::default());
+    }
+
+    #[test]
+    fn bursts_reach_every_cell_around_their_center() {
+        let mut cells = Skill::Fireball.area_cells((2, 2));
+        cells.sort();
+        assert_eq!(cells, vec![(1, 2), (2, 1), (2, 2), (2, 3), (3, 2)]);
+        assert_eq!(Skill::StunningBlow.area_cells((2, 2)), vec![(2, 2)]);
+    }
+
+    #[test]
+    fn affects_only_the_side_it_targets() {
+        let user = Unit::new("User", Class::Mage, 0);
+        let ally = Unit::new("Ally", Class::Swordsman, 0);
+        let enemy = Unit::new("Enemy", Class::Swordsman, 1);
+        assert!(Skill::Fireball.affects(&user, &enemy));
+        assert!(!Skill::Fireball.affects(&user, &ally));
+        assert!(Skill::Guard.affects(&user, &ally));
+        assert!(!Skill::Guard.affects(&user, &enemy));
+    }
+}
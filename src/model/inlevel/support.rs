@@ -0,0 +1,211 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use crate::model::inlevel::effect::{Effect, EffectKind};
+use crate::model::inlevel::unit::{Class, Stat, Unit};
+
+/// HP a heal restores before Magic is added.
+const BASE_HEAL: u32 = 5;
+/// How many of the ally's own phases a barrier lasts into.
+pub const BARRIER_TURNS: u32 = 2;
+/// Experience for helping an ally.
+pub const XP_PER_SUPPORT: u32 = 15;
+
+///
+/// Something a unit can do for an ally instead of attacking.
+///
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Support {
+    /// Restores HP, more with higher Magic.
+    Heal,
+    /// Takes harmful effects away.
+    Cure,
+    /// Raises Resistance for a while. See `EffectKind::Barrier`.
+    Barrier,
+}
+
+///
+/// What helping an ally did.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SupportReport {
+    /// HP restored, effects taken away, or turns of barrier given, depending on the support.
+    pub amount: u32,
+    /// Levels the helper gained.
+    pub levels: u32,
+}
+
+impl Support {
+    pub const ALL: [Support; 3] = [Support::Heal, Support::Cure, Support::Barrier];
+
+    pub fn get_name(&self) -> &str {
+        match self {
+            Support::Heal => "Heal",
+            Support::Cure => "Cure",
+            Support::Barrier => "Barrier",
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * The stamina using this support takes.
+    ///
+    pub fn cost(&self) -> u32 {
+        match self {
+            Support::Heal => 3,
+            Support::Cure => 2,
+            Support::Barrier => 4,
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * The closest and furthest, in steps, an ally can be helped from.
+    ///
+    pub fn range(&self) -> (i32, i32) {
+        match self {
+            Support::Heal | Support::Barrier => (1, 2),
+            Support::Cure => (1, 1),
+        }
+    }
+
+    pub fn usable_by(&self, class: Class) -> bool {
+        class.base_class() == Class::Cleric
+    }
+
+    ///
+    /// # Returns
+    /// * The supports units of `class` know.
+    ///
+    pub fn known_by(class: Class) -> Vec<Support> {
+        Support::ALL.into_iter().filter(|support| support.usable_by(class)).collect()
+    }
+
+    ///
+    /// # Returns
+    /// * The HP a heal from `user` restores, before it is capped by the ally's missing HP.
+    ///
+    pub fn heal_amount(user: &Unit) -> u32 {
+        BASE_HEAL + user.effective_stat(Stat::Magic)
+    }
+
+    ///
+    /// # Returns
+    /// * Whether `user` can use this support on `target`, `distance` steps away: it must know
+    ///   it and have the stamina for it, and `target` must be another unit of its team that
+    ///   it would do something for.
+    ///
+    pub fn can_use(&self, user: &Unit, target: &Unit, distance: i32) -> bool {
+        let (min_range, max_range) = self.range();
+        let useful = match self {
+            Support::Heal => target.get_hp() < target.get_max_hp(),
+            Support::Cure => target.get_effects().iter().any(|effect| effect.kind.is_harmful()),
+            Support::Barrier => !target.get_effects().iter().any(|effect| effect.kind == EffectKind::Barrier),
+        };
+        self.usable_by(user.get_class()) && user.get_stamina() >= self.cost()
+            && (min_range..=max_range).contains(&distance)
+            && user.get_team() == target.get_team() && useful
+    }
+
+    ///
+    /// Has `user` help `target`, spending its stamina. The helper gains experience, and may
+    /// level up. Use `can_use` first.
+    ///
+    /// # Arguments
+    /// * `rng` - The battle's random number generator, for any levels gained.
+    ///
+    pub fn apply<R: Rng>(&self, user: &mut Unit, target: &mut Unit, rng: &mut R) -> SupportReport {
+        user.spend_stamina(self.cost());
+        let amount = match self {
+            Support::Heal => target.heal(Support::heal_amount(user)),
+            Support::Cure => target.cure() as u32,
+            Support::Barrier => {
+                target.add_effect(Effect { kind: EffectKind::Barrier, turns: BARRIER_TURNS });
+                BARRIER_TURNS
+            }
+        };
+        let levels = user.gain_experience(XP_PER_SUPPORT, rng);
+        SupportReport { amount, levels }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use crate::model::inlevel::effect::BARRIER_RESISTANCE;
+
+    fn rng() -> ChaCha8Rng {
+        ChaCha8Rng::seed_from_u64(0)
+    }
+
+    fn wounded(amount: u32) -> Unit {
+        let mut ally = Unit::new("Ally", Class::Swordsman, 0);
+        ally.take_damage(amount);
+        ally
+    }
+
+    #[test]
+    fn heals_no_more_than_the_ally_is_missing() {
+        let mut cleric = Unit::new("Cleric", Class::Cleric, 0);
+        let mut ally = wounded(2);
+        let report = Support::Heal.apply(&mut cleric, &mut ally, &mut rng());
+        assert!(Support::heal_amount(&cleric) > 2);
+        assert_eq!(report.amount, 2);
+        assert_eq!(ally.get_hp(), ally.get_max_hp());
+        assert_eq!(cleric.get_stamina(), cleric.get_max_stamina() - Support::Heal.cost());
+    }
+
+    #[test]
+    fn only_heals_wounded_allies_in_range() {
+        let cleric = Unit::new("Cleric", Class::Cleric, 0);
+        assert!(Support::Heal.can_use(&cleric, &wounded(5), 2));
+        assert!(!Support::Heal.can_use(&cleric, &wounded(0), 1));
+        assert!(!Support::Heal.can_use(&cleric, &wounded(5), 3));
+        let mut enemy = wounded(5);
+        enemy.set_team(1);
+        assert!(!Support::Heal.can_use(&cleric, &enemy, 1));
+    }
+
+    #[test]
+    fn is_only_known_by_clerics_and_their_promotions() {
+        assert_eq!(Support::known_by(Class::Cleric), Support::ALL.to_vec());
+        assert_eq!(Support::known_by(Class::Bishop), Support::ALL.to_vec());
+        assert!(Support::known_by(Class::Mage).is_empty());
+        let mage = Unit::new("Mage", Class::Mage, 0);
+        assert!(!Support::Heal.can_use(&mage, &wounded(5), 1));
+    }
+
+    #[test]
+    fn needs_the_stamina_to_help() {
+        let mut cleric = Unit::new("Cleric", Class::Cleric, 0);
+        cleric.set_stamina(Support::Heal.cost() - 1);
+        assert!(!Support::Heal.can_use(&cleric, &wounded(5), 1));
+    }
+
+    #[test]
+    fn cures_harmful_effects_and_leaves_the_rest() {
+        let mut cleric = Unit::new("Cleric", Class::Cleric, 0);
+        let mut ally = wounded(0);
+        assert!(!Support::Cure.can_use(&cleric, &ally, 1));
+        ally.add_effect(Effect { kind: EffectKind::Poison(3), turns: 2 });
+        ally.add_effect(Effect { kind: EffectKind::Stun, turns: 1 });
+        ally.add_effect(Effect { kind: EffectKind::Buff(Stat::Defense, 2), turns: 1 });
+        assert!(Support::Cure.can_use(&cleric, &ally, 1));
+        assert!(!Support::Cure.can_use(&cleric, &ally, 2));
+        let report = Support::Cure.apply(&mut cleric, &mut ally, &mut rng());
+        assert_eq!(report.amount, 2);
+        assert_eq!(ally.get_effects(), &[Effect { kind: EffectKind::Buff(Stat::Defense, 2), turns: 1 }]);
+    }
+
+    #[test]
+    fn puts_up_a_barrier_that_raises_resistance_once() {
+        let mut cleric = Unit::new("Cleric", Class::Cleric, 0);
+        let mut ally = wounded(0);
+        let resistance = ally.effective_stat(Stat::Resistance);
+        let report = Support::Barrier.apply(&mut cleric, &mut ally, &mut rng());
+        assert_eq!(report.amount, BARRIER_TURNS);
+        assert_eq!(ally.effective_stat(Stat::Resistance), resistance + BARRIER_RESISTANCE);
+        assert!(!Support::Barrier.can_use(&cleric, &ally, 1));
+    }
+}
@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+
+/// Who gives a team its orders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Control {
+    Human,
+    Computer,
+    /// A person playing from another machine, whose orders arrive over the network.
+    Remote,
+}
+
+///
+/// One team taking part in a battle, and who plays it. A battle's sides take their phases
+/// in order, each turn.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Side {
+    pub team: usize,
+    pub control: Control,
+    /// What the side is called on screen, e.g. "Player 2".
+    pub name: String,
+}
+
+impl Side {
+    ///
+    /// # Returns
+    /// * Whether a person gives this side its orders, at this machine or another.
+    ///
+    pub fn is_human(&self) -> bool {
+        matches!(self.control, Control::Human | Control::Remote)
+    }
+
+    ///
+    /// # Returns
+    /// * Whether this side's orders are given at this machine.
+    ///
+    pub fn is_local(&self) -> bool {
+        self.control == Control::Human
+    }
+}
+
+///
+/// # Arguments
+/// * `teams`  - The teams in the battle, in the order they take their phases.
+/// * `player` - The team the one human player controls.
+///
+/// # Returns
+/// * The sides of a battle between one player and the computer, which runs every other team.
+///
+pub fn single_player(teams: &[usize], player: usize) -> Vec<Side> {
+    teams.iter()
+        .map(|&team| match team == player {
+            true => Side { team, control: Control::Human, name: "Player".to_string() },
+            false => Side { team, control: Control::Computer, name: "Enemy".to_string() },
+        })
+        .collect()
+}
+
+///
+/// # Returns
+/// * The sides of a battle where every team is played by a different person at the same
+///   machine, taking turns.
+///
+pub fn hot_seat(teams: &[usize]) -> Vec<Side> {
+    teams.iter()
+        .enumerate()
+        .map(|(i, &team)| Side { team, control: Control::Human, name: format!("Player {}", i + 1) })
+        .collect()
+}
+
+///
+/// # Arguments
+/// * `teams` - The teams in the battle, in the order they take their phases.
+/// * `local` - 0 on the host's machine, 1 on the guest's.
+///
+/// # Returns
+/// * The sides of a battle between two machines. The host plays the first team and the
+///   guest the second; the computer runs any others, on both machines alike.
+///
+pub fn network(teams: &[usize], local: usize) -> Vec<Side> {
+    teams.iter()
+        .enumerate()
+        .map(|(i, &team)| {
+            let control = match i {
+                _ if i == local => Control::Human,
+                0 | 1 => Control::Remote,
+                _ => Control::Computer,
+            };
+            let name = match i {
+                0 => "Host".to_string(),
+                1 => "Guest".to_string(),
+                _ => format!("Computer {}", i - 1),
+            };
+            Side { team, control, name }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gives_the_computer_every_team_but_the_players() {
+        let sides = single_player(&[0, 1, 2], 0);
+        let controls: Vec<Control> = sides.iter().map(|side| side.control).collect();
+        assert_eq!(controls, vec![Control::Human, Control::Computer, Control::Computer]);
+    }
+
+    #[test]
+    fn hot_seat_players_take_their_phases_in_team_order() {
+        let sides = hot_seat(&[0, 2, 1]);
+        let teams: Vec<usize> = sides.iter().map(|side| side.team).collect();
+        let names: Vec<&str> = sides.iter().map(|side| side.name.as_str()).collect();
+        assert_eq!(teams, vec![0, 2, 1]);
+        assert_eq!(names, vec!["Player 1", "Player 2", "Player 3"]);
+        assert!(sides.iter().all(|side| side.is_human() && side.is_local()));
+    }
+
+    #[test]
+    fn host_and_guest_agree_on_who_plays_what() {
+        let host = network(&[0, 1, 2], 0);
+        let guest = network(&[0, 1, 2], 1);
+        let controls = |sides: &[Side]| sides.iter().map(|side| side.control).collect::<Vec<_>>();
+        assert_eq!(controls(&host), vec![Control::Human, Control::Remote, Control::Computer]);
+        assert_eq!(controls(&guest), vec![Control::Remote, Control::Human, Control::Computer]);
+        for (host_side, guest_side) in host.iter().zip(&guest) {
+            assert_eq!((host_side.team, &host_side.name), (guest_side.team, &guest_side.name));
+        }
+        let names: Vec<&str> = host.iter().map(|side| side.name.as_str()).collect();
+        assert_eq!(names, vec!["Host", "Guest", "Computer 1"]);
+    }
+
+    #[test]
+    fn only_the_local_side_takes_orders_at_this_machine() {
+        let guest = network(&[0, 1], 1);
+        assert!(guest[0].is_human() && !guest[0].is_local());
+        assert!(guest[1].is_human() && guest[1].is_local());
+    }
+}
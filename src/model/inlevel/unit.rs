@@ -1,8 +1,36 @@
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use crate::model::inlevel::cell::Terrain;
+use crate::model::inlevel::effect::{Effect, EffectKind, StackRule, Tick, SLOW_MOVE_PENALTY};
+use crate::model::inlevel::facing::Facing;
+use crate::model::inlevel::item::{Weapon, INVENTORY_SLOTS};
 
 const MAX_STAT: u32 = 30;
+/// The highest any stat can go once a unit has been promoted.
+const PROMOTED_MAX_STAT: u32 = 40;
+/// The level a unit has to reach before it can be promoted.
+pub const PROMOTION_LEVEL: u32 = 4;
+/// Max HP a unit gains when it is promoted.
+const PROMOTION_HP_BONUS: u32 = 5;
+const BASE_HP: u32 = 20;
+const BASE_STAMINA: u32 = 10;
+/// Stamina every unit gets back as its side's phase begins.
+const STAMINA_PER_TURN: u32 = 1;
+/// Extra stamina for a unit that spent its last phase waiting where it stood.
+const STAMINA_FOR_RESTING: u32 = 2;
+/// Movement points a unit can spend in one move without tiring. Each point past these costs
+/// one stamina.
+const FREE_MOVE_COST: u32 = 3;
+/// How much less far an exhausted unit can move.
+const EXHAUSTED_MOVE_PENALTY: u32 = 1;
+/// Experience needed to gain a level. Leftover experience carries over.
+pub const XP_PER_LEVEL: u32 = 100;
+/// Chance, in percent, that each stat goes up by one on a level up.
+const STAT_GROWTH_CHANCE: u32 = 50;
 
-#[derive(Debug, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Stat {
     Strength,
     Magic,
@@ -14,6 +42,21 @@ pub enum Stat {
 }
 
 impl Stat {
+    pub const ALL: [Stat; 7] = [Stat::Strength, Stat::Magic, Stat::Agility, Stat::Precision,
+        Stat::Defense, Stat::Resistance, Stat::Wisdom];
+
+    pub fn get_name(&self) -> &str {
+        match self {
+            Stat::Strength => "Strength",
+            Stat::Magic => "Magic",
+            Stat::Agility => "Agility",
+            Stat::Precision => "Precision",
+            Stat::Defense => "Defense",
+            Stat::Resistance => "Resistance",
+            Stat::Wisdom => "Wisdom"
+        }
+    }
+
     pub fn get_description(&self) -> &str {
         match self {
             Stat::Strength => "Increases damage with physical weapons",
@@ -27,16 +70,149 @@ impl Stat {
     }
 }
 
-#[derive(Debug, Hash, PartialEq, Eq)]
+///
+/// What a unit is trained as. Units start in one of the four base classes, and can be promoted
+/// into one of two advanced classes of theirs. See `promotions`.
+///
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Class {
     Swordsman,
     Archer,
     Mage,
-    Cleric
+    Cleric,
+    Knight,
+    Duelist,
+    Ranger,
+    Sniper,
+    Sage,
+    Warlock,
+    Bishop,
+    Oracle,
 }
 
 impl Class {
+    pub const ALL: [Class; 12] = [Class::Swordsman, Class::Archer, Class::Mage, Class::Cleric,
+        Class::Knight, Class::Duelist, Class::Ranger, Class::Sniper,
+        Class::Sage, Class::Warlock, Class::Bishop, Class::Oracle];
+
+    pub fn get_name(&self) -> &str {
+        match self {
+            Class::Swordsman => "Swordsman",
+            Class::Archer => "Archer",
+            Class::Mage => "Mage",
+            Class::Cleric => "Cleric",
+            Class::Knight => "Knight",
+            Class::Duelist => "Duelist",
+            Class::Ranger => "Ranger",
+            Class::Sniper => "Sniper",
+            Class::Sage => "Sage",
+            Class::Warlock => "Warlock",
+            Class::Bishop => "Bishop",
+            Class::Oracle => "Oracle",
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * The base class this class is promoted from, or the class itself if it is a base class.
+    ///
+    pub fn base_class(&self) -> Class {
+        match self {
+            Class::Knight | Class::Duelist => Class::Swordsman,
+            Class::Ranger | Class::Sniper => Class::Archer,
+            Class::Sage | Class::Warlock => Class::Mage,
+            Class::Bishop | Class::Oracle => Class::Cleric,
+            base => *base,
+        }
+    }
+
+    pub fn is_promoted(&self) -> bool {
+        self.base_class() != *self
+    }
+
+    ///
+    /// # Returns
+    /// * The advanced classes units of this class can be promoted into. None for classes that
+    ///   already are advanced.
+    ///
+    pub fn promotions(&self) -> &'static [Class] {
+        match self {
+            Class::Swordsman => &[Class::Knight, Class::Duelist],
+            Class::Archer => &[Class::Ranger, Class::Sniper],
+            Class::Mage => &[Class::Sage, Class::Warlock],
+            Class::Cleric => &[Class::Bishop, Class::Oracle],
+            _ => &[],
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * What being promoted into this class adds to each stat. Nothing for base classes.
+    ///
+    pub fn promotion_bonus(&self) -> &'static [(Stat, u32)] {
+        match self {
+            Class::Knight => &[(Stat::Strength, 2), (Stat::Defense, 5), (Stat::Resistance, 2)],
+            Class::Duelist => &[(Stat::Strength, 3), (Stat::Agility, 4), (Stat::Precision, 2)],
+            Class::Ranger => &[(Stat::Strength, 2), (Stat::Agility, 3), (Stat::Defense, 2)],
+            Class::Sniper => &[(Stat::Strength, 2), (Stat::Precision, 5)],
+            Class::Sage => &[(Stat::Magic, 3), (Stat::Resistance, 3), (Stat::Wisdom, 2)],
+            Class::Warlock => &[(Stat::Magic, 5), (Stat::Precision, 2)],
+            Class::Bishop => &[(Stat::Magic, 3), (Stat::Resistance, 4)],
+            Class::Oracle => &[(Stat::Magic, 2), (Stat::Agility, 3), (Stat::Wisdom, 3)],
+            _ => &[],
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * The highest any stat of a unit of this class can go.
+    ///
+    pub fn stat_cap(&self) -> u32 {
+        if self.is_promoted() { PROMOTED_MAX_STAT } else { MAX_STAT }
+    }
+
+    ///
+    /// # Returns
+    /// * The class called `name`, ignoring case, if there is one.
+    ///
+    pub fn from_name(name: &str) -> Option<Class> {
+        Class::ALL.into_iter().find(|class| class.get_name().eq_ignore_ascii_case(name))
+    }
+
+    ///
+    /// # Returns
+    /// * How many movement points units of this class have each turn.
+    ///
+    pub fn movement(&self) -> u32 {
+        match self {
+            Class::Swordsman => 4,
+            Class::Archer => 4,
+            Class::Mage => 3,
+            Class::Cleric => 4,
+            Class::Knight => 4,
+            Class::Duelist => 5,
+            Class::Ranger => 5,
+            Class::Sniper => 4,
+            Class::Sage => 3,
+            Class::Warlock => 3,
+            Class::Bishop => 4,
+            Class::Oracle => 5,
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * The stats a new unit of this class starts with. Advanced classes start with their
+    ///   base class's stats plus their promotion bonus.
+    ///
     pub fn base_stats(&self) -> HashMap<Stat, u32> {
+        if self.is_promoted() {
+            let mut stats = self.base_class().base_stats();
+            for &(stat, bonus) in self.promotion_bonus() {
+                *stats.entry(stat).or_insert(0) += bonus;
+            }
+            return stats;
+        }
         match self {
             Class::Swordsman => [
                 (Stat::Strength, 7),
@@ -74,24 +250,657 @@ impl Class {
                 (Stat::Resistance, 4),
                 (Stat::Wisdom, 5)
             ].into_iter().collect(),
+            _ => unreachable!("advanced classes are handled above"),
         }
     }
 }
 
-
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Unit {
     name: String,
     class: Class,
+    team: usize,
     level: u32,
     hp: u32,
     max_hp: u32,
     stamina: u32,
     max_stamina: u32,
-    stat_map: HashMap<String, Stat>,
+    experience: u32,
+    stat_map: HashMap<Stat, u32>,
+    /// What the unit carries, at most `INVENTORY_SLOTS` weapons. See `get_weapon`.
+    inventory: Vec<Weapon>,
+    /// What is lasting on the unit for now. See `add_effect`.
+    #[serde(default)]
+    effects: Vec<Effect>,
+    /// Whether the unit has moved during its side's current phase.
+    moved: bool,
+    /// Whether the unit has finished acting for its side's current phase.
+    done: bool,
+    /// Whether the unit waited without moving during its side's last phase. See `recover_stamina`.
+    #[serde(default)]
+    rested: bool,
+    /// Which way the unit is looking, which decides where attacks on it come from.
+    #[serde(default)]
+    facing: Facing,
 }
 
 impl Unit {
-    pub fn new(&self, name: &str, class: Class) -> Self {
-        
+    pub fn new(name: &str, class: Class, team: usize) -> Self {
+        let stat_map = class.base_stats();
+        Self {
+            name: name.to_string(),
+            class,
+            team,
+            level: 1,
+            hp: BASE_HP,
+            max_hp: BASE_HP,
+            stamina: BASE_STAMINA,
+            max_stamina: BASE_STAMINA,
+            experience: 0,
+            stat_map,
+            inventory: vec![Weapon::starting(class)],
+            effects: Vec::new(),
+            moved: false,
+            done: false,
+            rested: false,
+            facing: Facing::default(),
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_class(&self) -> Class {
+        self.class
+    }
+
+    pub fn get_team(&self) -> usize {
+        self.team
+    }
+
+    pub fn get_level(&self) -> u32 {
+        self.level
+    }
+
+    pub fn get_hp(&self) -> u32 {
+        self.hp
+    }
+
+    pub fn get_max_hp(&self) -> u32 {
+        self.max_hp
+    }
+
+    pub fn get_stamina(&self) -> u32 {
+        self.stamina
+    }
+
+    pub fn get_max_stamina(&self) -> u32 {
+        self.max_stamina
+    }
+
+    pub fn get_experience(&self) -> u32 {
+        self.experience
+    }
+
+    pub fn get_stat(&self, stat: Stat) -> u32 {
+        self.stat_map.get(&stat).copied().unwrap_or(0)
+    }
+
+    ///
+    /// # Returns
+    /// * The unit's `stat` with whatever its effects add to or take from it, never below 0.
+    ///
+    pub fn effective_stat(&self, stat: Stat) -> u32 {
+        let modifier: i32 = self.effects.iter().map(|effect| effect.kind.stat_modifier(stat)).sum();
+        (self.get_stat(stat) as i32 + modifier).max(0) as u32
+    }
+
+    pub fn get_effects(&self) -> &[Effect] {
+        &self.effects
+    }
+
+    ///
+    /// Puts `effect` on the unit, following its kind's `StackRule` if one like it is already on.
+    ///
+    pub fn add_effect(&mut self, effect: Effect) {
+        let existing = self.effects.iter_mut().find(|e| e.kind.is_like(&effect.kind));
+        match (existing, effect.kind.stack_rule()) {
+            (Some(existing), StackRule::Refresh) => {
+                existing.turns = existing.turns.max(effect.turns);
+                if effect.kind.potency() > existing.kind.potency() {
+                    existing.kind = effect.kind;
+                }
+            }
+            _ => self.effects.push(effect),
+        }
+    }
+
+    pub fn is_stunned(&self) -> bool {
+        self.effects.iter().any(|effect| effect.kind == EffectKind::Stun)
+    }
+
+    ///
+    /// Takes every harmful effect off the unit.
+    ///
+    /// # Returns
+    /// * How many effects were taken off.
+    ///
+    pub fn cure(&mut self) -> usize {
+        let before = self.effects.len();
+        self.effects.retain(|effect| !effect.kind.is_harmful());
+        before - self.effects.len()
+    }
+
+    ///
+    /// Has the effects that act as the unit's side's phase begins do so: poison hurts it, and
+    /// a stun leaves it done for the phase. Those effects then wear down.
+    ///
+    /// # Returns
+    /// * The HP the unit lost.
+    ///
+    pub fn start_phase(&mut self) -> u32 {
+        if self.is_stunned() {
+            self.done = true;
+        }
+        self.tick_effects(Tick::PhaseStart)
+    }
+
+    ///
+    /// Has the effects that act as the unit's side's phase ends do so, and wear them down.
+    ///
+    /// # Returns
+    /// * The HP the unit lost.
+    ///
+    pub fn end_phase(&mut self) -> u32 {
+        self.tick_effects(Tick::PhaseEnd)
+    }
+
+    ///
+    /// Hurts the unit with every effect that acts at `tick`, wears them down by one and drops
+    /// the ones that run out. Effects never take the unit's last HP.
+    ///
+    fn tick_effects(&mut self, tick: Tick) -> u32 {
+        let damage: u32 = self.effects.iter().filter(|e| e.kind.tick() == tick).map(|e| e.kind.damage()).sum();
+        let lost = self.take_damage(damage.min(self.hp.saturating_sub(1)));
+        for effect in self.effects.iter_mut().filter(|e| e.kind.tick() == tick) {
+            effect.turns = effect.turns.saturating_sub(1);
+        }
+        self.effects.retain(|effect| effect.turns > 0);
+        lost
+    }
+
+    ///
+    /// Takes `amount` off the unit's stamina.
+    ///
+    /// # Returns
+    /// * Whether it had that much to spend. It spends nothing if it didn't.
+    ///
+    pub fn spend_stamina(&mut self, amount: u32) -> bool {
+        if self.stamina < amount {
+            return false;
+        }
+        self.stamina -= amount;
+        true
+    }
+
+    ///
+    /// Tires the unit out after a move that cost `move_cost` movement points. Moves past
+    /// `FREE_MOVE_COST` take stamina, down to none.
+    ///
+    pub fn tire_from_move(&mut self, move_cost: u32) {
+        self.stamina = self.stamina.saturating_sub(move_cost.saturating_sub(FREE_MOVE_COST));
+    }
+
+    ///
+    /// # Returns
+    /// * Whether the unit has no stamina left. Exhausted units move less far, hit less often
+    ///   and are easier to hit.
+    ///
+    pub fn is_exhausted(&self) -> bool {
+        self.stamina == 0
+    }
+
+    ///
+    /// Gives the unit back some stamina as its side's phase begins: `STAMINA_PER_TURN`, more
+    /// if it rested last phase, and more again for good ground.
+    ///
+    /// # Arguments
+    /// * `terrain` - What the unit is standing on.
+    ///
+    pub fn recover_stamina(&mut self, terrain: Terrain) {
+        let mut gain = STAMINA_PER_TURN + terrain.stamina_bonus();
+        if self.rested {
+            gain += STAMINA_FOR_RESTING;
+        }
+        self.stamina = (self.stamina + gain).min(self.max_stamina);
+        self.rested = false;
+    }
+
+    ///
+    /// Sets the unit's stamina, e.g. when a move is taken back. Capped at the maximum.
+    ///
+    pub fn set_stamina(&mut self, stamina: u32) {
+        self.stamina = stamina.min(self.max_stamina);
+    }
+
+    pub fn set_rested(&mut self, rested: bool) {
+        self.rested = rested;
+    }
+
+    pub fn get_inventory(&self) -> &[Weapon] {
+        &self.inventory
+    }
+
+    ///
+    /// Replaces everything the unit carries, keeping at most the first `INVENTORY_SLOTS` weapons.
+    ///
+    pub fn set_inventory(&mut self, mut inventory: Vec<Weapon>) {
+        inventory.truncate(INVENTORY_SLOTS);
+        self.inventory = inventory;
+    }
+
+    ///
+    /// # Returns
+    /// * The weapon the unit fights with: the first one in its inventory its class can use.
+    ///
+    pub fn get_weapon(&self) -> Option<&Weapon> {
+        self.inventory.iter().find(|weapon| weapon.get_kind().usable_by(self.class))
     }
-}
\ No newline at end of file
+
+    ///
+    /// # Returns
+    /// * The closest and furthest the unit can attack at, or `None` if it has no weapon it can
+    ///   use or not the stamina to strike with it.
+    ///
+    pub fn attack_range(&self) -> Option<(i32, i32)> {
+        self.get_weapon()
+            .filter(|weapon| self.stamina >= weapon.get_kind().stamina_cost())
+            .map(Weapon::get_range)
+    }
+
+    ///
+    /// # Returns
+    /// * How many movement points the unit has this phase, fewer while it is exhausted or
+    ///   slowed.
+    ///
+    pub fn movement(&self) -> u32 {
+        let mut movement = self.class.movement();
+        if self.is_exhausted() {
+            movement = movement.saturating_sub(EXHAUSTED_MOVE_PENALTY);
+        }
+        if self.effects.iter().any(|effect| effect.kind == EffectKind::Slow) {
+            movement = movement.saturating_sub(SLOW_MOVE_PENALTY);
+        }
+        movement
+    }
+
+    ///
+    /// Adds `weapon` to the end of the inventory.
+    ///
+    /// # Returns
+    /// * The weapon back, if the inventory is full.
+    ///
+    pub fn give(&mut self, weapon: Weapon) -> Result<(), Weapon> {
+        if self.inventory.len() >= INVENTORY_SLOTS {
+            return Err(weapon);
+        }
+        self.inventory.push(weapon);
+        Ok(())
+    }
+
+    ///
+    /// Moves the weapon in inventory slot `slot` to the top, so the unit fights with it.
+    ///
+    /// # Returns
+    /// * Whether there is such a weapon and the unit's class can use it.
+    ///
+    pub fn equip(&mut self, slot: usize) -> bool {
+        if !self.inventory.get(slot).is_some_and(|weapon| weapon.get_kind().usable_by(self.class)) {
+            return false;
+        }
+        let weapon = self.inventory.remove(slot);
+        self.inventory.insert(0, weapon);
+        true
+    }
+
+    ///
+    /// Wears down the weapon the unit fights with by one use, and throws it away if that
+    /// breaks it.
+    ///
+    /// # Returns
+    /// * The weapon, if it broke.
+    ///
+    pub fn wear_weapon(&mut self) -> Option<Weapon> {
+        let slot = self.inventory.iter().position(|weapon| weapon.get_kind().usable_by(self.class))?;
+        self.inventory[slot].wear();
+        if self.inventory[slot].is_broken() {
+            Some(self.inventory.remove(slot))
+        } else {
+            None
+        }
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.hp > 0
+    }
+
+    ///
+    /// Lowers HP by `amount`, stopping at 0.
+    ///
+    /// # Returns
+    /// * The HP actually lost.
+    ///
+    pub fn take_damage(&mut self, amount: u32) -> u32 {
+        let lost = amount.min(self.hp);
+        self.hp -= lost;
+        lost
+    }
+
+    ///
+    /// Raises HP by `amount`, stopping at the maximum.
+    ///
+    /// # Returns
+    /// * The HP actually restored.
+    ///
+    pub fn heal(&mut self, amount: u32) -> u32 {
+        let gained = amount.min(self.max_hp - self.hp);
+        self.hp += gained;
+        gained
+    }
+
+    ///
+    /// Adds experience, scaled up by Wisdom, and levels up for every `XP_PER_LEVEL` reached.
+    ///
+    /// # Returns
+    /// * How many levels were gained.
+    ///
+    pub fn gain_experience<R: Rng>(&mut self, amount: u32, rng: &mut R) -> u32 {
+        self.experience += amount * (100 + 5 * self.effective_stat(Stat::Wisdom)) / 100;
+        let mut levels = 0;
+        while self.experience >= XP_PER_LEVEL {
+            self.experience -= XP_PER_LEVEL;
+            self.level_up(rng);
+            levels += 1;
+        }
+        levels
+    }
+
+    fn level_up<R: Rng>(&mut self, rng: &mut R) {
+        self.level += 1;
+        let hp_gain = rng.random_range(1..=3);
+        self.max_hp += hp_gain;
+        self.hp += hp_gain;
+        for stat in Stat::ALL {
+            if rng.random_range(0..100) < STAT_GROWTH_CHANCE {
+                let value = self.stat_map.entry(stat).or_insert(0);
+                *value = (*value + 1).min(self.class.stat_cap());
+            }
+        }
+    }
+
+    pub fn has_moved(&self) -> bool {
+        self.moved
+    }
+
+    pub fn set_moved(&mut self, moved: bool) {
+        self.moved = moved;
+    }
+
+    pub fn get_facing(&self) -> Facing {
+        self.facing
+    }
+
+    pub fn set_facing(&mut self, facing: Facing) {
+        self.facing = facing;
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    pub fn set_done(&mut self, done: bool) {
+        self.done = done;
+    }
+
+    ///
+    /// Readies the unit for a new phase of its side.
+    ///
+    pub fn refresh(&mut self) {
+        self.moved = false;
+        self.done = false;
+    }
+
+    ///
+    /// Restores HP and stamina and clears per-battle state, ready for the next battle. A unit
+    /// left with no weapon it can use is handed its class's starting one, if it has room.
+    ///
+    pub fn restore(&mut self) {
+        self.hp = self.max_hp;
+        self.stamina = self.max_stamina;
+        self.effects.clear();
+        if self.get_weapon().is_none() {
+            let _ = self.give(Weapon::starting(self.class));
+        }
+        self.refresh();
+    }
+
+    ///
+    /// # Returns
+    /// * Whether the unit has reached `PROMOTION_LEVEL` in a class that can be promoted.
+    ///
+    pub fn can_promote(&self) -> bool {
+        self.level >= PROMOTION_LEVEL && !self.class.promotions().is_empty()
+    }
+
+    ///
+    /// # Returns
+    /// * Whether the unit can be promoted into `class`: it is one of its class's promotions,
+    ///   and the unit has reached `PROMOTION_LEVEL`.
+    ///
+    pub fn can_promote_to(&self, class: Class) -> bool {
+        self.can_promote() && self.class.promotions().contains(&class)
+    }
+
+    ///
+    /// Promotes the unit into `class`, raising its stats and max HP. It is handed the starting
+    /// weapon of `class` too, if it has room, so it can use the new kind of weapon straight away.
+    ///
+    /// # Returns
+    /// * Whether it was promoted. See `can_promote_to`.
+    ///
+    pub fn promote(&mut self, class: Class) -> bool {
+        if !self.can_promote_to(class) {
+            return false;
+        }
+        self.class = class;
+        for &(stat, bonus) in class.promotion_bonus() {
+            let value = self.stat_map.entry(stat).or_insert(0);
+            *value = (*value + bonus).min(class.stat_cap());
+        }
+        self.max_hp += PROMOTION_HP_BONUS;
+        self.hp += PROMOTION_HP_BONUS;
+        let _ = self.give(Weapon::starting(class));
+        true
+    }
+
+    ///
+    /// Moves the unit over to another team, e.g. when deploying a campaign roster.
+    ///
+    pub fn set_team(&mut self, team: usize) {
+        self.team = team;
+    }
+}
+
+// Stats are hashed in `Stat::ALL` order, since the stat map's own order differs from run to run.
+impl Hash for Unit {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (&self.name, self.class, self.team, self.level).hash(state);
+        (self.hp, self.max_hp, self.stamina, self.max_stamina, self.experience).hash(state);
+        (&self.inventory, &self.effects, self.moved, self.done, self.rested, self.facing).hash(state);
+        for stat in Stat::ALL {
+            self.stat_map.get(&stat).hash(state);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn effect(kind: EffectKind, turns: u32) -> Effect {
+        Effect { kind, turns }
+    }
+
+    #[test]
+    fn refreshing_keeps_the_longer_duration_and_stronger_potency() {
+        let mut unit = Unit::new("Target", Class::Swordsman, 0);
+        unit.add_effect(effect(EffectKind::Poison(2), 3));
+        unit.add_effect(effect(EffectKind::Poison(4), 1));
+        assert_eq!(unit.get_effects(), &[effect(EffectKind::Poison(4), 3)]);
+    }
+
+    #[test]
+    fn buffs_only_refresh_buffs_to_the_same_stat() {
+        let mut unit = Unit::new("Target", Class::Swordsman, 0);
+        unit.add_effect(effect(EffectKind::Buff(Stat::Defense, 2), 2));
+        unit.add_effect(effect(EffectKind::Buff(Stat::Strength, 2), 2));
+        unit.add_effect(effect(EffectKind::Buff(Stat::Defense, 1), 3));
+        assert_eq!(unit.get_effects(), &[effect(EffectKind::Buff(Stat::Defense, 2), 3),
+                                         effect(EffectKind::Buff(Stat::Strength, 2), 2)]);
+    }
+
+    #[test]
+    fn burns_stack() {
+        let mut unit = Unit::new("Target", Class::Swordsman, 0);
+        unit.add_effect(effect(EffectKind::Burn(2), 2));
+        unit.add_effect(effect(EffectKind::Burn(3), 1));
+        assert_eq!(unit.get_effects().len(), 2);
+        assert_eq!(unit.end_phase(), 5);
+        assert_eq!(unit.get_effects(), &[effect(EffectKind::Burn(2), 1)]);
+    }
+
+    #[test]
+    fn effects_wear_down_at_their_own_tick() {
+        let mut unit = Unit::new("Target", Class::Swordsman, 0);
+        unit.add_effect(effect(EffectKind::Poison(1), 1));
+        unit.add_effect(effect(EffectKind::Burn(1), 1));
+        assert_eq!(unit.start_phase(), 1);
+        assert_eq!(unit.get_effects(), &[effect(EffectKind::Burn(1), 1)]);
+        assert_eq!(unit.end_phase(), 1);
+        assert!(unit.get_effects().is_empty());
+    }
+
+    #[test]
+    fn effects_never_take_the_last_hp() {
+        let mut unit = Unit::new("Target", Class::Swordsman, 0);
+        unit.take_damage(BASE_HP - 3);
+        unit.add_effect(effect(EffectKind::Poison(5), 3));
+        assert_eq!(unit.start_phase(), 2);
+        assert_eq!(unit.start_phase(), 0);
+        assert_eq!(unit.get_hp(), 1);
+        assert!(unit.is_alive());
+    }
+
+    #[test]
+    fn long_moves_tire_a_unit_out() {
+        let mut unit = Unit::new("Walker", Class::Swordsman, 0);
+        unit.tire_from_move(FREE_MOVE_COST);
+        assert_eq!(unit.get_stamina(), BASE_STAMINA);
+        unit.tire_from_move(FREE_MOVE_COST + 2);
+        assert_eq!(unit.get_stamina(), BASE_STAMINA - 2);
+    }
+
+    #[test]
+    fn spends_stamina_only_when_it_has_enough() {
+        let mut unit = Unit::new("Caster", Class::Mage, 0);
+        unit.set_stamina(3);
+        assert!(!unit.spend_stamina(4));
+        assert_eq!(unit.get_stamina(), 3);
+        assert!(unit.spend_stamina(3));
+        assert!(unit.is_exhausted());
+    }
+
+    #[test]
+    fn recovers_more_after_resting_and_on_good_ground() {
+        let mut unit = Unit::new("Rester", Class::Swordsman, 0);
+        unit.set_stamina(0);
+        unit.recover_stamina(Terrain::Plains);
+        assert_eq!(unit.get_stamina(), STAMINA_PER_TURN);
+        unit.set_rested(true);
+        unit.recover_stamina(Terrain::Forest);
+        assert_eq!(unit.get_stamina(), 2 * STAMINA_PER_TURN + STAMINA_FOR_RESTING + Terrain::Forest.stamina_bonus());
+        let before = unit.get_stamina();
+        unit.recover_stamina(Terrain::Plains);
+        assert_eq!(unit.get_stamina(), before + STAMINA_PER_TURN, "resting only counts for one phase");
+    }
+
+    #[test]
+    fn never_recovers_past_the_maximum() {
+        let mut unit = Unit::new("Rester", Class::Swordsman, 0);
+        unit.set_rested(true);
+        unit.recover_stamina(Terrain::Forest);
+        assert_eq!(unit.get_stamina(), unit.get_max_stamina());
+    }
+
+    #[test]
+    fn an_exhausted_unit_moves_less_far() {
+        let mut unit = Unit::new("Walker", Class::Swordsman, 0);
+        assert_eq!(unit.movement(), Class::Swordsman.movement());
+        unit.set_stamina(0);
+        assert_eq!(unit.movement(), Class::Swordsman.movement() - EXHAUSTED_MOVE_PENALTY);
+    }
+
+    fn ready_to_promote(class: Class) -> Unit {
+        let mut unit = Unit::new("Veteran", class, 0);
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        while unit.get_level() < PROMOTION_LEVEL {
+            unit.gain_experience(XP_PER_LEVEL / 4, &mut rng);
+        }
+        unit
+    }
+
+    #[test]
+    fn only_promotes_at_the_promotion_level_into_its_own_promotions() {
+        let mut rookie = Unit::new("Rookie", Class::Swordsman, 0);
+        assert!(!rookie.can_promote());
+        assert!(!rookie.promote(Class::Knight));
+        let mut veteran = ready_to_promote(Class::Swordsman);
+        assert!(veteran.can_promote());
+        assert!(!veteran.promote(Class::Sniper));
+        assert!(veteran.promote(Class::Knight));
+        assert!(!veteran.can_promote());
+    }
+
+    #[test]
+    fn promotion_raises_stats_and_hp_and_hands_over_a_weapon() {
+        let mut unit = ready_to_promote(Class::Swordsman);
+        let before = unit.clone();
+        unit.promote(Class::Knight);
+        assert_eq!(unit.get_class(), Class::Knight);
+        for &(stat, bonus) in Class::Knight.promotion_bonus() {
+            assert_eq!(unit.get_stat(stat), before.get_stat(stat) + bonus, "{:?}", stat);
+        }
+        assert_eq!(unit.get_stat(Stat::Magic), before.get_stat(Stat::Magic));
+        assert_eq!(unit.get_max_hp(), before.get_max_hp() + PROMOTION_HP_BONUS);
+        assert_eq!(unit.get_hp(), before.get_hp() + PROMOTION_HP_BONUS);
+        assert!(unit.get_inventory().iter().any(|weapon| weapon.get_name() == "Iron Lance"));
+    }
+
+    #[test]
+    fn promotion_raises_the_stat_cap() {
+        let mut unit = ready_to_promote(Class::Swordsman);
+        unit.stat_map.insert(Stat::Defense, MAX_STAT);
+        unit.promote(Class::Knight);
+        assert_eq!(unit.get_stat(Stat::Defense), MAX_STAT + 5);
+
+        let mut capped = ready_to_promote(Class::Swordsman);
+        capped.stat_map.insert(Stat::Defense, PROMOTED_MAX_STAT);
+        capped.promote(Class::Knight);
+        assert_eq!(capped.get_stat(Stat::Defense), PROMOTED_MAX_STAT);
+        assert_eq!(Class::Swordsman.stat_cap(), MAX_STAT);
+        assert_eq!(Class::Knight.stat_cap(), PROMOTED_MAX_STAT);
+    }
+}
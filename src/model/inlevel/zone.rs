@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+
+///
+/// How a battle royale's safe zone shrinks, as given by the `[zone]` table of a level file.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ZoneSchedule {
+    /// The cell the zone closes in on. The middle of the map when left out.
+    #[serde(default)]
+    pub center: Option<(i32, i32)>,
+    /// The zone's radius, in cells, when the battle starts.
+    pub start_radius: u32,
+    /// Turns between contractions.
+    pub shrink_every: u32,
+    /// Cells the radius loses at each contraction.
+    pub shrink_by: u32,
+    /// The radius the zone stops shrinking at.
+    #[serde(default)]
+    pub min_radius: u32,
+    /// Damage dealt, at the start of each turn, to every unit outside the zone before it has
+    /// contracted at all.
+    pub damage: u32,
+    /// How much that damage goes up with each contraction.
+    #[serde(default)]
+    pub damage_growth: u32,
+}
+
+///
+/// The safe zone of a battle royale: a circle that shrinks every few turns. Units caught
+/// outside it take damage at the start of every turn. Everything about it follows from the
+/// turn number, so every copy of a battle agrees on it.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Zone {
+    center: (i32, i32),
+    schedule: ZoneSchedule,
+}
+
+impl Zone {
+    ///
+    /// # Arguments
+    /// * `map_size` - The width and height of the map, used to find its middle when the
+    ///   schedule doesn't give a center.
+    ///
+    pub fn new(schedule: ZoneSchedule, map_size: (i32, i32)) -> Self {
+        let center = schedule.center.unwrap_or((map_size.0 / 2, map_size.1 / 2));
+        Self { center, schedule }
+    }
+
+    pub fn get_center(&self) -> (i32, i32) {
+        self.center
+    }
+
+    ///
+    /// # Returns
+    /// * How many times the zone has contracted by the start of `turn`.
+    ///
+    pub fn contractions(&self, turn: u32) -> u32 {
+        turn.saturating_sub(1) / self.schedule.shrink_every.max(1)
+    }
+
+    pub fn radius(&self, turn: u32) -> u32 {
+        let shrunk = self.schedule.shrink_by.saturating_mul(self.contractions(turn));
+        self.schedule.start_radius.saturating_sub(shrunk).max(self.schedule.min_radius)
+    }
+
+    ///
+    /// # Returns
+    /// * The damage dealt to each unit outside the zone at the start of `turn`.
+    ///
+    pub fn damage(&self, turn: u32) -> u32 {
+        self.schedule.damage + self.schedule.damage_growth * self.contractions(turn)
+    }
+
+    ///
+    /// # Returns
+    /// * Whether the cell at `pos` is inside the zone during `turn`.
+    ///
+    pub fn contains(&self, pos: (i32, i32), turn: u32) -> bool {
+        let (dx, dy) = ((pos.0 - self.center.0) as i64, (pos.1 - self.center.1) as i64);
+        let radius = self.radius(turn) as i64;
+        dx * dx + dy * dy <= radius * radius
+    }
+
+    ///
+    /// # Returns
+    /// * How many turns are left before the zone next contracts, or `None` once it has
+    ///   stopped shrinking.
+    ///
+    pub fn turns_until_shrink(&self, turn: u32) -> Option<u32> {
+        if self.radius(turn) == self.radius(u32::MAX) {
+            return None;
+        }
+        let every = self.schedule.shrink_every.max(1);
+        Some(every - turn.saturating_sub(1) % every)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A zone in the middle of a 10 by 11 map that loses 2 cells of radius every 3 turns,
+    /// from 5 down to 1.
+    fn zone() -> Zone {
+        let schedule = ZoneSchedule {
+            center: None,
+            start_radius: 5,
+            shrink_every: 3,
+            shrink_by: 2,
+            min_radius: 1,
+            damage: 2,
+            damage_growth: 1,
+        };
+        Zone::new(schedule, (10, 11))
+    }
+
+    #[test]
+    fn centers_on_the_middle_of_the_map_by_default() {
+        assert_eq!(zone().get_center(), (5, 5));
+    }
+
+    #[test]
+    fn shrinks_on_schedule_down_to_its_minimum() {
+        let radii: Vec<u32> = (1..=10).map(|turn| zone().radius(turn)).collect();
+        assert_eq!(radii, vec![5, 5, 5, 3, 3, 3, 1, 1, 1, 1]);
+        assert_eq!((zone().damage(1), zone().damage(4), zone().damage(7)), (2, 3, 4));
+    }
+
+    #[test]
+    fn contains_cells_within_the_radius() {
+        let zone = zone();
+        assert!(zone.contains((10, 5), 1));
+        assert!(!zone.contains((9, 9), 1));
+        assert!(zone.contains((8, 5), 4));
+        assert!(!zone.contains((9, 5), 4));
+    }
+
+    #[test]
+    fn counts_down_to_the_next_contraction() {
+        let zone = zone();
+        assert_eq!(zone.turns_until_shrink(1), Some(3));
+        assert_eq!(zone.turns_until_shrink(3), Some(1));
+        assert_eq!(zone.turns_until_shrink(4), Some(3));
+        assert_eq!(zone.turns_until_shrink(7), None);
+    }
+}
@@ -0,0 +1,74 @@
+use std::any::Any;
+use crate::model::button::Button;
+use crate::model::game::{GameError, GameEvent, Scene, UIElement};
+use crate::{HALF_X, HALF_Y};
+
+///
+/// A screen with a message and one button, shown between a hot seat battle's phases so the
+/// next person can take the device without seeing the map early, and after it to name the
+/// winner.
+///
+pub struct InterstitialScene {
+    name: String,
+    activated: bool,
+    message: String,
+    /// The team the message is about, shown in its color.
+    team: Option<usize>,
+    button: Button,
+}
+
+impl InterstitialScene {
+    pub fn new(message: &str, team: Option<usize>, button_label: &str, on_click: GameEvent) -> Self {
+        let button = Button::new(button_label, (HALF_X as f64, (HALF_Y + 2) as f64),
+                                 ((HALF_X + 2) as f64, (HALF_Y + 3) as f64), on_click);
+        Self { name: "Interstitial".to_string(), activated: false, message: message.to_string(), team, button }
+    }
+
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn get_team(&self) -> Option<usize> {
+        self.team
+    }
+
+    pub fn get_button(&self) -> &Button {
+        &self.button
+    }
+}
+
+impl Scene for InterstitialScene {
+    fn get_name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    fn activate(&mut self) -> Result<(), GameError> {
+        if self.activated {
+            return Err(GameError::SceneActivationError);
+        }
+        self.activated = true;
+        Ok(())
+    }
+
+    fn deactivate(&mut self) -> Result<(), GameError> {
+        if !self.activated {
+            return Err(GameError::SceneActivationError);
+        }
+        self.activated = false;
+        Ok(())
+    }
+
+    fn receive_event(&mut self, _event: &GameEvent) {}
+
+    fn get_ui_elements(&self) -> Vec<&dyn UIElement> {
+        vec![&self.button]
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
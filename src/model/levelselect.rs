@@ -0,0 +1,145 @@
+use std::any::Any;
+use crate::model::button::Button;
+use crate::model::campaign::Campaign;
+use crate::model::game::GameEvent::SelectLevel;
+use crate::model::game::{GameError, GameEvent, Scene, UIElement, ROYALE_LEVEL};
+use crate::{WIDTH, HEIGHT};
+
+pub struct LevelSelectScene {
+    name: String,
+    activated: bool,
+    level_buttons: Vec<Button>,
+}
+
+impl LevelSelectScene {
+    ///
+    /// Lays out one button per level. Levels the campaign hasn't unlocked yet are disabled,
+    /// and cleared ones show their best score.
+    ///
+    pub fn new(num_levels: i32, campaign: &Campaign) -> Self {
+        Self::with_buttons("Level Selection", num_levels, |level| {
+            let label = match campaign.get_record(level) {
+                Some(record) => format!("Level {} - Best {}", level, record.score),
+                None if !campaign.is_unlocked(level) => format!("Level {} (Locked)", level),
+                None => format!("Level {}", level),
+            };
+            (label, SelectLevel(level), campaign.is_unlocked(level))
+        })
+    }
+
+    ///
+    /// Lays out one button per level for a hot seat battle, and one for the battle royale.
+    /// Every level can be played.
+    ///
+    pub fn hot_seat(num_levels: i32) -> Self {
+        Self::with_buttons("Hot Seat", num_levels + 1, |level| match level > num_levels {
+            true => ("Battle Royale - Hot Seat".to_string(), GameEvent::StartHotSeat(ROYALE_LEVEL), true),
+            false => (format!("Level {} - Hot Seat", level), GameEvent::StartHotSeat(level), true),
+        })
+    }
+
+    ///
+    /// # Arguments
+    /// * `button` - Gives the label, event and whether it is enabled, for a level's button.
+    ///
+    fn with_buttons(name: &str, num_levels: i32, button: impl Fn(i32) -> (String, GameEvent, bool)) -> Self {
+        if num_levels < 1 {
+            panic!("Need at least one level");
+        }
+
+        // GRID-BASED MARGINS 
+        // Side margins = 25% of WIDTH
+        let side_margin: i32 = ((WIDTH as f64) * 0.25).round() as i32;
+        // Top & bottom margins = 20% of HEIGHT
+        let vert_margin: i32 = ((HEIGHT as f64) * 0.20).round() as i32;
+
+        // How many grid-rows remain for buttons + gaps?
+        let usable_rows = HEIGHT - 2 * vert_margin;
+        // How many grid-cols for each button?
+        let button_cols = WIDTH - 2 * side_margin;
+
+        // ─── VERTICAL GAPS ───
+        // Reserve 25% of usable_rows for gaps
+        let total_gap_rows: i32 =
+            ((usable_rows as f64) * 0.25).round() as i32;
+        // Distribute those rows into (num_levels - 1) gaps, rounding up so each gap is >=1 when possible
+        let gap_rows = if num_levels > 1 {
+            (total_gap_rows + (num_levels - 2)) / (num_levels - 1)
+        } else {
+            0
+        };
+
+        // ─── BUTTON HEIGHT ───
+        // Remaining rows divided evenly among buttons
+        let button_rows: i32 = ((usable_rows - gap_rows * (num_levels - 1)) as f64
+            / num_levels as f64)
+            .round() as i32;
+
+        // ─── BUILD THE BUTTONS ──
+        let mut level_buttons = Vec::with_capacity(num_levels as usize);
+        let mut current_y = vert_margin;
+
+        for i in 0..num_levels {
+            let top_left     = (side_margin      as f64, current_y       as f64);
+            let bottom_right = ( (side_margin + button_cols) as f64,
+                                 (current_y   + button_rows)   as f64 );
+
+            let (label, event, enabled) = button(i + 1);
+            let mut button = Button::new(&label, top_left, bottom_right, event);
+            button.set_enabled(enabled);
+            level_buttons.push(button);
+
+            // advance past this button + one gap
+            current_y += button_rows + gap_rows;
+        }
+
+        Self {
+            name: name.to_string(),
+            level_buttons,
+            activated: false,
+        }
+    }
+
+    pub fn get_buttons(&self) -> &Vec<Button> {
+        &self.level_buttons
+    }
+}
+
+impl Scene for LevelSelectScene {
+    fn get_name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    fn activate(&mut self) -> Result<(), GameError> {
+        if self.activated {
+            return Err(GameError::SceneActivationError);
+        }
+        self.activated = true;
+        Ok(())
+    }
+
+    fn deactivate(&mut self) -> Result<(), GameError> {
+        if !self.activated {
+            return Err(GameError::SceneActivationError);
+        }
+        self.activated = false;
+        Ok(())
+    }
+
+    fn receive_event(&mut self, _event: &GameEvent) {}
+
+    fn get_ui_elements(&self) -> Vec<&dyn UIElement> {
+        self.level_buttons
+            .iter()
+            .map(|btn| btn as &dyn UIElement)
+            .collect()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
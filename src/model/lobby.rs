@@ -0,0 +1,108 @@
+use std::any::Any;
+use std::net::SocketAddr;
+use crate::HALF_Y;
+use crate::model::button::{button_column, Button};
+use crate::model::game::{GameError, GameEvent, Scene, UIElement, ROYALE_LEVEL};
+use crate::model::net::Role;
+use crate::settings;
+
+///
+/// How far setting up a network battle has got, as shown in the lobby.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LobbyState {
+    /// Nothing started yet. Holds why the last attempt failed, if one did.
+    Idle(Option<String>),
+    Hosting(u16),
+    Joining(SocketAddr),
+    /// Both machines are connected, and the host picks the level.
+    Ready(Role),
+}
+
+///
+/// Where battles between people are set up. A hot seat battle is started from here, or a
+/// battle against another machine: one player hosts, the other joins, then the host picks
+/// the level. The port and the address joined come from the settings.
+///
+pub struct LobbyScene {
+    name: String,
+    activated: bool,
+    message: String,
+    buttons: Vec<Button>,
+}
+
+impl LobbyScene {
+    pub fn new(state: &LobbyState, num_levels: i32) -> Self {
+        let leave = ("Leave".to_string(), GameEvent::LeaveNetwork, true);
+        let (message, entries) = match state {
+            LobbyState::Idle(failure) => {
+                let settings = settings::current();
+                let join = match settings.join_address.parse::<SocketAddr>() {
+                    Ok(address) => (format!("Join {}", address), GameEvent::JoinGame(address), true),
+                    Err(_) => (format!("Can't join {}", settings.join_address), GameEvent::OpenLobby, false),
+                };
+                let message = failure.clone().unwrap_or_else(|| "Play at this machine, or over the network".to_string());
+                (message, vec![("Hot Seat".to_string(), GameEvent::OpenHotSeat, true),
+                               (format!("Host on port {}", settings.host_port), GameEvent::HostGame(settings.host_port), true),
+                               join, ("Back".to_string(), GameEvent::LeaveNetwork, true)])
+            }
+            LobbyState::Hosting(port) => (format!("Waiting for a guest to join on port {}", port), vec![leave]),
+            LobbyState::Joining(address) => (format!("Connecting to {}", address), vec![leave]),
+            LobbyState::Ready(Role::Host) => {
+                let mut entries: Vec<_> = (1..=num_levels)
+                    .map(|level| (format!("Level {}", level), GameEvent::StartNetworkBattle(level), true))
+                    .collect();
+                entries.push(("Battle Royale".to_string(), GameEvent::StartNetworkBattle(ROYALE_LEVEL), true));
+                entries.push(leave);
+                ("A guest has joined. Pick a level".to_string(), entries)
+            }
+            LobbyState::Ready(Role::Guest) => ("Connected. Waiting for the host to pick a level".to_string(), vec![leave]),
+        };
+        let top = HALF_Y + 2 - entries.len() as i32;
+        Self { name: "Lobby".to_string(), activated: false, message, buttons: button_column(top, entries) }
+    }
+
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn get_buttons(&self) -> &[Button] {
+        &self.buttons
+    }
+}
+
+impl Scene for LobbyScene {
+    fn get_name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    fn activate(&mut self) -> Result<(), GameError> {
+        if self.activated {
+            return Err(GameError::SceneActivationError);
+        }
+        self.activated = true;
+        Ok(())
+    }
+
+    fn deactivate(&mut self) -> Result<(), GameError> {
+        if !self.activated {
+            return Err(GameError::SceneActivationError);
+        }
+        self.activated = false;
+        Ok(())
+    }
+
+    fn receive_event(&mut self, _event: &GameEvent) {}
+
+    fn get_ui_elements(&self) -> Vec<&dyn UIElement> {
+        self.buttons.iter().map(|button| button as &dyn UIElement).collect()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -0,0 +1,1514 @@
+use std::any::Any;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use crate::model::button::Button;
+use crate::model::dialog::Dialog;
+use crate::model::game::{GameError, GameEvent, Scene, Targeting, UIElement};
+use crate::model::hash::Fnv1aHasher;
+use crate::model::inlevel::ai;
+use crate::model::inlevel::cell::Cell;
+use crate::model::inlevel::combat::{self, CombatReport, Fight, Strike};
+use crate::model::inlevel::command::Command;
+use crate::model::inlevel::facing::Facing;
+use crate::model::inlevel::level::{LevelData, LevelError};
+use crate::model::inlevel::minimap::Minimap;
+use crate::model::inlevel::pathing::{self, distance};
+use crate::model::inlevel::sight;
+use crate::model::inlevel::skill::{self, Primitive, Skill};
+use crate::model::inlevel::support::{Support, SupportReport};
+use crate::model::inlevel::team::{self, Control, Side};
+use crate::model::inlevel::unit::Unit;
+use crate::model::inlevel::zone::Zone;
+use crate::model::replay::Replay;
+use crate::model::save::{self, QUICKSAVE_PATH};
+use crate::settings;
+use crate::{WIDTH, HEIGHT};
+
+/// Width and height of the minimap, in grid cells.
+const MINIMAP_SIZE: (i32, i32) = (4, 3);
+/// Width of the selected unit's action menu, in grid cells. It sits against the right edge
+/// of the screen, just under the heads-up text.
+const ACTION_MENU_WIDTH: i32 = 2;
+
+/// The team the player controls in a campaign battle. Every other team is run by the computer.
+pub const PLAYER_TEAM: usize = 0;
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BattleOutcome {
+    Victory,
+    Defeat,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MidLevelScene {
+    name: String,
+    level: i32,
+    /// Indexed as `grid[y][x]`.
+    grid: Vec<Vec<Cell>>,
+    /// Starts at 1 and goes up each time the first side's phase comes back around.
+    turn: u32,
+    /// Every team in the battle and who plays it, in the order they take their phases.
+    sides: Vec<Side>,
+    /// Index into `sides` of the side whose phase it is.
+    phase: usize,
+    #[serde(skip)]
+    activated: bool,
+    /// The map cell shown at the top left of the screen.
+    camera: (i32, i32),
+    minimap: Minimap,
+    /// The seed the battle's random number generator started from.
+    seed: u64,
+    /// Source of every random outcome in the battle.
+    rng: ChaCha8Rng,
+    /// The player's unit waiting for orders, if any.
+    selected: Option<(i32, i32)>,
+    /// Set once the battle is decided, from the campaign player's point of view.
+    outcome: Option<BattleOutcome>,
+    /// The last team standing, once the battle is decided, if any team is.
+    winner: Option<usize>,
+    /// Set when a human's phase has ended and another human's is about to start, until
+    /// they say they're ready. See `needs_handoff`.
+    #[serde(default)]
+    handoff: bool,
+    /// Whether the squares enemies could attack next phase are shaded.
+    #[serde(default)]
+    show_threat_map: bool,
+    /// The safe zone, if the battle is a battle royale.
+    #[serde(default)]
+    zone: Option<Zone>,
+    /// Asks the player to confirm ending their phase, when that setting is on.
+    #[serde(skip)]
+    dialog: Option<Dialog>,
+    /// The support or skill the selected unit is about to use, while the player picks where.
+    #[serde(skip)]
+    targeting: Option<Targeting>,
+    /// What the selected unit can do, as buttons. See `update_action_menu`.
+    #[serde(skip)]
+    action_menu: Vec<Button>,
+    /// The roster the battle was deployed from, kept for its replay.
+    #[serde(default)]
+    roster: Vec<Unit>,
+    /// Every order the player has carried out, in order. See `to_replay`.
+    #[serde(default)]
+    log: Vec<GameEvent>,
+    /// The player's orders this phase, most recent last, so they can be taken back.
+    #[serde(default)]
+    history: Vec<Command>,
+    /// Whether the battle is played in casual mode, where any order can be taken back.
+    /// Fixed when the battle starts, so a replay plays out the same whatever the settings.
+    #[serde(default)]
+    casual: bool,
+    /// Every fight since the battle was built or loaded, in order.
+    #[serde(skip)]
+    fights: Vec<Fight>,
+    /// Whether fights go unreported on the console, e.g. while simulating many battles.
+    #[serde(skip)]
+    quiet: bool,
+}
+
+///
+/// Turns every unit on `grid` to face the closest unit of another team, as it stands when the
+/// battle begins. Ties go to the first such unit in reading order.
+///
+fn face_nearest_enemies(grid: &mut [Vec<Cell>]) {
+    let units: Vec<((i32, i32), usize)> = grid.iter().flatten()
+        .filter_map(|cell| cell.get_unit().map(|unit| (cell.get_position(), unit.get_team())))
+        .collect();
+    for cell in grid.iter_mut().flatten() {
+        let pos = cell.get_position();
+        let Some(unit) = cell.get_unit_mut() else { continue };
+        let nearest = units.iter()
+            .filter(|&&(_, team)| team != unit.get_team())
+            .min_by_key(|&&(other, _)| distance(pos, other));
+        if let Some(facing) = nearest.and_then(|&(other, _)| Facing::towards(pos, other)) {
+            unit.set_facing(facing);
+        }
+    }
+}
+
+impl MidLevelScene {
+    ///
+    /// Builds the battle for a level from its level file.
+    ///
+    /// # Arguments
+    /// * `roster` - The player's units to deploy, in order, onto the level's player starting
+    ///   positions. When empty, the level's own player units are used.
+    ///
+    pub fn new(level: i32, data: &LevelData, seed: u64, roster: &[Unit]) -> Self {
+        let (width, height) = (data.width(), data.height());
+        let mut grid: Vec<Vec<Cell>> = (0..height)
+            .map(|y| (0..width).map(|x| {
+                let mut cell = Cell::new(data.terrain_at(x, y), (x, y));
+                cell.set_elevation(data.elevation_at(x, y));
+                cell
+            }).collect())
+            .collect();
+        let mut deploying = roster.iter();
+        for ((x, y), unit) in data.spawn_units() {
+            let unit = if !roster.is_empty() && unit.get_team() == PLAYER_TEAM {
+                let Some(deployed) = deploying.next() else { continue };
+                let mut deployed = deployed.clone();
+                deployed.restore();
+                deployed.set_team(PLAYER_TEAM);
+                deployed
+            } else {
+                unit
+            };
+            if grid[y as usize][x as usize].set_unit(unit).is_err() {
+                println!("Two units placed at {}, {}; keeping the first", x, y);
+            }
+        }
+        let mut teams: Vec<usize> = grid.iter().flatten()
+            .filter_map(|cell| cell.get_unit())
+            .map(|unit| unit.get_team())
+            .collect();
+        teams.sort();
+        teams.dedup();
+        face_nearest_enemies(&mut grid);
+        let minimap = Minimap::new(
+            ((WIDTH - MINIMAP_SIZE.0) as f64, (HEIGHT - MINIMAP_SIZE.1) as f64),
+            (WIDTH as f64, HEIGHT as f64),
+            width, height);
+        Self {
+            name: "Mid-Level".to_string(),
+            level,
+            grid,
+            turn: 1,
+            sides: team::single_player(&teams, PLAYER_TEAM),
+            phase: 0,
+            activated: false,
+            camera: (0, 0),
+            minimap,
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            selected: None,
+            outcome: None,
+            winner: None,
+            handoff: false,
+            show_threat_map: settings::current().show_threat_map,
+            zone: data.zone.map(|schedule| Zone::new(schedule, (width, height))),
+            dialog: None,
+            targeting: None,
+            action_menu: Vec::new(),
+            roster: roster.to_vec(),
+            log: Vec::new(),
+            history: Vec::new(),
+            casual: settings::current().casual_mode,
+            fights: Vec::new(),
+            quiet: false,
+        }
+    }
+
+    ///
+    /// Loads the given level's file and builds its battle with a random seed, deploying
+    /// `roster` as in `new`.
+    ///
+    pub fn load(level: i32, roster: &[Unit]) -> Result<Self, LevelError> {
+        Ok(Self::new(level, &LevelData::load(level)?, rand::rng().random(), roster))
+    }
+
+    pub fn get_level(&self) -> i32 {
+        self.level
+    }
+
+    pub fn get_turn(&self) -> u32 {
+        self.turn
+    }
+
+    pub fn get_sides(&self) -> &[Side] {
+        &self.sides
+    }
+
+    ///
+    /// Changes who plays each team, e.g. to have several people share the machine. Must be
+    /// called before anyone acts; sides take their phases in the order given.
+    ///
+    pub fn set_sides(&mut self, sides: Vec<Side>) {
+        self.sides = sides;
+        self.phase = 0;
+    }
+
+    ///
+    /// # Returns
+    /// * Whether more than one person is playing this battle.
+    ///
+    pub fn is_hot_seat(&self) -> bool {
+        self.sides.iter().filter(|side| side.is_local()).count() > 1
+    }
+
+    ///
+    /// # Returns
+    /// * Whether someone on another machine is playing this battle.
+    ///
+    pub fn is_network_battle(&self) -> bool {
+        self.sides.iter().any(|side| side.control == Control::Remote)
+    }
+
+    ///
+    /// # Returns
+    /// * The side whose phase it is.
+    ///
+    pub fn get_active_side(&self) -> &Side {
+        &self.sides[self.phase]
+    }
+
+    ///
+    /// # Returns
+    /// * The index, into `get_sides`, of the side whose phase it is.
+    ///
+    pub fn get_phase(&self) -> usize {
+        self.phase
+    }
+
+    pub fn get_active_team(&self) -> usize {
+        self.get_active_side().team
+    }
+
+    ///
+    /// # Returns
+    /// * Whether a person, rather than the computer, is giving orders this phase.
+    ///
+    pub fn is_human_phase(&self) -> bool {
+        self.get_active_side().is_human()
+    }
+
+    ///
+    /// # Returns
+    /// * Whether whoever is giving orders this phase is at this machine.
+    ///
+    pub fn is_local_phase(&self) -> bool {
+        self.get_active_side().is_local()
+    }
+
+    ///
+    /// # Returns
+    /// * Whether the device should be passed to the next player before the map is shown again.
+    ///
+    pub fn needs_handoff(&self) -> bool {
+        self.handoff
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn get_selected(&self) -> Option<(i32, i32)> {
+        self.selected
+    }
+
+    pub fn get_dialog(&self) -> Option<&Dialog> {
+        self.dialog.as_ref()
+    }
+
+    pub fn is_threat_map_shown(&self) -> bool {
+        self.show_threat_map
+    }
+
+    pub fn get_zone(&self) -> Option<&Zone> {
+        self.zone.as_ref()
+    }
+
+    pub fn get_log(&self) -> &[GameEvent] {
+        &self.log
+    }
+
+    ///
+    /// # Returns
+    /// * A hash of everything that decides how the battle plays out from here: every unit,
+    ///   whose phase it is and the state of the random number generator. Two copies of a
+    ///   battle that have played out the same way hash the same, even on different machines.
+    ///
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = Fnv1aHasher::default();
+        (self.turn, self.phase, self.outcome, self.winner).hash(&mut hasher);
+        (self.rng.get_seed(), self.rng.get_word_pos()).hash(&mut hasher);
+        for cell in self.get_cells() {
+            if let Some(unit) = cell.get_unit() {
+                (cell.get_position(), unit).hash(&mut hasher);
+            }
+        }
+        hasher.finish()
+    }
+
+    ///
+    /// # Returns
+    /// * A replay that plays this battle out, up to now, from the same start.
+    ///
+    pub fn to_replay(&self) -> Replay {
+        Replay {
+            level: self.level,
+            seed: self.seed,
+            roster: self.roster.clone(),
+            casual: self.casual,
+            sides: self.sides.clone(),
+            events: self.log.clone(),
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * Every square some enemy of the side whose phase it is could attack during its next
+    ///   phase, wherever it chose to move first, counting high ground and line of sight.
+    ///
+    pub fn threatened_cells(&self) -> HashSet<(i32, i32)> {
+        let mut threatened = HashSet::new();
+        let team = self.get_active_team();
+        for cell in self.get_cells() {
+            let Some(unit) = cell.get_unit().filter(|u| u.get_team() != team) else { continue };
+            let from = cell.get_position();
+            for dest in pathing::destinations(&self.grid, from, unit.movement(), unit.get_team()) {
+                threatened.extend(sight::strikable_cells(&self.grid, unit, dest));
+            }
+        }
+        threatened
+    }
+
+    pub fn get_fights(&self) -> &[Fight] {
+        &self.fights
+    }
+
+    pub fn set_quiet(&mut self, quiet: bool) {
+        self.quiet = quiet;
+    }
+
+    pub fn is_casual(&self) -> bool {
+        self.casual
+    }
+
+    pub fn set_casual(&mut self, casual: bool) {
+        self.casual = casual;
+    }
+
+    ///
+    /// # Returns
+    /// * Whether the campaign player won, once the battle is over.
+    ///
+    pub fn get_outcome(&self) -> Option<BattleOutcome> {
+        self.outcome
+    }
+
+    ///
+    /// # Returns
+    /// * The team left standing, once the battle is over. `None` if it ended with no human
+    ///   players left but several computer teams still fighting.
+    ///
+    pub fn get_winner(&self) -> Option<usize> {
+        self.winner
+    }
+
+    ///
+    /// # Returns
+    /// * The side that won, once the battle is over.
+    ///
+    pub fn get_winning_side(&self) -> Option<&Side> {
+        self.winner.and_then(|team| self.sides.iter().find(|side| side.team == team))
+    }
+
+    ///
+    /// # Returns
+    /// * Copies of the player's units still on the map.
+    ///
+    pub fn get_survivors(&self) -> Vec<Unit> {
+        self.get_cells()
+            .filter_map(|cell| cell.get_unit())
+            .filter(|unit| unit.get_team() == PLAYER_TEAM)
+            .cloned()
+            .collect()
+    }
+
+    ///
+    /// # Returns
+    /// * Whether units of `team` get to act in the current phase.
+    ///
+    fn is_active_team(&self, team: usize) -> bool {
+        team == self.get_active_team()
+    }
+
+    ///
+    /// # Returns
+    /// * The unit at `pos`, if it is still allowed to act this phase.
+    ///
+    fn ready_unit(&self, pos: (i32, i32)) -> Result<&Unit, GameError> {
+        if self.outcome.is_some() {
+            return Err(GameError::InvalidAction);
+        }
+        match self.get_cell(pos.0, pos.1).and_then(|cell| cell.get_unit()) {
+            Some(unit) if self.is_active_team(unit.get_team()) && !unit.is_done() => Ok(unit),
+            _ => Err(GameError::InvalidAction),
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * Where the unit at `pos` can move to, or nothing if it can't move right now.
+    ///
+    pub fn move_options(&self, pos: (i32, i32)) -> Vec<(i32, i32)> {
+        match self.ready_unit(pos) {
+            Ok(unit) if !unit.has_moved() =>
+                pathing::destinations(&self.grid, pos, unit.movement(), unit.get_team()),
+            _ => vec![],
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * The units the unit at `pos` can attack from where it stands, with the weapon it
+    ///   fights with. See `sight::can_strike`.
+    ///
+    pub fn attack_options(&self, pos: (i32, i32)) -> Vec<(i32, i32)> {
+        let Ok(unit) = self.ready_unit(pos) else { return vec![] };
+        self.get_cells()
+            .filter(|cell| cell.get_unit().is_some_and(|u| u.get_team() != unit.get_team()))
+            .map(|cell| cell.get_position())
+            .filter(|&target| sight::can_strike(&self.grid, unit, pos, target))
+            .collect()
+    }
+
+    ///
+    /// # Returns
+    /// * Every cell the unit at `pos` could strike this phase with the weapon it fights with:
+    ///   from where it stands once it has moved, or from anywhere it can move to before that.
+    ///
+    pub fn attack_range_cells(&self, pos: (i32, i32)) -> HashSet<(i32, i32)> {
+        let Ok(unit) = self.ready_unit(pos) else { return HashSet::new() };
+        let mut from = self.move_options(pos);
+        if from.is_empty() {
+            from.push(pos);
+        }
+        from.into_iter()
+            .flat_map(|origin| sight::strikable_cells(&self.grid, unit, origin))
+            .collect()
+    }
+
+    fn get_cell_mut(&mut self, (x, y): (i32, i32)) -> &mut Cell {
+        &mut self.grid[y as usize][x as usize]
+    }
+
+    ///
+    /// Moves the unit at `from` to `to`, leaving it facing the way it took its last step.
+    /// Each unit can move once per phase, and long moves tire it out.
+    ///
+    pub fn move_unit(&mut self, from: (i32, i32), to: (i32, i32)) -> Result<(), GameError> {
+        if !self.move_options(from).contains(&to) {
+            return Err(GameError::InvalidAction);
+        }
+        let last_step = self.ready_unit(from).ok()
+            .and_then(|unit| pathing::last_step(&self.grid, from, to, unit.movement(), unit.get_team()));
+        let mut unit = self.get_cell_mut(from).take_unit().unwrap();
+        let cost = pathing::reachable(&self.grid, from, unit.movement(), unit.get_team())
+            .get(&to).copied().unwrap_or(0);
+        unit.tire_from_move(cost);
+        unit.set_moved(true);
+        if let Some(facing) = last_step.and_then(|step| Facing::towards(step, to)) {
+            unit.set_facing(facing);
+        }
+        // `to` is empty unless it is `from`, which was just emptied.
+        let _ = self.get_cell_mut(to).set_unit(unit);
+        if self.selected == Some(from) {
+            self.selected = Some(to);
+        }
+        Ok(())
+    }
+
+    ///
+    /// The unit at `from` turns to attack the unit at `target`, which strikes back if it
+    /// survives, can reach, and was attacked from the front. Attacking ends the attacker's
+    /// action, and units that fall are removed from the map.
+    ///
+    /// # Returns
+    /// * What happened in the fight.
+    ///
+    pub fn attack(&mut self, from: (i32, i32), target: (i32, i32)) -> Result<CombatReport, GameError> {
+        if !self.attack_options(from).contains(&target) {
+            return Err(GameError::InvalidAction);
+        }
+        let attacker_terrain = *self.get_cell_mut(from).get_terrain();
+        let defender_terrain = *self.get_cell_mut(target).get_terrain();
+        let defender_reaches = self.get_cell(target.0, target.1).and_then(|cell| cell.get_unit())
+            .is_some_and(|defender| sight::can_strike(&self.grid, defender, target, from));
+        let mut attacker = self.get_cell_mut(from).take_unit().unwrap();
+        let mut defender = self.get_cell_mut(target).take_unit().unwrap();
+        let flank = defender.get_facing().flank(target, from);
+        if let Some(facing) = Facing::towards(from, target) {
+            attacker.set_facing(facing);
+        }
+        let report = combat::resolve(&mut attacker, &mut defender, attacker_terrain,
+                                     defender_terrain, defender_reaches, flank, &mut self.rng);
+        if !self.quiet {
+            println!("{} attacked {} ({}): {:?}", attacker.get_name(), defender.get_name(), flank.get_name(), report.strikes);
+        }
+        self.fights.push(Fight {
+            attacker: attacker.get_class(),
+            attacker_team: attacker.get_team(),
+            defender: defender.get_class(),
+            defender_team: defender.get_team(),
+            report: report.clone(),
+        });
+
+        attacker.set_moved(true);
+        attacker.set_done(true);
+        for (pos, unit) in [(from, attacker), (target, defender)] {
+            if unit.is_alive() {
+                let _ = self.get_cell_mut(pos).set_unit(unit);
+            } else if !self.quiet {
+                println!("{} fell", unit.get_name());
+            }
+        }
+        self.selected = None;
+        self.update_outcome();
+        Ok(report)
+    }
+
+    ///
+    /// # Returns
+    /// * The allies the unit at `pos` can use `support` on from where it stands.
+    ///
+    pub fn support_options(&self, pos: (i32, i32), support: Support) -> Vec<(i32, i32)> {
+        let Ok(user) = self.ready_unit(pos) else { return vec![] };
+        self.get_cells()
+            .filter(|cell| cell.get_unit().is_some_and(|target| {
+                support.can_use(user, target, distance(pos, cell.get_position()))
+            }))
+            .map(|cell| cell.get_position())
+            .collect()
+    }
+
+    ///
+    /// The unit at `from` uses `support` on the ally at `target`. This ends its action.
+    ///
+    /// # Returns
+    /// * What the support did.
+    ///
+    pub fn support(&mut self, from: (i32, i32), target: (i32, i32), support: Support) -> Result<SupportReport, GameError> {
+        if !self.support_options(from, support).contains(&target) {
+            return Err(GameError::InvalidAction);
+        }
+        let mut user = self.get_cell_mut(from).take_unit().unwrap();
+        let mut ally = self.get_cell_mut(target).take_unit().unwrap();
+        let report = support.apply(&mut user, &mut ally, &mut self.rng);
+        if !self.quiet {
+            println!("{} used {} on {}: {:?}", user.get_name(), support.get_name(), ally.get_name(), report);
+        }
+        user.set_moved(true);
+        user.set_done(true);
+        let _ = self.get_cell_mut(from).set_unit(user);
+        let _ = self.get_cell_mut(target).set_unit(ally);
+        self.selected = None;
+        Ok(report)
+    }
+
+    ///
+    /// # Returns
+    /// * The cells the unit at `pos` can aim `skill` at from where it stands: those it can
+    ///   see that would reach at least one unit the skill does something to.
+    ///
+    pub fn skill_options(&self, pos: (i32, i32), skill: Skill) -> Vec<(i32, i32)> {
+        let Ok(user) = self.ready_unit(pos) else { return vec![] };
+        if !skill.usable_by(user) {
+            return vec![];
+        }
+        skill.aim_cells(pos).into_iter()
+            .filter(|&(x, y)| self.get_cell(x, y).is_some())
+            .filter(|&center| sight::has_line_of_sight(&self.grid, pos, center))
+            .filter(|&center| skill.area_cells(center).into_iter().any(|(x, y)| {
+                self.get_cell(x, y).and_then(|cell| cell.get_unit()).is_some_and(|target| skill.affects(user, target))
+            }))
+            .collect()
+    }
+
+    ///
+    /// The unit at `from` uses `skill` aimed at `target`, spending its stamina. Every unit the
+    /// skill reaches there is affected, the user included if the skill is for allies. Units
+    /// that fall are removed from the map. This ends the user's action. Each unit the skill
+    /// damages is recorded as a fight the user started, with a strike for each hit.
+    ///
+    /// # Returns
+    /// * How many units the skill affected.
+    ///
+    pub fn use_skill(&mut self, from: (i32, i32), target: (i32, i32), skill: Skill) -> Result<usize, GameError> {
+        if !self.skill_options(from, skill).contains(&target) {
+            return Err(GameError::InvalidAction);
+        }
+        // The skill is worked out from the user as it was before using it.
+        let user = self.get_cell(from.0, from.1).and_then(|cell| cell.get_unit()).cloned().unwrap();
+        let quiet = self.quiet;
+        let mut affected = 0;
+        let mut kills = 0;
+        for (x, y) in skill.area_cells(target) {
+            let Some(cell) = self.grid.get_mut(y as usize).and_then(|row| row.get_mut(x as usize)) else { continue };
+            let Some(unit) = cell.get_unit_mut().filter(|unit| skill.affects(&user, unit)) else { continue };
+            let amounts: Vec<u32> = skill.effects().iter().map(|effect| effect.apply(&user, unit)).collect();
+            if !quiet {
+                println!("{} used {} on {}: {:?}", user.get_name(), skill.get_name(), unit.get_name(), amounts);
+            }
+            let strikes: Vec<Strike> = skill.effects().iter().zip(amounts)
+                .filter(|(effect, _)| matches!(effect, Primitive::Damage { .. }))
+                .map(|(_, damage)| Strike { by_initiator: true, hit: true, crit: false, damage, broke: false })
+                .collect();
+            if !strikes.is_empty() {
+                self.fights.push(Fight {
+                    attacker: user.get_class(),
+                    attacker_team: user.get_team(),
+                    defender: unit.get_class(),
+                    defender_team: unit.get_team(),
+                    report: CombatReport { strikes, defender_killed: !unit.is_alive(), ..Default::default() },
+                });
+            }
+            affected += 1;
+            if !unit.is_alive() {
+                if !quiet {
+                    println!("{} fell", unit.get_name());
+                }
+                let _ = cell.remove_unit();
+                kills += 1;
+            }
+        }
+        let rng = &mut self.rng;
+        let unit = self.grid[from.1 as usize][from.0 as usize].get_unit_mut().unwrap();
+        unit.spend_stamina(skill.cost());
+        unit.gain_experience(skill::XP_PER_SKILL + kills * combat::XP_PER_KILL, rng);
+        unit.set_moved(true);
+        unit.set_done(true);
+        self.selected = None;
+        self.update_outcome();
+        Ok(affected)
+    }
+
+    ///
+    /// Ends the action of the unit at `pos` without attacking, turned to face `facing`. A
+    /// unit that waits without having moved rests, and gets more stamina back next phase.
+    ///
+    pub fn wait(&mut self, pos: (i32, i32), facing: Facing) -> Result<(), GameError> {
+        self.ready_unit(pos)?;
+        let unit = self.get_cell_mut(pos).get_unit_mut().unwrap();
+        unit.set_facing(facing);
+        unit.set_rested(!unit.has_moved());
+        unit.set_moved(true);
+        unit.set_done(true);
+        if self.selected == Some(pos) {
+            self.selected = None;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Has the unit at `pos` fight with the weapon in inventory slot `slot`. Doesn't end its
+    /// action.
+    ///
+    pub fn equip(&mut self, pos: (i32, i32), slot: usize) -> Result<(), GameError> {
+        self.ready_unit(pos)?;
+        let quiet = self.quiet;
+        let unit = self.get_cell_mut(pos).get_unit_mut().unwrap();
+        if !unit.equip(slot) {
+            return Err(GameError::InvalidAction);
+        }
+        if !quiet {
+            println!("{} equipped {}", unit.get_name(), unit.get_inventory()[0].get_name());
+        }
+        Ok(())
+    }
+
+    ///
+    /// # Returns
+    /// * The order for the selected unit to equip the next weapon it can use, if it has
+    ///   another. Taking the last one each time goes through them all in turn.
+    ///
+    fn cycle_weapon_order(&self) -> Option<GameEvent> {
+        let pos = self.selected?;
+        let unit = self.ready_unit(pos).ok()?;
+        let slot = unit.get_inventory().iter()
+            .rposition(|weapon| weapon.get_kind().usable_by(unit.get_class()))
+            .filter(|&slot| slot > 0)?;
+        Some(GameEvent::Equip(pos.0, pos.1, slot))
+    }
+
+    ///
+    /// Carries out a unit's order. People's orders are logged for the battle's replay.
+    ///
+    fn apply_action(&mut self, event: &GameEvent) -> Result<(), GameError> {
+        let player_order = self.is_human_phase();
+        let command = if player_order { self.to_command(event) } else { None };
+        match *event {
+            GameEvent::MoveUnit(x1, y1, x2, y2) => self.move_unit((x1, y1), (x2, y2)),
+            GameEvent::Attack(x1, y1, x2, y2) => self.attack((x1, y1), (x2, y2)).map(|_| ()),
+            GameEvent::Wait(x, y, facing) => self.wait((x, y), facing),
+            GameEvent::Equip(x, y, slot) => self.equip((x, y), slot),
+            GameEvent::Support(x1, y1, x2, y2, support) => self.support((x1, y1), (x2, y2), support).map(|_| ()),
+            GameEvent::UseSkill(x1, y1, x2, y2, skill) => self.use_skill((x1, y1), (x2, y2), skill).map(|_| ()),
+            _ => Err(GameError::InvalidEvent),
+        }?;
+        if player_order {
+            self.log.push(*event);
+            self.history.extend(command);
+        }
+        Ok(())
+    }
+
+    ///
+    /// # Returns
+    /// * The command that takes a unit's order back, made before the order is carried out.
+    ///
+    fn to_command(&self, event: &GameEvent) -> Option<Command> {
+        let unit_at = |(x, y): (i32, i32)| self.get_cell(x, y).and_then(|cell| cell.get_unit()).cloned();
+        match *event {
+            GameEvent::MoveUnit(x1, y1, x2, y2) => {
+                let unit = unit_at((x1, y1))?;
+                Some(Command::Move { from: (x1, y1), to: (x2, y2), stamina: unit.get_stamina(), facing: unit.get_facing() })
+            }
+            GameEvent::Attack(x1, y1, x2, y2) => Some(Command::Attack {
+                from: (x1, y1),
+                target: (x2, y2),
+                attacker: Box::new(unit_at((x1, y1))?),
+                defender: Box::new(unit_at((x2, y2))?),
+                rng: Box::new(self.rng.clone()),
+            }),
+            GameEvent::Wait(x, y, _) => {
+                let unit = unit_at((x, y))?;
+                Some(Command::Wait { pos: (x, y), moved: unit.has_moved(), facing: unit.get_facing() })
+            }
+            GameEvent::Equip(x, y, _) => Some(Command::Equip { pos: (x, y), unit: Box::new(unit_at((x, y))?) }),
+            GameEvent::Support(x1, y1, x2, y2, _) => Some(Command::Support {
+                from: (x1, y1),
+                target: (x2, y2),
+                user: Box::new(unit_at((x1, y1))?),
+                ally: Box::new(unit_at((x2, y2))?),
+                rng: Box::new(self.rng.clone()),
+            }),
+            GameEvent::UseSkill(x1, y1, x2, y2, skill) => {
+                let reached = skill.area_cells((x2, y2)).into_iter().filter(|&cell| cell != (x1, y1));
+                let units = std::iter::once((x1, y1)).chain(reached)
+                    .filter_map(|pos| Some((pos, unit_at(pos)?)))
+                    .collect();
+                Some(Command::Skill { units, rng: Box::new(self.rng.clone()), fights: self.fights.len() })
+            }
+            _ => None,
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * Whether the player's last order this phase can be taken back. Outside casual mode
+    ///   only a move can, until the unit does something else. Changing weapons after the
+    ///   move doesn't count, and is taken back along with it.
+    ///
+    pub fn can_undo(&self) -> bool {
+        if !self.is_human_phase() || self.outcome.is_some() {
+            return false;
+        }
+        if self.casual {
+            return !self.history.is_empty();
+        }
+        self.history.iter().rev().find(|command| !command.is_equip()).is_some_and(Command::is_move)
+    }
+
+    ///
+    /// Takes back the player's last order this phase. See `can_undo`.
+    ///
+    pub fn undo(&mut self) -> Result<(), GameError> {
+        if !self.can_undo() {
+            return Err(GameError::InvalidAction);
+        }
+        if !self.casual {
+            while self.history.last().is_some_and(Command::is_equip) {
+                let equip = self.history.pop().unwrap();
+                self.revert(equip);
+            }
+        }
+        let command = self.history.pop().unwrap();
+        self.revert(command);
+        self.log.push(GameEvent::UndoMove);
+        Ok(())
+    }
+
+    ///
+    /// Takes back every order the player has given this phase. Only allowed in casual mode.
+    ///
+    pub fn rewind_turn(&mut self) -> Result<(), GameError> {
+        if !self.casual || !self.is_human_phase() || self.outcome.is_some() || self.history.is_empty() {
+            return Err(GameError::InvalidAction);
+        }
+        while let Some(command) = self.history.pop() {
+            self.revert(command);
+        }
+        self.selected = None;
+        self.log.push(GameEvent::RewindTurn);
+        Ok(())
+    }
+
+    ///
+    /// Puts the battle back the way it was before `command` was carried out. Commands must
+    /// be reverted most recent first.
+    ///
+    fn revert(&mut self, command: Command) {
+        match command {
+            Command::Move { from, to, stamina, facing } => {
+                if let Some(mut unit) = self.get_cell_mut(to).take_unit() {
+                    unit.set_moved(false);
+                    unit.set_stamina(stamina);
+                    unit.set_facing(facing);
+                    let _ = self.get_cell_mut(from).set_unit(unit);
+                }
+                if self.selected == Some(to) {
+                    self.selected = Some(from);
+                }
+            }
+            Command::Attack { from, target, attacker, defender, rng } => {
+                for (pos, unit) in [(from, attacker), (target, defender)] {
+                    self.get_cell_mut(pos).take_unit();
+                    let _ = self.get_cell_mut(pos).set_unit(*unit);
+                }
+                self.rng = *rng;
+                self.fights.pop();
+            }
+            Command::Wait { pos, moved, facing } => {
+                if let Some(unit) = self.get_cell_mut(pos).get_unit_mut() {
+                    unit.set_done(false);
+                    unit.set_moved(moved);
+                    unit.set_rested(false);
+                    unit.set_facing(facing);
+                }
+            }
+            Command::Equip { pos, unit } => {
+                self.get_cell_mut(pos).take_unit();
+                let _ = self.get_cell_mut(pos).set_unit(*unit);
+            }
+            Command::Support { from, target, user, ally, rng } => {
+                for (pos, unit) in [(from, user), (target, ally)] {
+                    self.get_cell_mut(pos).take_unit();
+                    let _ = self.get_cell_mut(pos).set_unit(*unit);
+                }
+                self.rng = *rng;
+            }
+            Command::Skill { units, rng, fights } => {
+                for (pos, unit) in units {
+                    self.get_cell_mut(pos).take_unit();
+                    let _ = self.get_cell_mut(pos).set_unit(unit);
+                }
+                self.rng = *rng;
+                self.fights.truncate(fights);
+            }
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * Whether the selected unit has moved and done nothing since, so cancelling takes the
+    ///   move back.
+    ///
+    fn selected_just_moved(&self) -> bool {
+        matches!(self.history.last(), Some(Command::Move { to, .. }) if Some(*to) == self.selected)
+    }
+
+    ///
+    /// Reacts to the player clicking a cell: picks one of their units, then moves it, has it
+    /// attack, has it use the support or skill picked from its action menu, or has it wait
+    /// when its own cell is clicked again. While picking which way to face, clicking a cell
+    /// next to the unit has it wait facing that cell.
+    ///
+    fn select_cell(&mut self, pos: (i32, i32)) {
+        let targeting = self.targeting.take();
+        if !self.is_human_phase() || self.outcome.is_some() {
+            return;
+        }
+        let order = match (self.selected, targeting) {
+            (Some(selected), Some(Targeting::Support(support))) if self.support_options(selected, support).contains(&pos) =>
+                GameEvent::Support(selected.0, selected.1, pos.0, pos.1, support),
+            (Some(selected), Some(Targeting::Skill(skill))) if self.skill_options(selected, skill).contains(&pos) =>
+                GameEvent::UseSkill(selected.0, selected.1, pos.0, pos.1, skill),
+            (Some(selected), Some(Targeting::Facing)) if self.facing_options(selected).contains(&pos) => {
+                let facing = Facing::towards(selected, pos).unwrap();
+                GameEvent::Wait(selected.0, selected.1, facing)
+            }
+            (Some(_), Some(_)) => return,
+            (Some(selected), None) if selected == pos => {
+                let Ok(unit) = self.ready_unit(pos) else { return };
+                GameEvent::Wait(pos.0, pos.1, unit.get_facing())
+            }
+            (Some(selected), None) if self.move_options(selected).contains(&pos) =>
+                GameEvent::MoveUnit(selected.0, selected.1, pos.0, pos.1),
+            (Some(selected), None) if self.attack_options(selected).contains(&pos) =>
+                GameEvent::Attack(selected.0, selected.1, pos.0, pos.1),
+            _ => {
+                self.selected = self.ready_unit(pos).ok().map(|_| pos);
+                return;
+            }
+        };
+        if let Err(e) = self.apply_action(&order) {
+            println!("Couldn't act on cell {:?}: {:?}", pos, e);
+        }
+    }
+
+    ///
+    /// Starts picking who the selected unit uses `support` on, if there is anyone.
+    ///
+    fn choose_support(&mut self, support: Support) {
+        let Some(selected) = self.selected else { return };
+        if self.support_options(selected, support).is_empty() {
+            println!("No one to use {} on", support.get_name());
+            return;
+        }
+        self.targeting = Some(Targeting::Support(support));
+    }
+
+    ///
+    /// Starts picking where the selected unit aims `skill`, if it would reach anyone.
+    ///
+    fn choose_skill(&mut self, skill: Skill) {
+        let Some(selected) = self.selected else { return };
+        if self.skill_options(selected, skill).is_empty() {
+            println!("No one for {} to reach", skill.get_name());
+            return;
+        }
+        self.targeting = Some(Targeting::Skill(skill));
+    }
+
+    ///
+    /// Starts picking which way the selected unit turns as it waits.
+    ///
+    fn choose_facing(&mut self) {
+        if self.selected.is_some() {
+            self.targeting = Some(Targeting::Facing);
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * The cells next to the unit at `pos` it can turn to face before waiting.
+    ///
+    pub fn facing_options(&self, pos: (i32, i32)) -> Vec<(i32, i32)> {
+        if self.ready_unit(pos).is_err() {
+            return vec![];
+        }
+        Facing::ALL.iter()
+            .map(|facing| facing.offset())
+            .map(|(dx, dy)| (pos.0 + dx, pos.1 + dy))
+            .filter(|&(x, y)| self.get_cell(x, y).is_some())
+            .collect()
+    }
+
+    ///
+    /// # Returns
+    /// * The cells the selected unit can use what it is about to use on, or turn to face,
+    ///   while the player picks one.
+    ///
+    pub fn targeting_options(&self) -> Vec<(i32, i32)> {
+        match (self.selected, self.targeting) {
+            (Some(selected), Some(Targeting::Support(support))) => self.support_options(selected, support),
+            (Some(selected), Some(Targeting::Skill(skill))) => self.skill_options(selected, skill),
+            (Some(selected), Some(Targeting::Facing)) => self.facing_options(selected),
+            _ => vec![],
+        }
+    }
+
+    ///
+    /// Lays out the action menu for the selected unit: a button for each support and active
+    /// skill it knows, usable when it has someone to use it on, and one to pick which way to
+    /// face and wait. The menu is empty when no unit of the local player is selected.
+    ///
+    fn update_action_menu(&mut self) {
+        self.action_menu.clear();
+        let Some(pos) = self.selected.filter(|_| self.is_local_phase() && self.outcome.is_none()) else { return };
+        let Ok(unit) = self.ready_unit(pos) else { return };
+        let mut entries: Vec<(String, GameEvent, bool)> = Support::known_by(unit.get_class()).into_iter()
+            .map(|support| {
+                let label = format!("{} ({})", support.get_name(), support.cost());
+                let usable = self.targeting.is_none() && !self.support_options(pos, support).is_empty();
+                (label, GameEvent::ChooseSupport(support), usable)
+            })
+            .collect();
+        entries.extend(Skill::known_by(unit).into_iter().filter(|skill| skill.is_active()).map(|skill| {
+            let label = format!("{} ({})", skill.get_name(), skill.cost());
+            let usable = self.targeting.is_none() && !self.skill_options(pos, skill).is_empty();
+            (label, GameEvent::ChooseSkill(skill), usable)
+        }));
+        entries.push(("Wait".to_string(), GameEvent::ChooseFacing, self.targeting.is_none()));
+        let left = (WIDTH - ACTION_MENU_WIDTH) as f64;
+        self.action_menu = entries.into_iter().enumerate()
+            .map(|(row, (label, event, enabled))| {
+                let top = (row + 1) as f64;
+                let mut button = Button::new(&label, (left, top), (WIDTH as f64, top + 1.0), event);
+                button.set_enabled(enabled);
+                button
+            })
+            .collect();
+    }
+
+    pub fn get_action_menu(&self) -> &[Button] {
+        &self.action_menu
+    }
+
+    ///
+    /// # Returns
+    /// * The support or skill the selected unit is about to use, while the player picks where.
+    ///
+    pub fn get_targeting(&self) -> Option<Targeting> {
+        self.targeting
+    }
+
+    ///
+    /// Ends the phase of whoever is playing now, has the computer take the phases of any
+    /// computer sides that come next, and stops at the next person's phase.
+    ///
+    pub fn end_turn(&mut self) {
+        if !self.is_human_phase() || self.outcome.is_some() {
+            return;
+        }
+        // Logged as confirmed, so the replay doesn't depend on the confirm-end-turn setting.
+        self.log.push(GameEvent::ConfirmEndTurn);
+        self.history.clear();
+        self.selected = None;
+        let ending = self.phase;
+        self.next_phase();
+        while self.outcome.is_none() && !self.is_human_phase() {
+            self.run_computer_phase();
+            if self.outcome.is_none() {
+                self.next_phase();
+            }
+        }
+        self.handoff = self.outcome.is_none() && self.phase != ending && self.is_hot_seat();
+    }
+
+    ///
+    /// Moves on to the next side that still has units, starting a new turn whenever the order
+    /// comes back around, and readies every unit for the new phase. Effects act on the units
+    /// of the side whose phase is ending, then on those of the side whose phase begins, which
+    /// also get stamina back.
+    ///
+    fn next_phase(&mut self) {
+        let (ending, quiet) = (self.get_active_team(), self.quiet);
+        for unit in self.grid.iter_mut().flatten().filter_map(|cell| cell.get_unit_mut()) {
+            if unit.get_team() == ending {
+                let lost = unit.end_phase();
+                if lost > 0 && !quiet {
+                    println!("{} lost {} HP to its effects", unit.get_name(), lost);
+                }
+            }
+        }
+        for _ in 0..self.sides.len() {
+            self.phase = (self.phase + 1) % self.sides.len();
+            if self.phase == 0 {
+                self.turn += 1;
+                self.apply_zone_damage();
+            }
+            let team = self.get_active_team();
+            if self.get_cells().any(|cell| cell.get_unit().is_some_and(|u| u.get_team() == team)) {
+                break;
+            }
+        }
+        let team = self.get_active_team();
+        for cell in self.grid.iter_mut().flatten() {
+            let terrain = *cell.get_terrain();
+            if let Some(unit) = cell.get_unit_mut() {
+                unit.refresh();
+                if unit.get_team() == team {
+                    unit.recover_stamina(terrain);
+                    let lost = unit.start_phase();
+                    if lost > 0 && !quiet {
+                        println!("{} lost {} HP to its effects", unit.get_name(), lost);
+                    }
+                }
+            }
+        }
+    }
+
+    ///
+    /// Hurts every unit outside the safe zone, as each turn starts. Units it kills are taken
+    /// off the map, which can decide the battle.
+    ///
+    fn apply_zone_damage(&mut self) {
+        let Some(zone) = self.zone else { return };
+        let (turn, quiet) = (self.turn, self.quiet);
+        let damage = zone.damage(turn);
+        let mut fell = false;
+        for cell in self.grid.iter_mut().flatten() {
+            if zone.contains(cell.get_position(), turn) {
+                continue;
+            }
+            let Some(unit) = cell.get_unit_mut() else { continue };
+            unit.take_damage(damage);
+            if !unit.is_alive() {
+                let unit = cell.take_unit().unwrap();
+                if !quiet {
+                    println!("{} was caught outside the zone", unit.get_name());
+                }
+                fell = true;
+            }
+        }
+        if fell {
+            self.update_outcome();
+        }
+    }
+
+    ///
+    /// Has the computer act with every unit of the side whose phase it is.
+    ///
+    fn run_computer_phase(&mut self) {
+        let team = self.get_active_team();
+        while let Some(mut pos) = ai::next_unit(self, team) {
+            if self.outcome.is_some() {
+                return;
+            }
+            for event in ai::plan_unit(self, pos) {
+                if self.apply_action(&event).is_ok() {
+                    if let GameEvent::MoveUnit(_, _, x, y) = event {
+                        pos = (x, y);
+                    }
+                }
+            }
+            // Never let a unit whose plan fell through hold up the phase.
+            if let Some(unit) = self.get_cell_mut(pos).get_unit_mut() {
+                unit.set_done(true);
+            }
+        }
+    }
+
+    ///
+    /// Decides the battle once one team or none is left standing, or once none of the people
+    /// playing have any units left.
+    ///
+    fn update_outcome(&mut self) {
+        let mut standing: Vec<usize> = self.get_cells()
+            .filter_map(|cell| cell.get_unit())
+            .map(|unit| unit.get_team())
+            .collect();
+        standing.sort();
+        standing.dedup();
+        let humans_left = self.sides.iter().any(|side| side.is_human() && standing.contains(&side.team));
+        if standing.len() > 1 && humans_left {
+            return;
+        }
+        self.winner = if standing.len() == 1 { standing.first().copied() } else { None };
+        self.outcome = Some(if self.winner == Some(PLAYER_TEAM) { BattleOutcome::Victory } else { BattleOutcome::Defeat });
+    }
+
+    ///
+    /// # Returns
+    /// * The width and height of the map, in cells.
+    ///
+    pub fn get_map_size(&self) -> (i32, i32) {
+        (self.grid.first().map_or(0, |row| row.len() as i32), self.grid.len() as i32)
+    }
+
+    pub fn get_cell(&self, x: i32, y: i32) -> Option<&Cell> {
+        if x < 0 || y < 0 {
+            return None;
+        }
+        self.grid.get(y as usize).and_then(|row| row.get(x as usize))
+    }
+
+    pub fn get_cells(&self) -> impl Iterator<Item = &Cell> {
+        self.grid.iter().flatten()
+    }
+
+    ///
+    /// # Returns
+    /// * Every cell of the map, indexed as `grid[y][x]`.
+    ///
+    pub fn get_grid(&self) -> &[Vec<Cell>] {
+        &self.grid
+    }
+
+    pub fn get_camera(&self) -> (i32, i32) {
+        self.camera
+    }
+
+    ///
+    /// # Returns
+    /// * How many cells the camera shows across and down.
+    ///
+    pub fn get_viewport_size(&self) -> (i32, i32) {
+        let (width, height) = self.get_map_size();
+        (WIDTH.min(width), HEIGHT.min(height))
+    }
+
+    pub fn get_minimap(&self) -> &Minimap {
+        &self.minimap
+    }
+
+    ///
+    /// # Returns
+    /// * The cells currently on screen, leaving out those hidden behind the minimap.
+    ///
+    pub fn visible_cells(&self) -> impl Iterator<Item = &Cell> {
+        let (cam_x, cam_y) = self.camera;
+        let (view_w, view_h) = self.get_viewport_size();
+        self.grid.iter()
+            .skip(cam_y as usize)
+            .take(view_h as usize)
+            .flat_map(move |row| row.iter().skip(cam_x as usize).take(view_w as usize))
+            .filter(move |cell| {
+                let (x, y) = cell.get_position();
+                !self.minimap.covers(x - cam_x, y - cam_y)
+            })
+    }
+
+    ///
+    /// Moves the camera so the given cell is as close to the middle of the screen as the
+    /// edges of the map allow.
+    ///
+    pub fn center_camera(&mut self, x: i32, y: i32) {
+        let (view_w, view_h) = self.get_viewport_size();
+        self.set_camera((x - view_w / 2, y - view_h / 2));
+    }
+
+    fn set_camera(&mut self, camera: (i32, i32)) {
+        let (width, height) = self.get_map_size();
+        let (view_w, view_h) = self.get_viewport_size();
+        self.camera = (camera.0.clamp(0, width - view_w), camera.1.clamp(0, height - view_h));
+        let camera = self.camera;
+        self.grid.iter_mut().flatten().for_each(|cell| cell.scroll_to(camera));
+    }
+}
+
+impl Scene for MidLevelScene {
+    fn get_name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    fn activate(&mut self) -> Result<(), GameError> {
+        if self.activated {
+            return Err(GameError::SceneActivationError);
+        }
+        self.activated = true;
+        Ok(())
+    }
+
+    fn deactivate(&mut self) -> Result<(), GameError> {
+        if !self.activated {
+            return Err(GameError::SceneActivationError);
+        }
+        self.activated = false;
+        Ok(())
+    }
+
+    fn receive_event(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::CenterCamera(x, y) => self.center_camera(*x, *y),
+            GameEvent::MoveCamera(dx, dy) => {
+                self.set_camera((self.camera.0 + dx, self.camera.1 + dy));
+            }
+            GameEvent::EndTurn if settings::current().confirm_end_turn && self.is_human_phase() && self.outcome.is_none() => {
+                self.dialog = Some(Dialog::new("End your turn?", "End Turn", GameEvent::ConfirmEndTurn));
+            }
+            GameEvent::EndTurn => self.end_turn(),
+            GameEvent::ConfirmEndTurn => {
+                self.dialog = None;
+                self.end_turn();
+            }
+            GameEvent::ToggleThreatMap => self.show_threat_map = !self.show_threat_map,
+            GameEvent::SelectCell(x, y) => self.select_cell((*x, *y)),
+            GameEvent::Cancel if self.dialog.is_some() => self.dialog = None,
+            GameEvent::Cancel if self.targeting.is_some() => self.targeting = None,
+            GameEvent::Cancel if self.selected_just_moved() => {
+                let _ = self.undo();
+            }
+            GameEvent::Cancel => self.selected = None,
+            GameEvent::BeginPhase => self.handoff = false,
+            GameEvent::UndoMove => {
+                if let Err(e) = self.undo() {
+                    println!("Nothing to undo: {:?}", e);
+                }
+            }
+            GameEvent::RewindTurn => {
+                if let Err(e) = self.rewind_turn() {
+                    println!("Couldn't rewind the turn: {:?}", e);
+                }
+            }
+            GameEvent::CycleWeapon => match self.cycle_weapon_order() {
+                Some(order) => self.receive_event(&order),
+                None => println!("No other weapon to equip"),
+            },
+            GameEvent::ChooseSupport(support) => self.choose_support(*support),
+            GameEvent::ChooseSkill(skill) => self.choose_skill(*skill),
+            GameEvent::ChooseFacing => self.choose_facing(),
+            GameEvent::MoveUnit(..) | GameEvent::Attack(..) | GameEvent::Wait(..) | GameEvent::Equip(..)
+            | GameEvent::Support(..) | GameEvent::UseSkill(..) => {
+                if let Err(e) = self.apply_action(event) {
+                    println!("Couldn't carry out {:?}: {:?}", event, e);
+                }
+            }
+            GameEvent::SaveBattle => match save::save_battle(self, QUICKSAVE_PATH) {
+                Ok(()) => println!("Saved battle to {}", QUICKSAVE_PATH),
+                Err(e) => println!("Couldn't save battle: {}", e),
+            },
+            _ => {}
+        }
+        self.update_action_menu();
+    }
+
+    fn get_ui_elements(&self) -> Vec<&dyn UIElement> {
+        if let Some(dialog) = &self.dialog {
+            return dialog.get_ui_elements();
+        }
+        // The action menu and minimap come first so clicks on them aren't taken by the cells
+        // underneath.
+        let mut elements: Vec<&dyn UIElement> = self.action_menu.iter().map(|b| b as &dyn UIElement).collect();
+        elements.push(&self.minimap);
+        elements.extend(self.visible_cells().map(|cell| cell as &dyn UIElement));
+        elements
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::inlevel::unit::Class;
+
+    impl MidLevelScene {
+        /// Lets tests set a unit up in ways battles only get to after many phases.
+        pub(crate) fn get_unit_mut(&mut self, pos: (i32, i32)) -> Option<&mut Unit> {
+            self.get_cell_mut(pos).get_unit_mut()
+        }
+    }
+
+    /// Two of the player's units facing two enemies across open plains.
+    const LEVEL: &str = r#"
+        name = "Open Field"
+        terrain = ["PPPPPP", "PPPPPP"]
+
+        [[units]]
+        name = "Ayla"
+        class = "Swordsman"
+        team = 0
+        position = [0, 0]
+        weapons = ["Iron Sword", "Steel Sword"]
+
+        [[units]]
+        name = "Cato"
+        class = "Mage"
+        team = 0
+        position = [2, 1]
+        weapons = ["Fire"]
+
+        [[units]]
+        name = "Grunt"
+        class = "Swordsman"
+        team = 1
+        position = [4, 0]
+
+        [[units]]
+        name = "Brute"
+        class = "Swordsman"
+        team = 1
+        position = [5, 1]
+    "#;
+
+    fn scene(casual: bool) -> MidLevelScene {
+        let mut scene = MidLevelScene::new(1, &LevelData::parse(LEVEL).unwrap(), 0, &[]);
+        scene.set_quiet(true);
+        scene.set_casual(casual);
+        scene
+    }
+
+    fn unit_at(scene: &MidLevelScene, (x, y): (i32, i32)) -> Option<&Unit> {
+        scene.get_cell(x, y).and_then(|cell| cell.get_unit())
+    }
+
+    #[test]
+    fn undoes_a_move() {
+        let mut scene = scene(false);
+        let before = unit_at(&scene, (0, 0)).cloned();
+        scene.apply_action(&GameEvent::MoveUnit(0, 0, 2, 0)).unwrap();
+        assert!(scene.can_undo());
+        scene.undo().unwrap();
+        assert_eq!(unit_at(&scene, (0, 0)).cloned(), before);
+        assert!(unit_at(&scene, (2, 0)).is_none());
+        assert_eq!(scene.get_log().last(), Some(&GameEvent::UndoMove));
+    }
+
+    #[test]
+    fn undoes_a_move_along_with_the_weapon_changes_after_it() {
+        let mut scene = scene(false);
+        let before = unit_at(&scene, (0, 0)).cloned();
+        scene.apply_action(&GameEvent::MoveUnit(0, 0, 2, 0)).unwrap();
+        scene.apply_action(&GameEvent::Equip(2, 0, 1)).unwrap();
+        assert_eq!(unit_at(&scene, (2, 0)).unwrap().get_inventory()[0].get_name(), "Steel Sword");
+        assert!(scene.can_undo());
+        scene.undo().unwrap();
+        assert_eq!(unit_at(&scene, (0, 0)).cloned(), before);
+        assert!(!scene.can_undo());
+    }
+
+    #[test]
+    fn cannot_undo_an_attack() {
+        let mut scene = scene(false);
+        scene.apply_action(&GameEvent::MoveUnit(0, 0, 3, 0)).unwrap();
+        scene.apply_action(&GameEvent::Attack(3, 0, 4, 0)).unwrap();
+        assert!(!scene.can_undo());
+        assert!(matches!(scene.undo(), Err(GameError::InvalidAction)));
+    }
+
+    #[test]
+    fn undoes_an_attack_in_casual_mode() {
+        let mut scene = scene(true);
+        scene.apply_action(&GameEvent::MoveUnit(0, 0, 3, 0)).unwrap();
+        let attacker = unit_at(&scene, (3, 0)).cloned();
+        let defender = unit_at(&scene, (4, 0)).cloned();
+        scene.apply_action(&GameEvent::Attack(3, 0, 4, 0)).unwrap();
+        scene.undo().unwrap();
+        assert_eq!(unit_at(&scene, (3, 0)).cloned(), attacker);
+        assert_eq!(unit_at(&scene, (4, 0)).cloned(), defender);
+        assert!(scene.get_fights().is_empty());
+    }
+
+    #[test]
+    fn rewinds_the_whole_phase_only_in_casual_mode() {
+        let mut strict = scene(false);
+        strict.apply_action(&GameEvent::MoveUnit(0, 0, 2, 0)).unwrap();
+        assert!(matches!(strict.rewind_turn(), Err(GameError::InvalidAction)));
+
+        let mut casual = scene(true);
+        let before: Vec<Option<Unit>> = [(0, 0), (2, 1)].iter().map(|&pos| unit_at(&casual, pos).cloned()).collect();
+        casual.apply_action(&GameEvent::MoveUnit(0, 0, 2, 0)).unwrap();
+        casual.apply_action(&GameEvent::Wait(2, 0, Facing::default())).unwrap();
+        casual.apply_action(&GameEvent::MoveUnit(2, 1, 1, 1)).unwrap();
+        casual.rewind_turn().unwrap();
+        let after: Vec<Option<Unit>> = [(0, 0), (2, 1)].iter().map(|&pos| unit_at(&casual, pos).cloned()).collect();
+        assert_eq!(after, before);
+        assert!(!casual.can_undo());
+        assert_eq!(casual.get_log().last(), Some(&GameEvent::RewindTurn));
+    }
+
+    #[test]
+    fn records_a_fight_for_each_unit_a_skill_damages() {
+        let mut scene = scene(true);
+        scene.apply_action(&GameEvent::UseSkill(2, 1, 4, 1, Skill::Fireball)).unwrap();
+        let fights = scene.get_fights();
+        assert_eq!(fights.len(), 2);
+        for fight in fights {
+            assert_eq!((fight.attacker, fight.attacker_team), (Class::Mage, PLAYER_TEAM));
+            assert_eq!(fight.defender_team, 1);
+            assert_eq!(fight.report.strikes.len(), 1);
+            assert!(fight.report.strikes[0].by_initiator && fight.report.strikes[0].hit);
+        }
+        scene.undo().unwrap();
+        assert!(scene.get_fights().is_empty());
+    }
+
+    #[test]
+    fn hot_seat_players_take_turns_in_order() {
+        let mut scene = scene(false);
+        scene.set_sides(team::hot_seat(&[0, 1]));
+        assert!(scene.is_hot_seat());
+        assert_eq!(scene.get_active_side().name, "Player 1");
+        scene.end_turn();
+        assert_eq!(scene.get_active_side().name, "Player 2");
+        assert_eq!(scene.get_active_team(), 1);
+        assert!(scene.needs_handoff());
+        let turn = scene.get_turn();
+        scene.end_turn();
+        assert_eq!(scene.get_active_side().name, "Player 1");
+        assert_eq!(scene.get_turn(), turn + 1);
+    }
+}
@@ -1,3 +1,21 @@
 pub mod game;
-mod button;
-mod inlevel;
\ No newline at end of file
+pub mod button;
+pub mod campaign;
+pub mod dialog;
+pub mod hash;
+pub mod headless;
+pub mod inlevel;
+pub mod interstitial;
+pub mod levelselect;
+pub mod lobby;
+pub mod midlevel;
+pub mod net;
+pub mod options;
+pub mod promotion;
+pub mod reconnect;
+pub mod replay;
+pub mod save;
+pub mod saveslots;
+pub mod simulate;
+pub mod start;
+pub mod transition;
@@ -0,0 +1,483 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::model::game::{GameEvent, Scene};
+use crate::model::midlevel::MidLevelScene;
+use crate::model::inlevel::level::{LevelData, LevelError};
+use crate::model::inlevel::team;
+
+/// Bumped whenever the messages change, so mismatched builds refuse to play each other.
+pub const PROTOCOL_VERSION: u32 = 9;
+
+/// The port a game is hosted on when not told otherwise.
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// Seconds between the guest's attempts to reach the host.
+const RETRY_INTERVAL: f64 = 2.0;
+
+/// How long one attempt to reach the host may take.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug)]
+pub enum NetError {
+    Io(io::Error),
+    /// The other side sent something that isn't a message.
+    Malformed(serde_json::Error),
+    Disconnected,
+    IncompatibleVersion { found: u32, expected: u32 },
+    /// The other side sent an order its battle shouldn't have allowed, e.g. outside its phase.
+    Rejected(GameEvent),
+    /// The two battles no longer match, as of the given turn.
+    Desync(u32),
+    Level(LevelError),
+}
+
+impl fmt::Display for NetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetError::Io(e) => write!(f, "network error: {}", e),
+            NetError::Malformed(e) => write!(f, "the other player sent a malformed message: {}", e),
+            NetError::Disconnected => write!(f, "the other player disconnected"),
+            NetError::IncompatibleVersion { found, expected } => write!(f,
+                "the other player's game speaks version {} of the protocol, but this one speaks version {}",
+                found, expected),
+            NetError::Rejected(event) => write!(f, "the other player sent an order that isn't allowed: {:?}", event),
+            NetError::Desync(turn) => write!(f, "the battles went out of sync on turn {}", turn),
+            NetError::Level(e) => write!(f, "couldn't set up the battle: {}", e),
+        }
+    }
+}
+
+///
+/// Which end of the connection this machine is. The host picks the level and the seed, and
+/// plays the level's first team; the guest plays its second.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Host,
+    Guest,
+}
+
+impl Role {
+    ///
+    /// # Returns
+    /// * The index, among the battle's sides, of the side this machine plays.
+    ///
+    pub fn side_index(&self) -> usize {
+        match self {
+            Role::Host => 0,
+            Role::Guest => 1,
+        }
+    }
+}
+
+///
+/// What the two machines say to each other, one JSON message per line.
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Message {
+    /// The first thing each side sends on a new connection.
+    Hello { version: u32 },
+    /// From the host: the battle to play, and every order given in it so far. Sent when the
+    /// battle starts, and again whenever the guest reconnects.
+    Start { level: i32, seed: u64, casual: bool, events: Vec<GameEvent> },
+    /// An order given on the sender's machine.
+    Order(GameEvent),
+    /// A hash of the sender's battle, taken when a phase starts and when the battle is decided.
+    StateHash { checkpoint: Checkpoint, hash: u64 },
+}
+
+///
+/// A point in a battle at which both machines hash their copies: the turn, the index of the
+/// side whose phase it is, and whether the battle is decided.
+///
+pub type Checkpoint = (u32, usize, bool);
+
+fn checkpoint(battle: &MidLevelScene) -> Checkpoint {
+    (battle.get_turn(), battle.get_phase(), battle.get_outcome().is_some())
+}
+
+///
+/// A TCP connection carrying `Message`s. Never blocks to wait for the other side.
+///
+pub struct Connection {
+    stream: TcpStream,
+    /// Bytes received that don't make up a whole line yet.
+    received: Vec<u8>,
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nodelay(true)?;
+        stream.set_nonblocking(true)?;
+        Ok(Self { stream, received: Vec::new() })
+    }
+
+    pub fn connect(address: SocketAddr) -> Result<Self, NetError> {
+        TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)
+            .and_then(Self::new)
+            .map_err(NetError::Io)
+    }
+
+    pub fn send(&mut self, message: &Message) -> Result<(), NetError> {
+        let mut line = serde_json::to_vec(message).map_err(NetError::Malformed)?;
+        line.push(b'\n');
+        let mut written = 0;
+        while written < line.len() {
+            match self.stream.write(&line[written..]) {
+                Ok(0) => return Err(NetError::Disconnected),
+                Ok(n) => written += n,
+                // Messages are small, so a full send buffer empties again almost at once.
+                Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(Duration::from_millis(1)),
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(NetError::Io(e)),
+            }
+        }
+        Ok(())
+    }
+
+    ///
+    /// # Returns
+    /// * Every whole message that has arrived since the last call, or why the connection is
+    ///   no longer usable. Messages that arrived before the other side hung up are still
+    ///   returned first.
+    ///
+    pub fn receive(&mut self) -> Result<Vec<Message>, NetError> {
+        let mut buffer = [0; 4096];
+        let mut closed = false;
+        loop {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => {
+                    closed = true;
+                    break;
+                }
+                Ok(n) => self.received.extend_from_slice(&buffer[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(NetError::Io(e)),
+            }
+        }
+        let mut messages = Vec::new();
+        while let Some(end) = self.received.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = self.received.drain(..=end).collect();
+            messages.push(serde_json::from_slice(&line[..end]).map_err(NetError::Malformed)?);
+        }
+        if closed && messages.is_empty() {
+            return Err(NetError::Disconnected);
+        }
+        Ok(messages)
+    }
+}
+
+///
+/// Something that happened on the connection, for the game to act on.
+///
+#[derive(Debug)]
+pub enum NetUpdate {
+    /// The other side connected and speaks the same protocol.
+    Connected,
+    /// The host sent the battle to play. Guest only.
+    Start { level: i32, seed: u64, casual: bool, events: Vec<GameEvent> },
+    /// The other side gave an order, to be passed to `apply_order`.
+    Order(GameEvent),
+    /// The connection was lost. The session keeps trying to get it back.
+    Lost(NetError),
+    /// The other side's battle no longer matches this one, as of the given turn.
+    Desync(u32),
+}
+
+///
+/// One machine's end of a network battle, played in lockstep: both machines run the whole
+/// battle, and only the orders each player gives are sent across, along with a hash of the
+/// battle at every checkpoint so that any difference is caught.
+///
+pub struct NetSession {
+    role: Role,
+    /// Where the guest connects to, or where the host listens.
+    address: SocketAddr,
+    /// Host only.
+    listener: Option<TcpListener>,
+    connection: Option<Connection>,
+    /// Whether the other side has said hello on the current connection.
+    greeted: bool,
+    /// Seconds until the guest next tries to reach the host.
+    retry_in: f64,
+    /// How many of the battle's logged orders the other side already has.
+    synced: usize,
+    /// The last checkpoint this side hashed.
+    hashed: Option<Checkpoint>,
+    /// Hashes waiting for the other side's hash at the same checkpoint.
+    local_hashes: BTreeMap<Checkpoint, u64>,
+    remote_hashes: BTreeMap<Checkpoint, u64>,
+}
+
+impl NetSession {
+    fn new(role: Role, address: SocketAddr, listener: Option<TcpListener>) -> Self {
+        Self {
+            role,
+            address,
+            listener,
+            connection: None,
+            greeted: false,
+            retry_in: 0.0,
+            synced: 0,
+            hashed: None,
+            local_hashes: BTreeMap::new(),
+            remote_hashes: BTreeMap::new(),
+        }
+    }
+
+    ///
+    /// Starts listening for a guest on every interface.
+    ///
+    pub fn host(port: u16) -> Result<Self, NetError> {
+        let listener = TcpListener::bind(("0.0.0.0", port)).map_err(NetError::Io)?;
+        listener.set_nonblocking(true).map_err(NetError::Io)?;
+        let address = listener.local_addr().map_err(NetError::Io)?;
+        Ok(Self::new(Role::Host, address, Some(listener)))
+    }
+
+    ///
+    /// Prepares to join the host at `address`. Nothing is sent until the first `poll`.
+    ///
+    pub fn join(address: SocketAddr) -> Self {
+        Self::new(Role::Guest, address, None)
+    }
+
+    pub fn get_role(&self) -> Role {
+        self.role
+    }
+
+    pub fn get_address(&self) -> SocketAddr {
+        self.address
+    }
+
+    ///
+    /// # Returns
+    /// * Whether the other side is connected and has said hello.
+    ///
+    pub fn is_connected(&self) -> bool {
+        self.connection.is_some() && self.greeted
+    }
+
+    ///
+    /// # Returns
+    /// * Whether the other side has confirmed every state hash this side sent.
+    ///
+    pub fn is_in_step(&self) -> bool {
+        self.local_hashes.is_empty()
+    }
+
+    ///
+    /// Drops the connection. The session goes on trying to get it back when polled.
+    ///
+    pub fn disconnect(&mut self) {
+        self.connection = None;
+        self.greeted = false;
+        self.retry_in = RETRY_INTERVAL;
+    }
+
+    ///
+    /// Connects if not connected, then reads whatever the other side has sent. Never waits.
+    ///
+    /// # Arguments
+    /// * `dt` - Seconds elapsed since the last poll.
+    ///
+    pub fn poll(&mut self, dt: f64) -> Vec<NetUpdate> {
+        let mut updates = Vec::new();
+        if self.connection.is_none() && !self.try_connect(dt) {
+            return updates;
+        }
+        let received = self.connection.as_mut().map_or(Ok(Vec::new()), Connection::receive);
+        let result = received.and_then(|messages| {
+            messages.into_iter().try_for_each(|message| self.handle(message, &mut updates))
+        });
+        match result {
+            Ok(()) => {}
+            Err(NetError::Desync(turn)) => updates.push(NetUpdate::Desync(turn)),
+            Err(e) => {
+                self.disconnect();
+                updates.push(NetUpdate::Lost(e));
+            }
+        }
+        updates
+    }
+
+    ///
+    /// # Returns
+    /// * Whether a new connection was made.
+    ///
+    fn try_connect(&mut self, dt: f64) -> bool {
+        let connection = match &self.listener {
+            Some(listener) => match listener.accept() {
+                Ok((stream, from)) => {
+                    println!("Guest connected from {}", from);
+                    Connection::new(stream).map_err(NetError::Io)
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return false,
+                Err(e) => Err(NetError::Io(e)),
+            },
+            None => {
+                self.retry_in -= dt;
+                if self.retry_in > 0.0 {
+                    return false;
+                }
+                self.retry_in = RETRY_INTERVAL;
+                Connection::connect(self.address)
+            }
+        };
+        let mut connection = match connection {
+            Ok(connection) => connection,
+            Err(e) => {
+                println!("Couldn't connect to the other player: {}", e);
+                return false;
+            }
+        };
+        if let Err(e) = connection.send(&Message::Hello { version: PROTOCOL_VERSION }) {
+            println!("Couldn't greet the other player: {}", e);
+            return false;
+        }
+        self.connection = Some(connection);
+        self.greeted = false;
+        // Hashes from before the connection dropped may never be answered.
+        self.local_hashes.clear();
+        self.remote_hashes.clear();
+        true
+    }
+
+    fn handle(&mut self, message: Message, updates: &mut Vec<NetUpdate>) -> Result<(), NetError> {
+        match message {
+            Message::Hello { version } if version != PROTOCOL_VERSION =>
+                return Err(NetError::IncompatibleVersion { found: version, expected: PROTOCOL_VERSION }),
+            Message::Hello { .. } => {
+                self.greeted = true;
+                updates.push(NetUpdate::Connected);
+            }
+            Message::Start { level, seed, casual, events } if self.role == Role::Guest =>
+                updates.push(NetUpdate::Start { level, seed, casual, events }),
+            Message::Start { .. } => println!("Ignoring a battle sent by the guest"),
+            Message::Order(event) => updates.push(NetUpdate::Order(event)),
+            Message::StateHash { checkpoint, hash } => {
+                self.remote_hashes.insert(checkpoint, hash);
+                self.compare_hashes()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn send(&mut self, message: &Message) -> Result<(), NetError> {
+        match self.connection.as_mut() {
+            Some(connection) if self.greeted => connection.send(message),
+            _ => Err(NetError::Disconnected),
+        }
+    }
+
+    ///
+    /// Sends the guest the battle to play, as it stands. Host only.
+    ///
+    pub fn send_start(&mut self, battle: &MidLevelScene) -> Result<(), NetError> {
+        self.send(&Message::Start {
+            level: battle.get_level(),
+            seed: battle.get_seed(),
+            casual: battle.is_casual(),
+            events: battle.get_log().to_vec(),
+        })?;
+        self.begin(battle);
+        Ok(())
+    }
+
+    ///
+    /// Takes `battle`, as both sides now have it, as the one being played.
+    ///
+    pub fn begin(&mut self, battle: &MidLevelScene) {
+        self.synced = battle.get_log().len();
+        self.hashed = Some(checkpoint(battle));
+        self.local_hashes.clear();
+        self.remote_hashes.clear();
+    }
+
+    ///
+    /// Sends the other side every order given here since the last call, and a state hash if
+    /// the battle reached a new checkpoint.
+    ///
+    /// # Returns
+    /// * `NetError::Desync` if the other side already sent a different hash for the checkpoint.
+    ///
+    pub fn sync(&mut self, battle: &MidLevelScene) -> Result<(), NetError> {
+        let log = battle.get_log();
+        for event in log[self.synced.min(log.len())..].iter() {
+            self.send(&Message::Order(*event))?;
+        }
+        self.synced = log.len();
+        let checkpoint = checkpoint(battle);
+        if self.hashed != Some(checkpoint) {
+            let hash = battle.state_hash();
+            self.hashed = Some(checkpoint);
+            self.local_hashes.insert(checkpoint, hash);
+            self.send(&Message::StateHash { checkpoint, hash })?;
+            self.compare_hashes()?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Carries out an order from the other side, once its own copy of the rules has allowed
+    /// it, after checking this copy allows it too.
+    ///
+    /// # Returns
+    /// * `NetError::Rejected` if it isn't the other side's phase or the order is against the rules.
+    ///
+    pub fn apply_order(&mut self, battle: &mut MidLevelScene, event: GameEvent) -> Result<(), NetError> {
+        if !event.is_order() || battle.is_local_phase() || battle.get_outcome().is_some() {
+            return Err(NetError::Rejected(event));
+        }
+        let logged = battle.get_log().len();
+        battle.receive_event(&event);
+        if battle.get_log().len() != logged + 1 {
+            return Err(NetError::Rejected(event));
+        }
+        self.synced = battle.get_log().len();
+        self.sync(battle)
+    }
+
+    fn compare_hashes(&mut self) -> Result<(), NetError> {
+        let answered: Vec<Checkpoint> = self.local_hashes.keys()
+            .filter(|checkpoint| self.remote_hashes.contains_key(checkpoint))
+            .copied()
+            .collect();
+        for checkpoint in answered {
+            let local = self.local_hashes.remove(&checkpoint);
+            if self.remote_hashes.remove(&checkpoint) != local {
+                return Err(NetError::Desync(checkpoint.0));
+            }
+        }
+        Ok(())
+    }
+}
+
+///
+/// Builds a network battle the same way on both machines.
+///
+/// # Arguments
+/// * `role`   - Which machine the battle is built on.
+/// * `events` - Orders already given in the battle, replayed onto it in order.
+///
+pub fn build_battle(role: Role, level: i32, seed: u64, casual: bool, events: &[GameEvent]) -> Result<MidLevelScene, NetError> {
+    let data = LevelData::load(level).map_err(NetError::Level)?;
+    let mut battle = MidLevelScene::new(level, &data, seed, &[]);
+    let teams: Vec<usize> = battle.get_sides().iter().map(|side| side.team).collect();
+    if teams.len() < 2 {
+        let reason = format!("level {} needs at least two teams for a network battle", level);
+        return Err(NetError::Level(LevelError::Invalid(reason)));
+    }
+    battle.set_casual(casual);
+    battle.set_sides(team::network(&teams, role.side_index()));
+    for event in events {
+        battle.receive_event(event);
+    }
+    Ok(battle)
+}
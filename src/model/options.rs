@@ -0,0 +1,89 @@
+use std::any::Any;
+use crate::model::button::Button;
+use crate::model::game::{GameError, GameEvent, Scene, UIElement};
+use crate::settings::{self, Setting};
+use crate::{WIDTH, HEIGHT, HALF_X};
+
+/// Width, in grid cells, of each button in the options scene.
+const OPTION_WIDTH: i32 = 7;
+
+///
+/// Lists every setting on a button showing its value. Clicking one moves it on to its next
+/// value, which takes effect straight away.
+///
+pub struct OptionsScene {
+    name: String,
+    activated: bool,
+    option_buttons: Vec<Button>,
+    back_button: Button,
+    /// What was wrong with the settings file, if anything. See `Settings::warnings`.
+    warnings: Vec<String>,
+}
+
+impl OptionsScene {
+    ///
+    /// Lays out a button per setting, labelled with the settings currently in effect. The
+    /// buttons share the rows between the title and the back button, a cell high each while
+    /// they fit.
+    ///
+    pub fn new() -> Self {
+        let current = settings::current();
+        let x = ((WIDTH - OPTION_WIDTH) / 2) as f64;
+        let row_height = ((HEIGHT - 2) as f64 / Setting::ALL.len() as f64).min(1.0);
+        let option_buttons = Setting::ALL.iter().enumerate()
+            .map(|(row, &setting)| {
+                let y = 1.0 + row as f64 * row_height;
+                Button::new(&current.describe(setting), (x, y), (x + OPTION_WIDTH as f64, y + row_height),
+                            GameEvent::ChangeSetting(setting))
+            })
+            .collect();
+        let back_button = Button::new("Back", (HALF_X as f64, (HEIGHT - 1) as f64),
+                                      ((HALF_X + 2) as f64, HEIGHT as f64), GameEvent::CloseOptions);
+        let warnings = current.warnings.clone();
+        Self { name: "Options".to_string(), activated: false, option_buttons, back_button, warnings }
+    }
+
+    pub fn get_buttons(&self) -> impl Iterator<Item = &Button> {
+        self.option_buttons.iter().chain(std::iter::once(&self.back_button))
+    }
+
+    pub fn get_warnings(&self) -> &[String] {
+        &self.warnings
+    }
+}
+
+impl Scene for OptionsScene {
+    fn get_name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    fn activate(&mut self) -> Result<(), GameError> {
+        if self.activated {
+            return Err(GameError::SceneActivationError);
+        }
+        self.activated = true;
+        Ok(())
+    }
+
+    fn deactivate(&mut self) -> Result<(), GameError> {
+        if !self.activated {
+            return Err(GameError::SceneActivationError);
+        }
+        self.activated = false;
+        Ok(())
+    }
+
+    fn receive_event(&mut self, _event: &GameEvent) {}
+
+    fn get_ui_elements(&self) -> Vec<&dyn UIElement> {
+        self.get_buttons().map(|btn| btn as &dyn UIElement).collect()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -0,0 +1,95 @@
+use std::any::Any;
+use crate::model::button::Button;
+use crate::model::campaign::Campaign;
+use crate::model::game::{GameError, GameEvent, Scene, UIElement};
+use crate::{HEIGHT, HALF_X};
+
+/// Column, in grid cells, the promotion scene's class buttons start at.
+const PROMOTION_BUTTONS_LEFT: i32 = 7;
+/// Width, in grid cells, of each class button in the promotion scene.
+const PROMOTION_BUTTON_WIDTH: i32 = 3;
+
+///
+/// Shown between battles while any unit in the campaign roster is ready to be promoted: one
+/// row per such unit, with a button for each class it can be promoted into.
+///
+pub struct PromotionScene {
+    name: String,
+    activated: bool,
+    /// A line about each unit that can be promoted, and the buttons to promote it.
+    rows: Vec<(String, Vec<Button>)>,
+    done_button: Button,
+}
+
+impl PromotionScene {
+    pub fn new(campaign: &Campaign) -> Self {
+        let rows = campaign.get_roster().iter().enumerate()
+            .filter(|(_, unit)| unit.can_promote())
+            .enumerate()
+            .map(|(row, (index, unit))| {
+                let label = format!("{} - {} Lv {}", unit.get_name(), unit.get_class().get_name(), unit.get_level());
+                let y = (2 + row) as f64;
+                let buttons = unit.get_class().promotions().iter().enumerate()
+                    .map(|(column, &class)| {
+                        let left = (PROMOTION_BUTTONS_LEFT + column as i32 * (PROMOTION_BUTTON_WIDTH + 1)) as f64;
+                        Button::new(class.get_name(), (left, y), (left + PROMOTION_BUTTON_WIDTH as f64, y + 1.0),
+                                    GameEvent::Promote(index, class))
+                    })
+                    .collect();
+                (label, buttons)
+            })
+            .collect();
+        let top = (HEIGHT - 2) as f64;
+        let done_button = Button::new("Done", (HALF_X as f64, top), ((HALF_X + 2) as f64, top + 1.0),
+                                      GameEvent::FinishPromotion);
+        Self { name: "Promotion".to_string(), activated: false, rows, done_button }
+    }
+
+    pub fn get_rows(&self) -> &[(String, Vec<Button>)] {
+        &self.rows
+    }
+
+    pub fn get_done_button(&self) -> &Button {
+        &self.done_button
+    }
+}
+
+impl Scene for PromotionScene {
+    fn get_name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    fn activate(&mut self) -> Result<(), GameError> {
+        if self.activated {
+            return Err(GameError::SceneActivationError);
+        }
+        self.activated = true;
+        Ok(())
+    }
+
+    fn deactivate(&mut self) -> Result<(), GameError> {
+        if !self.activated {
+            return Err(GameError::SceneActivationError);
+        }
+        self.activated = false;
+        Ok(())
+    }
+
+    fn receive_event(&mut self, _event: &GameEvent) {}
+
+    fn get_ui_elements(&self) -> Vec<&dyn UIElement> {
+        let mut elements: Vec<&dyn UIElement> = self.rows.iter()
+            .flat_map(|(_, buttons)| buttons.iter().map(|b| b as &dyn UIElement))
+            .collect();
+        elements.push(&self.done_button);
+        elements
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -0,0 +1,72 @@
+use std::any::Any;
+use crate::HALF_Y;
+use crate::model::button::{button_column, Button};
+use crate::model::game::{GameError, GameEvent, Scene, UIElement};
+use crate::model::net::{NetSession, Role};
+
+///
+/// Covers a network battle while the connection is down. The session keeps trying to get it
+/// back, and the battle picks up where it left off once it does.
+///
+pub struct ReconnectScene {
+    name: String,
+    activated: bool,
+    message: String,
+    button: Button,
+}
+
+impl ReconnectScene {
+    pub fn new(session: &NetSession) -> Self {
+        let message = match session.get_role() {
+            Role::Host => format!("Connection lost. Waiting for the guest to rejoin on port {}",
+                                  session.get_address().port()),
+            Role::Guest => format!("Connection lost. Reconnecting to {}", session.get_address()),
+        };
+        let button = button_column(HALF_Y + 2, vec![("Leave".to_string(), GameEvent::LeaveNetwork, true)]).remove(0);
+        Self { name: "Reconnect".to_string(), activated: false, message, button }
+    }
+
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn get_button(&self) -> &Button {
+        &self.button
+    }
+}
+
+impl Scene for ReconnectScene {
+    fn get_name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    fn activate(&mut self) -> Result<(), GameError> {
+        if self.activated {
+            return Err(GameError::SceneActivationError);
+        }
+        self.activated = true;
+        Ok(())
+    }
+
+    fn deactivate(&mut self) -> Result<(), GameError> {
+        if !self.activated {
+            return Err(GameError::SceneActivationError);
+        }
+        self.activated = false;
+        Ok(())
+    }
+
+    fn receive_event(&mut self, _event: &GameEvent) {}
+
+    fn get_ui_elements(&self) -> Vec<&dyn UIElement> {
+        vec![&self.button]
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
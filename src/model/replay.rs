@@ -0,0 +1,185 @@
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::model::game::GameEvent;
+use crate::model::inlevel::team::Side;
+use crate::model::inlevel::unit::Unit;
+use crate::model::save::{self, SaveError};
+
+/// Version written into every replay file. Bump it whenever replays change shape, or the
+/// rules change in a way that would make old replays play out differently.
+pub const REPLAY_VERSION: u32 = 8;
+
+/// Where the last battle's replay is kept.
+pub const REPLAY_PATH: &str = "saves/replay.json";
+
+/// How many events a replay plays per second at 1x speed.
+const EVENTS_PER_SECOND: f64 = 2.0;
+
+/// The playback speeds a replay can be switched between.
+const SPEEDS: [f64; 5] = [0.5, 1.0, 2.0, 4.0, 8.0];
+
+///
+/// Everything needed to play a battle out again: how it started, and every order the player
+/// gave, in order. The computer's turns aren't stored, since they follow from the rest.
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub level: i32,
+    pub seed: u64,
+    /// The units deployed for the player, as passed to `MidLevelScene::new`.
+    pub roster: Vec<Unit>,
+    /// Whether the battle was played in casual mode. See `MidLevelScene::is_casual`.
+    #[serde(default)]
+    pub casual: bool,
+    /// Who played each team. Replays that leave it out were the player against the computer.
+    #[serde(default)]
+    pub sides: Vec<Side>,
+    pub events: Vec<GameEvent>,
+}
+
+impl Replay {
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SaveError> {
+        save::write_versioned(self, REPLAY_VERSION, path)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Replay, SaveError> {
+        save::read_versioned(path, REPLAY_VERSION)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReplayControl {
+    TogglePause,
+    /// Plays the next event, while paused.
+    Step,
+    Faster,
+    Slower,
+    Stop,
+}
+
+///
+/// Hands out a replay's events as time passes, or one at a time while paused.
+///
+pub struct ReplayPlayer {
+    replay: Replay,
+    /// Index of the next event to play.
+    next: usize,
+    playing: bool,
+    /// Index into `SPEEDS`.
+    speed: usize,
+    /// Seconds since the last event was played.
+    timer: f64,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Self {
+        Self { replay, next: 0, playing: true, speed: 1, timer: 0.0 }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.replay.events.len()
+    }
+
+    pub fn get_speed(&self) -> f64 {
+        SPEEDS[self.speed]
+    }
+
+    ///
+    /// # Returns
+    /// * How many events have been played, and how many there are in all.
+    ///
+    pub fn get_progress(&self) -> (usize, usize) {
+        (self.next, self.replay.events.len())
+    }
+
+    ///
+    /// Applies a playback control. `Stop` is left to the game, which ends the replay.
+    ///
+    /// # Returns
+    /// * The event to play now, when stepping.
+    ///
+    pub fn control(&mut self, control: ReplayControl) -> Option<GameEvent> {
+        match control {
+            ReplayControl::TogglePause => self.playing = !self.playing,
+            ReplayControl::Step if !self.playing => return self.pop(),
+            ReplayControl::Faster => self.speed = (self.speed + 1).min(SPEEDS.len() - 1),
+            ReplayControl::Slower => self.speed = self.speed.saturating_sub(1),
+            _ => {}
+        }
+        None
+    }
+
+    ///
+    /// Moves playback on by `dt` seconds.
+    ///
+    /// # Returns
+    /// * The events that came due, in order.
+    ///
+    pub fn advance(&mut self, dt: f64) -> Vec<GameEvent> {
+        let mut due = Vec::new();
+        if !self.playing {
+            return due;
+        }
+        self.timer += dt * self.get_speed();
+        let interval = 1.0 / EVENTS_PER_SECOND;
+        while self.timer >= interval {
+            self.timer -= interval;
+            match self.pop() {
+                Some(event) => due.push(event),
+                None => break,
+            }
+        }
+        due
+    }
+
+    fn pop(&mut self) -> Option<GameEvent> {
+        let event = self.replay.events.get(self.next).copied();
+        if event.is_some() {
+            self.next += 1;
+        }
+        event
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::game::Scene;
+    use crate::model::midlevel::MidLevelScene;
+    use crate::model::inlevel::level::LevelData;
+
+    /// The first level, after the player has ended a few turns and the computer has played
+    /// its phases.
+    fn played_battle(data: &LevelData, seed: u64) -> MidLevelScene {
+        let mut battle = MidLevelScene::new(1, data, seed, &[]);
+        for _ in 0..3 {
+            battle.receive_event(&GameEvent::EndTurn);
+        }
+        battle
+    }
+
+    #[test]
+    fn replaying_a_battle_reproduces_its_state() {
+        let data = LevelData::load(1).unwrap();
+        let battle = played_battle(&data, 42);
+        let replay = battle.to_replay();
+        assert!(battle.get_turn() > 1 && !replay.events.is_empty());
+
+        let mut again = MidLevelScene::new(replay.level, &data, replay.seed, &replay.roster);
+        for event in &replay.events {
+            again.receive_event(event);
+        }
+        assert_eq!(again.get_turn(), battle.get_turn());
+        assert_eq!(again.state_hash(), battle.state_hash());
+    }
+
+    #[test]
+    fn battles_from_other_seeds_hash_differently() {
+        let data = LevelData::load(1).unwrap();
+        assert_ne!(played_battle(&data, 42).state_hash(), played_battle(&data, 43).state_hash());
+    }
+}
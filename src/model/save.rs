@@ -0,0 +1,180 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use crate::model::midlevel::MidLevelScene;
+
+/// Version written into every battle save. Bump it whenever the saved battle state changes
+/// shape, so old files are rejected instead of loading into a broken battle.
+pub const BATTLE_SAVE_VERSION: u32 = 7;
+
+/// Where the battle is saved when no save slot is involved.
+pub const QUICKSAVE_PATH: &str = "saves/quicksave.json";
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+    IncompatibleVersion { found: u32, expected: u32 },
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "couldn't access save file: {}", e),
+            SaveError::Parse(e) => write!(f, "save file is corrupted: {}", e),
+            SaveError::IncompatibleVersion { found, expected } => write!(f,
+                "save file is from version {} of the save format, but this game only reads version {}",
+                found, expected),
+        }
+    }
+}
+
+///
+/// Just the version of a save, read before the rest so that incompatible files are
+/// reported as such rather than as corrupted.
+///
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+#[derive(Serialize)]
+struct VersionedRef<'a, T> {
+    version: u32,
+    #[serde(flatten)]
+    contents: &'a T,
+}
+
+///
+/// Writes `contents` to `path` as JSON tagged with `version`, creating its directory if needed.
+///
+pub fn write_versioned<T: Serialize, P: AsRef<Path>>(contents: &T, version: u32, path: P) -> Result<(), SaveError> {
+    let path = path.as_ref();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(SaveError::Io)?;
+    }
+    let json = serde_json::to_string_pretty(&VersionedRef { version, contents })
+        .map_err(SaveError::Parse)?;
+    fs::write(path, json).map_err(SaveError::Io)
+}
+
+///
+/// Reads a file written by `write_versioned`.
+///
+/// # Returns
+/// * The contents, if the file is readable and was written with `expected` version.
+///
+pub fn read_versioned<T: DeserializeOwned, P: AsRef<Path>>(path: P, expected: u32) -> Result<T, SaveError> {
+    let json = fs::read_to_string(path).map_err(SaveError::Io)?;
+    let header: SaveHeader = serde_json::from_str(&json).map_err(SaveError::Parse)?;
+    if header.version != expected {
+        return Err(SaveError::IncompatibleVersion { found: header.version, expected });
+    }
+    serde_json::from_str(&json).map_err(SaveError::Parse)
+}
+
+#[derive(Serialize, Deserialize)]
+struct BattleSave<T> {
+    battle: T,
+}
+
+///
+/// Writes the full state of a battle to `path`, creating its directory if needed.
+///
+pub fn save_battle<P: AsRef<Path>>(battle: &MidLevelScene, path: P) -> Result<(), SaveError> {
+    write_versioned(&BattleSave { battle }, BATTLE_SAVE_VERSION, path)
+}
+
+///
+/// Reads a battle saved by `save_battle`.
+///
+/// # Returns
+/// * The battle exactly as it was saved, or why it couldn't be restored.
+///
+pub fn load_battle<P: AsRef<Path>>(path: P) -> Result<MidLevelScene, SaveError> {
+    let save: BattleSave<MidLevelScene> = read_versioned(path, BATTLE_SAVE_VERSION)?;
+    Ok(save.battle)
+}
+
+///
+/// # Returns
+/// * A Unix timestamp as "YYYY-MM-DD HH:MM UTC".
+///
+pub fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let (hour, minute) = ((secs % 86_400) / 3600, (secs % 3600) / 60);
+    // Days since the epoch to a civil date, after Howard Hinnant's `civil_from_days`.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02} {:02}:{:02} UTC", year, month, day, hour, minute)
+}
+
+///
+/// # Returns
+/// * A duration in seconds as "H:MM:SS".
+///
+pub fn format_play_time(seconds: f64) -> String {
+    let secs = seconds.max(0.0) as u64;
+    format!("{}:{:02}:{:02}", secs / 3600, (secs % 3600) / 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::path::PathBuf;
+
+    /// A path in the temp directory, unique to this test run, that is removed when dropped.
+    struct TempPath(PathBuf);
+
+    impl TempPath {
+        fn new(name: &str) -> Self {
+            Self(env::temp_dir().join(format!("ranger-royale-{}-{}.json", std::process::id(), name)))
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Contents {
+        value: u32,
+    }
+
+    #[test]
+    fn reads_back_what_it_wrote() {
+        let path = TempPath::new("round-trip");
+        write_versioned(&Contents { value: 7 }, 3, &path.0).unwrap();
+        assert_eq!(read_versioned::<Contents, _>(&path.0, 3).unwrap(), Contents { value: 7 });
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let path = TempPath::new("other-version");
+        write_versioned(&Contents { value: 7 }, 2, &path.0).unwrap();
+        match read_versioned::<Contents, _>(&path.0, 3) {
+            Err(SaveError::IncompatibleVersion { found: 2, expected: 3 }) => {}
+            other => panic!("expected a version mismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_files_without_a_version() {
+        let path = TempPath::new("no-version");
+        fs::write(&path.0, r#"{ "value": 7 }"#).unwrap();
+        assert!(matches!(read_versioned::<Contents, _>(&path.0, 3), Err(SaveError::Parse(_))));
+    }
+}
@@ -0,0 +1,148 @@
+use std::any::Any;
+use crate::model::button::Button;
+use crate::model::campaign::{self, SlotState, NUM_SLOTS};
+use crate::model::dialog::Dialog;
+use crate::model::game::{GameError, GameEvent, Scene, SlotAction, UIElement};
+use crate::{HEIGHT, HALF_X};
+
+/// Width, in grid cells, of the description shown for each save slot.
+const SLOT_INFO_WIDTH: i32 = 7;
+/// Height, in grid cells, of each save slot's row.
+const SLOT_ROW_HEIGHT: i32 = 2;
+
+///
+/// Lists the save slots, with buttons to save to, load from or delete each of them. Every
+/// action asks for confirmation first.
+///
+pub struct SaveSlotScene {
+    name: String,
+    activated: bool,
+    /// Whether the menu was opened from a battle, which is then saved along with the campaign.
+    paused: bool,
+    slots: Vec<(u32, SlotState)>,
+    /// Save, Load and Delete, for each slot in turn.
+    slot_buttons: Vec<Button>,
+    back_button: Button,
+    dialog: Option<Dialog>,
+}
+
+impl SaveSlotScene {
+    ///
+    /// Reads every slot and lays out a row for each.
+    ///
+    pub fn new(paused: bool) -> Self {
+        let slots: Vec<(u32, SlotState)> = (1..=NUM_SLOTS).map(|slot| (slot, campaign::peek_slot(slot))).collect();
+        let mut slot_buttons = Vec::new();
+        for (row, (slot, state)) in slots.iter().enumerate() {
+            let y = (1 + row as i32 * SLOT_ROW_HEIGHT) as f64;
+            let actions = [(SlotAction::Save, "Save", true),
+                           (SlotAction::Load, "Load", matches!(state, SlotState::Saved(_))),
+                           (SlotAction::Delete, "Delete", *state != SlotState::Empty)];
+            for (column, (action, label, enabled)) in actions.into_iter().enumerate() {
+                let x = (1 + SLOT_INFO_WIDTH + 2 * column as i32) as f64;
+                let mut button = Button::new(label, (x, y), (x + 2.0, y + SLOT_ROW_HEIGHT as f64),
+                                             GameEvent::SlotAction(action, *slot));
+                button.set_enabled(enabled);
+                slot_buttons.push(button);
+            }
+        }
+        let back_button = Button::new("Back", (HALF_X as f64, (HEIGHT - 1) as f64),
+                                      ((HALF_X + 2) as f64, HEIGHT as f64), GameEvent::CloseSaveSlots);
+        Self {
+            name: "Save Slots".to_string(),
+            activated: false,
+            paused,
+            slots,
+            slot_buttons,
+            back_button,
+            dialog: None,
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn get_slots(&self) -> &[(u32, SlotState)] {
+        &self.slots
+    }
+
+    ///
+    /// # Returns
+    /// * The top left and bottom right, in grid cells, of the row describing the slot at `index`.
+    ///
+    pub fn get_slot_area(&self, index: usize) -> ((i32, i32), (i32, i32)) {
+        let y = 1 + index as i32 * SLOT_ROW_HEIGHT;
+        ((1, y), (1 + SLOT_INFO_WIDTH, y + SLOT_ROW_HEIGHT))
+    }
+
+    pub fn get_buttons(&self) -> impl Iterator<Item = &Button> {
+        self.slot_buttons.iter().chain(std::iter::once(&self.back_button))
+    }
+
+    pub fn get_dialog(&self) -> Option<&Dialog> {
+        self.dialog.as_ref()
+    }
+
+    ///
+    /// Asks the player to confirm `action` on `slot`.
+    ///
+    fn confirm(&mut self, action: SlotAction, slot: u32) {
+        let occupied = self.slots.iter().any(|(s, state)| *s == slot && *state != SlotState::Empty);
+        let (message, label) = match action {
+            SlotAction::Save if occupied =>
+                (format!("Overwrite slot {}? What is saved there will be lost.", slot), "Overwrite"),
+            SlotAction::Save => (format!("Save to slot {}?", slot), "Save"),
+            SlotAction::Load if self.paused =>
+                (format!("Load slot {}? Unsaved progress in this battle will be lost.", slot), "Load"),
+            SlotAction::Load => (format!("Load slot {}?", slot), "Load"),
+            SlotAction::Delete => (format!("Delete slot {}? This can't be undone.", slot), "Delete"),
+        };
+        self.dialog = Some(Dialog::new(&message, label, GameEvent::ConfirmSlotAction(action, slot)));
+    }
+}
+
+impl Scene for SaveSlotScene {
+    fn get_name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    fn activate(&mut self) -> Result<(), GameError> {
+        if self.activated {
+            return Err(GameError::SceneActivationError);
+        }
+        self.activated = true;
+        Ok(())
+    }
+
+    fn deactivate(&mut self) -> Result<(), GameError> {
+        if !self.activated {
+            return Err(GameError::SceneActivationError);
+        }
+        self.activated = false;
+        Ok(())
+    }
+
+    fn receive_event(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::SlotAction(action, slot) => self.confirm(*action, *slot),
+            GameEvent::Cancel => self.dialog = None,
+            _ => {}
+        }
+    }
+
+    fn get_ui_elements(&self) -> Vec<&dyn UIElement> {
+        match &self.dialog {
+            Some(dialog) => dialog.get_ui_elements(),
+            None => self.get_buttons().map(|btn| btn as &dyn UIElement).collect(),
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
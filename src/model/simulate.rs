@@ -0,0 +1,222 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::model::midlevel::BattleOutcome;
+use crate::model::headless::{self, BattleResult};
+use crate::model::inlevel::level::{LevelData, LevelError};
+use crate::model::inlevel::unit::Class;
+
+/// How many battles a simulation plays when not told otherwise.
+pub const DEFAULT_BATTLES: u32 = 1000;
+
+/// Column headings of the CSV a simulation writes, one row per class.
+const CSV_HEADER: &str = "class,appearances,wins,win_rate,avg_damage_per_battle,strikes,hit_rate,\
+crit_rate,avg_turns_to_win,kills,deaths";
+
+///
+/// A batch of battles between two fixed sides, used to see how the classes measure up.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Simulation {
+    /// The levels to fight on, taken in turn, battle after battle.
+    pub levels: Vec<i32>,
+    /// The classes making up each side. One unit each is a duel.
+    pub sides: [Vec<Class>; 2],
+    pub battles: u32,
+    /// Battle `i` is played with seed `seed + i`, so a whole simulation can be repeated.
+    pub seed: u64,
+}
+
+///
+/// Totals for every unit of one class across a simulation.
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClassStats {
+    /// How many units of the class were fielded, counting every battle.
+    pub appearances: u32,
+    /// How many of those were on the side that won.
+    pub wins: u32,
+    /// The turns taken to win, summed over `wins`.
+    pub turns_to_win: u32,
+    pub strikes: u32,
+    pub hits: u32,
+    pub crits: u32,
+    pub damage_dealt: u32,
+    pub kills: u32,
+    pub deaths: u32,
+}
+
+fn ratio(part: u32, whole: u32) -> f64 {
+    if whole == 0 { 0.0 } else { part as f64 / whole as f64 }
+}
+
+impl ClassStats {
+    pub fn win_rate(&self) -> f64 {
+        ratio(self.wins, self.appearances)
+    }
+
+    pub fn hit_rate(&self) -> f64 {
+        ratio(self.hits, self.strikes)
+    }
+
+    pub fn crit_rate(&self) -> f64 {
+        ratio(self.crits, self.hits)
+    }
+
+    ///
+    /// # Returns
+    /// * The damage one unit of the class dealt in an average battle.
+    ///
+    pub fn avg_damage(&self) -> f64 {
+        ratio(self.damage_dealt, self.appearances)
+    }
+
+    ///
+    /// # Returns
+    /// * How many turns the class's winning battles took on average, if it won any.
+    ///
+    pub fn avg_turns_to_win(&self) -> Option<f64> {
+        (self.wins > 0).then(|| ratio(self.turns_to_win, self.wins))
+    }
+}
+
+///
+/// What a simulation found.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationReport {
+    pub battles: u32,
+    /// Battles won by each side.
+    pub side_wins: [u32; 2],
+    /// Battles still undecided after `headless::MAX_TURNS` turns.
+    pub draws: u32,
+    /// Every class that was fielded, in `Class::ALL` order.
+    pub classes: Vec<(Class, ClassStats)>,
+}
+
+impl Simulation {
+    ///
+    /// Plays every battle of the simulation. The sides swap starting positions every other
+    /// battle, so neither keeps the better ground.
+    ///
+    /// # Returns
+    /// * The totals, or an error if a level can't be loaded or can't fit a side.
+    ///
+    pub fn run(&self) -> Result<SimulationReport, LevelError> {
+        let mut layouts = Vec::new();
+        for &level in self.levels.iter() {
+            let data = LevelData::load(level)?;
+            let sides = [self.sides[0].as_slice(), self.sides[1].as_slice()];
+            let swapped = [sides[1], sides[0]];
+            layouts.push((level, [data.with_sides(sides)?, data.with_sides(swapped)?]));
+        }
+        if layouts.is_empty() {
+            return Err(LevelError::Invalid("no levels to simulate on".to_string()));
+        }
+
+        let mut report = SimulationReport {
+            battles: self.battles,
+            side_wins: [0, 0],
+            draws: 0,
+            classes: Class::ALL.into_iter()
+                .filter(|class| self.sides.iter().flatten().any(|c| c == class))
+                .map(|class| (class, ClassStats::default()))
+                .collect(),
+        };
+        for i in 0..self.battles {
+            let (level, data) = &layouts[i as usize % layouts.len()];
+            let swapped = i % 2 == 1;
+            let result = headless::play_battle(*level, &data[swapped as usize],
+                                               self.seed.wrapping_add(i as u64), &[], true);
+            // The side that took team 0's starting positions this battle.
+            let team_side = |team: usize| if (team == 0) != swapped { 0 } else { 1 };
+            report.record(&result, &self.sides, team_side);
+        }
+        Ok(report)
+    }
+}
+
+impl SimulationReport {
+    fn stats_mut(&mut self, class: Class) -> &mut ClassStats {
+        &mut self.classes.iter_mut().find(|(c, _)| *c == class).expect("every fielded class has stats").1
+    }
+
+    fn record(&mut self, result: &BattleResult, sides: &[Vec<Class>; 2], team_side: impl Fn(usize) -> usize) {
+        let winner = match result.outcome {
+            Some(BattleOutcome::Victory) => Some(team_side(0)),
+            Some(BattleOutcome::Defeat) => Some(team_side(1)),
+            None => None,
+        };
+        match winner {
+            Some(side) => self.side_wins[side] += 1,
+            None => self.draws += 1,
+        }
+        for (side, classes) in sides.iter().enumerate() {
+            for &class in classes {
+                let stats = self.stats_mut(class);
+                stats.appearances += 1;
+                if winner == Some(side) {
+                    stats.wins += 1;
+                    stats.turns_to_win += result.turns;
+                }
+            }
+        }
+        for fight in result.fights.iter() {
+            for strike in fight.report.strikes.iter() {
+                let striker = if strike.by_initiator { fight.attacker } else { fight.defender };
+                let stats = self.stats_mut(striker);
+                stats.strikes += 1;
+                stats.hits += strike.hit as u32;
+                stats.crits += strike.crit as u32;
+                stats.damage_dealt += strike.damage;
+            }
+            if fight.report.defender_killed {
+                self.stats_mut(fight.attacker).kills += 1;
+                self.stats_mut(fight.defender).deaths += 1;
+            }
+            if fight.report.attacker_killed {
+                self.stats_mut(fight.defender).kills += 1;
+                self.stats_mut(fight.attacker).deaths += 1;
+            }
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * The report as a table, for the console.
+    ///
+    pub fn describe(&self) -> String {
+        let mut text = format!("{} battles: side A won {}, side B won {}, {} draws\n",
+                               self.battles, self.side_wins[0], self.side_wins[1], self.draws);
+        let _ = writeln!(text, "{:<10} {:>8} {:>10} {:>8} {:>8} {:>13}",
+                         "Class", "Win rate", "Avg damage", "Hit rate", "Crit", "Turns to win");
+        for (class, stats) in self.classes.iter() {
+            let turns = stats.avg_turns_to_win().map_or("-".to_string(), |t| format!("{:.1}", t));
+            let _ = writeln!(text, "{:<10} {:>7.1}% {:>10.1} {:>7.1}% {:>7.1}% {:>13}",
+                             class.get_name(), stats.win_rate() * 100.0, stats.avg_damage(),
+                             stats.hit_rate() * 100.0, stats.crit_rate() * 100.0, turns);
+        }
+        text
+    }
+
+    ///
+    /// # Returns
+    /// * The report as CSV, one row per class.
+    ///
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!("{}\n", CSV_HEADER);
+        for (class, stats) in self.classes.iter() {
+            let turns = stats.avg_turns_to_win().map_or(String::new(), |t| format!("{:.3}", t));
+            let _ = writeln!(csv, "{},{},{},{:.4},{:.3},{},{:.4},{:.4},{},{},{}",
+                             class.get_name(), stats.appearances, stats.wins, stats.win_rate(),
+                             stats.avg_damage(), stats.strikes, stats.hit_rate(), stats.crit_rate(),
+                             turns, stats.kills, stats.deaths);
+        }
+        csv
+    }
+
+    pub fn write_csv<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_csv())
+    }
+}
@@ -0,0 +1,77 @@
+use std::any::Any;
+use crate::model::button::Button;
+use crate::model::game::{GameError, GameEvent, Scene, UIElement};
+use crate::{HALF_X, HALF_Y};
+
+pub struct StartScene {
+    name: String,
+    /// Start first, then the other menus, top to bottom.
+    buttons: Vec<Button>,
+    activated: bool,
+}
+
+impl StartScene {
+    pub fn new() -> Self {
+        let entries = [("Start", GameEvent::StartGame),
+                       ("Save Slots", GameEvent::OpenSaveSlots),
+                       ("Multiplayer", GameEvent::OpenLobby),
+                       ("Options", GameEvent::OpenOptions),
+                       ("Watch Replay", GameEvent::WatchReplay)];
+        // One row of space between buttons, with the column centered on the screen.
+        let top = HALF_Y + 1 - entries.len() as i32;
+        let buttons = entries.iter().enumerate()
+            .map(|(i, (label, event))| {
+                let y = (top + 2 * i as i32) as f64;
+                Button::new(label, (HALF_X as f64, y), ((HALF_X + 2) as f64, y + 1.0), *event)
+            })
+            .collect();
+        Self { name: "Start".to_string(), buttons, activated: false }
+    }
+
+    pub fn get_buttons(&self) -> &Vec<Button> {
+        &self.buttons
+    }
+}
+
+impl Scene for StartScene {
+    fn get_name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    fn activate(&mut self) -> Result<(), GameError> {
+        if self.activated {
+            return Err(GameError::SceneActivationError);
+        }
+        self.activated = true;
+        Ok(())
+    }
+
+    fn deactivate(&mut self) -> Result<(), GameError> {
+        if !self.activated {
+            return Err(GameError::SceneActivationError);
+        }
+        self.activated = false;
+        Ok(())
+    }
+
+    fn receive_event(&mut self, event: &GameEvent) {
+        if !self.activated {
+            println!("StartScene received event while deactivated: {:?}", event);
+        }
+    }
+
+    fn get_ui_elements(&self) -> Vec<&dyn UIElement> {
+        self.buttons
+            .iter()
+            .map(|btn| btn as &dyn UIElement)
+            .collect()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -0,0 +1,109 @@
+///
+/// How one scene gives way to the next.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionKind {
+    /// Swap scenes instantly.
+    Cut,
+    /// Fade the old scene out to black, then fade the new one in.
+    FadeToBlack,
+    /// Blend the new scene in over the old one.
+    Crossfade,
+    /// Sweep the new scene in over the old one from left to right.
+    Wipe,
+}
+
+///
+/// The kind and length of a transition, chosen each time the scene is switched.
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransitionStyle {
+    pub kind: TransitionKind,
+    /// Length of the transition in seconds.
+    pub duration: f64,
+}
+
+///
+/// A transition in progress between two scenes. Both scenes are rendered until it finishes.
+///
+pub struct Transition {
+    style: TransitionStyle,
+    from: String,
+    to: String,
+    elapsed: f64,
+}
+
+impl Transition {
+    pub fn new(style: TransitionStyle, from: &str, to: &str) -> Self {
+        Self { style, from: from.to_string(), to: to.to_string(), elapsed: 0.0 }
+    }
+
+    ///
+    /// Moves the transition forward by `dt` seconds.
+    ///
+    /// # Returns
+    /// * Whether the transition has finished.
+    ///
+    pub fn advance(&mut self, dt: f64) -> bool {
+        self.elapsed += dt;
+        self.is_finished()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.style.duration
+    }
+
+    ///
+    /// # Returns
+    /// * How far along the transition is, from 0.0 (just started) to 1.0 (done).
+    ///
+    pub fn progress(&self) -> f64 {
+        if self.style.duration <= 0.0 {
+            return 1.0;
+        }
+        (self.elapsed / self.style.duration).clamp(0.0, 1.0)
+    }
+
+    pub fn get_kind(&self) -> TransitionKind {
+        self.style.kind
+    }
+
+    pub fn get_from(&self) -> &str {
+        &self.from
+    }
+
+    pub fn get_to(&self) -> &str {
+        &self.to
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FADE: TransitionStyle = TransitionStyle { kind: TransitionKind::FadeToBlack, duration: 0.5 };
+
+    #[test]
+    fn runs_for_its_duration() {
+        let mut transition = Transition::new(FADE, "Start", "Level Selection");
+        assert!(!transition.advance(0.2));
+        assert!((transition.progress() - 0.4).abs() < 1e-9);
+        assert!(transition.advance(0.3));
+        assert_eq!(transition.progress(), 1.0);
+    }
+
+    #[test]
+    fn never_reports_progress_past_the_end() {
+        let mut transition = Transition::new(FADE, "Start", "Level Selection");
+        transition.advance(5.0);
+        assert_eq!(transition.progress(), 1.0);
+    }
+
+    #[test]
+    fn a_transition_with_no_duration_is_already_finished() {
+        let cut = TransitionStyle { kind: TransitionKind::Cut, duration: 0.0 };
+        let transition = Transition::new(cut, "Start", "Level Selection");
+        assert!(transition.is_finished());
+        assert_eq!(transition.progress(), 1.0);
+    }
+}
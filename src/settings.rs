@@ -0,0 +1,320 @@
+//! SETTINGS.RS:
+//! The player's preferences, kept in a TOML file next to the saves. Unlike the
+//! theme these are edited in game, from the options scene, and take effect
+//! straight away.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::sync::{OnceLock, RwLock, RwLockReadGuard};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::net::DEFAULT_PORT;
+
+/// Where the settings are read from and written to.
+pub const SETTINGS_PATH: &str = "settings.toml";
+
+/// The animation speeds the options scene cycles through.
+pub const ANIMATION_SPEEDS: [f64; 4] = [0.5, 1.0, 2.0, 4.0];
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Write(toml::ser::Error),
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::Io(e) => write!(f, "couldn't access settings file: {}", e),
+            SettingsError::Parse(e) => write!(f, "malformed settings file: {}", e),
+            SettingsError::Write(e) => write!(f, "couldn't write settings: {}", e),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WindowSize {
+    Small,
+    Medium,
+    Large,
+}
+
+impl WindowSize {
+    ///
+    /// # Returns
+    /// * How much bigger than the game's own layout the window is.
+    ///
+    pub fn scale(&self) -> f64 {
+        match self {
+            WindowSize::Small => 0.75,
+            WindowSize::Medium => 1.0,
+            WindowSize::Large => 1.25,
+        }
+    }
+
+    pub fn next(&self) -> WindowSize {
+        match self {
+            WindowSize::Small => WindowSize::Medium,
+            WindowSize::Medium => WindowSize::Large,
+            WindowSize::Large => WindowSize::Small,
+        }
+    }
+
+    pub fn get_description(&self) -> &str {
+        match self {
+            WindowSize::Small => "Small",
+            WindowSize::Medium => "Medium",
+            WindowSize::Large => "Large",
+        }
+    }
+}
+
+///
+/// One of the settings the options scene can change.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Setting {
+    WindowSize,
+    Fullscreen,
+    ExitOnEsc,
+    AnimationSpeed,
+    GridLines,
+    ConfirmEndTurn,
+    ThreatMap,
+    CasualMode,
+    ColorblindPalette,
+}
+
+impl Setting {
+    pub const ALL: [Setting; 9] = [Setting::WindowSize, Setting::Fullscreen, Setting::ExitOnEsc,
+        Setting::AnimationSpeed, Setting::GridLines, Setting::ConfirmEndTurn, Setting::ThreatMap,
+        Setting::CasualMode, Setting::ColorblindPalette];
+}
+
+///
+/// Every setting. Anything missing from the file keeps its default.
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub window_size: WindowSize,
+    pub fullscreen: bool,
+    /// Whether pressing Escape closes the game.
+    pub exit_on_esc: bool,
+    /// How fast animations such as scene transitions play, 1.0 being normal speed.
+    pub animation_speed: f64,
+    /// Whether battles outline every cell.
+    pub show_grid_lines: bool,
+    /// Whether ending the player's phase asks for confirmation first.
+    pub confirm_end_turn: bool,
+    /// Whether battles start with the enemy threat map shown.
+    pub show_threat_map: bool,
+    /// Whether anything done during the player's phase can be taken back, not just moves.
+    pub casual_mode: bool,
+    /// Whether the game draws with the theme's colorblind-safe palette instead of its own.
+    pub colorblind_palette: bool,
+    /// The port network battles are hosted on. Only set in the file.
+    pub host_port: u16,
+    /// The address the lobby's Join button connects to. Only set in the file.
+    pub join_address: String,
+    /// What was wrong with the settings file when it was read, for the options scene to show.
+    /// Never saved.
+    #[serde(skip)]
+    pub warnings: Vec<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            window_size: WindowSize::Medium,
+            fullscreen: false,
+            exit_on_esc: true,
+            animation_speed: 1.0,
+            show_grid_lines: false,
+            confirm_end_turn: false,
+            show_threat_map: false,
+            casual_mode: false,
+            colorblind_palette: false,
+            host_port: DEFAULT_PORT,
+            join_address: format!("127.0.0.1:{}", DEFAULT_PORT),
+            warnings: Vec::new(),
+        }
+    }
+}
+
+impl Settings {
+    ///
+    /// Reads the settings file. A missing file just means the defaults are used.
+    ///
+    /// # Returns
+    /// * The settings, or why the file couldn't be used.
+    ///
+    pub fn load() -> Result<Settings, SettingsError> {
+        match fs::read_to_string(SETTINGS_PATH) {
+            Ok(contents) => Self::parse(&contents),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Settings::default()),
+            Err(e) => Err(SettingsError::Io(e)),
+        }
+    }
+
+    ///
+    /// Like `load`, but falls back to the defaults, with a warning, if the file can't be used.
+    ///
+    pub fn load_or_default() -> Settings {
+        Self::or_default(Self::load())
+    }
+
+    fn or_default(loaded: Result<Settings, SettingsError>) -> Settings {
+        loaded.unwrap_or_else(|e| {
+            let warning = format!("Using default settings, {}", e);
+            eprintln!("Warning: {}", warning);
+            Settings { warnings: vec![warning], ..Settings::default() }
+        })
+    }
+
+    ///
+    /// Parses settings from the contents of a settings file. Values that can't be used are
+    /// replaced by their defaults, with a warning.
+    ///
+    pub fn parse(contents: &str) -> Result<Settings, SettingsError> {
+        let mut settings: Settings = toml::from_str(contents).map_err(SettingsError::Parse)?;
+        if !settings.animation_speed.is_finite() || settings.animation_speed <= 0.0 {
+            let warning = format!("animation_speed must be above 0, using {}", Settings::default().animation_speed);
+            eprintln!("Warning: {}", warning);
+            settings.warnings.push(warning);
+            settings.animation_speed = Settings::default().animation_speed;
+        }
+        Ok(settings)
+    }
+
+    ///
+    /// Writes the settings file.
+    ///
+    pub fn save(&self) -> Result<(), SettingsError> {
+        let contents = toml::to_string_pretty(self).map_err(SettingsError::Write)?;
+        fs::write(SETTINGS_PATH, contents).map_err(SettingsError::Io)
+    }
+
+    ///
+    /// Moves `setting` on to its next value, wrapping around.
+    ///
+    pub fn cycle(&mut self, setting: Setting) {
+        match setting {
+            Setting::WindowSize => self.window_size = self.window_size.next(),
+            Setting::Fullscreen => self.fullscreen = !self.fullscreen,
+            Setting::ExitOnEsc => self.exit_on_esc = !self.exit_on_esc,
+            Setting::AnimationSpeed => {
+                let next = ANIMATION_SPEEDS.iter().position(|&s| s > self.animation_speed).unwrap_or(0);
+                self.animation_speed = ANIMATION_SPEEDS[next];
+            }
+            Setting::GridLines => self.show_grid_lines = !self.show_grid_lines,
+            Setting::ConfirmEndTurn => self.confirm_end_turn = !self.confirm_end_turn,
+            Setting::ThreatMap => self.show_threat_map = !self.show_threat_map,
+            Setting::CasualMode => self.casual_mode = !self.casual_mode,
+            Setting::ColorblindPalette => self.colorblind_palette = !self.colorblind_palette,
+        }
+    }
+
+    ///
+    /// # Returns
+    /// * A setting and its current value, as shown on its button in the options scene.
+    ///
+    pub fn describe(&self, setting: Setting) -> String {
+        let on_off = |on: bool| if on { "On" } else { "Off" };
+        match setting {
+            Setting::WindowSize => format!("Window size: {}", self.window_size.get_description()),
+            Setting::Fullscreen => format!("Fullscreen: {}", on_off(self.fullscreen)),
+            Setting::ExitOnEsc => format!("Esc quits: {}", on_off(self.exit_on_esc)),
+            Setting::AnimationSpeed => format!("Animation speed: {}x", self.animation_speed),
+            Setting::GridLines => format!("Grid lines: {}", on_off(self.show_grid_lines)),
+            Setting::ConfirmEndTurn => format!("Confirm end turn: {}", on_off(self.confirm_end_turn)),
+            Setting::ThreatMap => format!("Threat map: {}",
+                                          if self.show_threat_map { "Shown" } else { "Hidden" }),
+            Setting::CasualMode => format!("Casual mode: {}", on_off(self.casual_mode)),
+            Setting::ColorblindPalette => format!("Colorblind colors: {}", on_off(self.colorblind_palette)),
+        }
+    }
+}
+
+static SETTINGS: OnceLock<RwLock<Settings>> = OnceLock::new();
+
+fn settings_lock() -> &'static RwLock<Settings> {
+    SETTINGS.get_or_init(|| RwLock::new(Settings::default()))
+}
+
+///
+/// # Returns
+/// * The settings currently in effect.
+///
+pub fn current() -> RwLockReadGuard<'static, Settings> {
+    settings_lock().read().unwrap()
+}
+
+///
+/// Replaces the settings in effect. The window picks up changes on the next frame.
+///
+pub fn set_current(settings: Settings) {
+    *settings_lock().write().unwrap() = settings;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_in_what_the_file_leaves_out() {
+        let settings = Settings::parse("fullscreen = true").unwrap();
+        assert_eq!(settings, Settings { fullscreen: true, ..Settings::default() });
+    }
+
+    #[test]
+    fn reads_back_what_it_writes() {
+        let mut settings = Settings::default();
+        settings.cycle(Setting::WindowSize);
+        settings.cycle(Setting::AnimationSpeed);
+        settings.cycle(Setting::CasualMode);
+        let contents = toml::to_string_pretty(&settings).unwrap();
+        assert_eq!(Settings::parse(&contents).unwrap(), settings);
+    }
+
+    #[test]
+    fn reports_a_malformed_settings_file() {
+        assert!(matches!(Settings::parse("fullscreen = \"maybe\""), Err(SettingsError::Parse(_))));
+        assert!(matches!(Settings::parse("window_size = \"huge\""), Err(SettingsError::Parse(_))));
+    }
+
+    #[test]
+    fn falls_back_to_the_defaults_with_a_warning() {
+        let settings = Settings::or_default(Settings::parse("not toml at all ["));
+        assert_eq!(settings.warnings.len(), 1);
+        assert!(settings.warnings[0].starts_with("Using default settings"));
+        assert_eq!(Settings { warnings: Vec::new(), ..settings }, Settings::default());
+    }
+
+    #[test]
+    fn replaces_an_animation_speed_that_isnt_above_zero() {
+        for speed in ["0.0", "-2.0", "nan"] {
+            let settings = Settings::parse(&format!("animation_speed = {}", speed)).unwrap();
+            assert_eq!(settings.animation_speed, Settings::default().animation_speed);
+            assert_eq!(settings.warnings.len(), 1);
+        }
+    }
+
+    #[test]
+    fn keeps_a_usable_file_free_of_warnings() {
+        let settings = Settings::parse("animation_speed = 2.0").unwrap();
+        assert_eq!(settings.animation_speed, 2.0);
+        assert!(settings.warnings.is_empty());
+    }
+
+    #[test]
+    fn cycles_animation_speed_around() {
+        let mut settings = Settings { animation_speed: 4.0, ..Settings::default() };
+        settings.cycle(Setting::AnimationSpeed);
+        assert_eq!(settings.animation_speed, ANIMATION_SPEEDS[0]);
+    }
+}
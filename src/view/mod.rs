@@ -1,2 +1,5 @@
 pub mod util;
 pub mod scenedrawer;
+pub mod text;
+pub mod theme;
+pub mod transition;
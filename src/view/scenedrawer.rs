@@ -1,33 +1,533 @@
-use piston_window::{rectangle, Context, G2d, Glyphs};
+use std::collections::HashSet;
+use piston_window::{ellipse, rectangle, Context, G2d, Glyphs, Rectangle};
 use piston_window::types::Color;
+use crate::model::button::Button;
+use crate::model::campaign::SlotState;
+use crate::model::dialog::Dialog;
 use crate::model::game::*;
-use crate::view::util;
-use crate::view::util::draw_button;
+use crate::model::interstitial::InterstitialScene;
+use crate::model::levelselect::LevelSelectScene;
+use crate::model::lobby::LobbyScene;
+use crate::model::midlevel::MidLevelScene;
+use crate::model::options::OptionsScene;
+use crate::model::promotion::PromotionScene;
+use crate::model::reconnect::ReconnectScene;
+use crate::model::saveslots::SaveSlotScene;
+use crate::model::start::StartScene;
+use crate::model::replay::ReplayPlayer;
+use crate::model::save::{format_play_time, format_timestamp};
+use crate::settings;
+use crate::model::inlevel::cell::Terrain;
+use crate::model::inlevel::skill::Skill;
+use crate::model::inlevel::unit::{Class, Stat, Unit, XP_PER_LEVEL};
+use crate::view::text::{draw_fitted_label, draw_rich_text_box, draw_text_box, Align, Bounds, Span};
+use crate::view::theme::{self, Palette, Theme};
+use crate::view::transition::draw_transition;
+use crate::{HALF_X, HALF_Y, HEIGHT, WIDTH};
+use crate::view::util::{draw_block, draw_button, faded, to_coord, CELL_SIZE};
 
-use crate::{HALF_Y, HALF_X};
+/// Gap between a unit's token and the edge of its cell, in pixels.
+const UNIT_INSET: f64 = 12.0;
+
+/// Gap between the heads-up text and the edge of the window, in pixels.
+const HUD_MARGIN: f64 = 8.0;
+
+/// Laid over the whole screen behind a dialog.
+const DIALOG_SHADE: Color = [0.0, 0.0, 0.0, 0.5];
+
+/// Width and height of the elevation label in the corner of a raised cell, in pixels.
+const ELEVATION_LABEL_SIZE: f64 = 20.0;
+
+/// Width and height of the mark on the side of a unit's token it is facing, in pixels.
+const FACING_MARK_SIZE: f64 = 8.0;
+
+/// Height of a unit's HP bar, in pixels.
+const HP_BAR_HEIGHT: f64 = 5.0;
+
+/// Height of a unit's stamina bar, under its HP bar, in pixels.
+const STAMINA_BAR_HEIGHT: f64 = 3.0;
+
+/// Gap between a unit's token and its HP bar, and between its two bars, in pixels.
+const BAR_GAP: f64 = 1.5;
+
+/// Thickness of the lines between cells, when they are shown, in pixels.
+const GRID_LINE_THICKNESS: f64 = 1.0;
+
+/// Thickness of the outline around the selected unit's cell, in pixels.
+const SELECTION_THICKNESS: f64 = 3.0;
+
+fn terrain_color(palette: &Palette, terrain: &Terrain) -> Color {
+    match terrain {
+        Terrain::Plains => palette.plains,
+        Terrain::Forest => palette.forest,
+        Terrain::Mountain => palette.mountain,
+    }
+}
+
+fn class_symbol(class: Class) -> &'static str {
+    match class {
+        Class::Swordsman => "S",
+        Class::Archer => "A",
+        Class::Mage => "M",
+        Class::Cleric => "C",
+        Class::Knight => "K",
+        Class::Duelist => "D",
+        Class::Ranger => "R",
+        Class::Sniper => "Sn",
+        Class::Sage => "Sa",
+        Class::Warlock => "W",
+        Class::Bishop => "B",
+        Class::Oracle => "O",
+    }
+}
+
+///
+/// Draws the game as it stands: its current scene, or the transition between two scenes,
+/// with the replay controls over it while a replay plays.
+///
+pub fn draw_game(game: &Game, con: &Context, graphics: &mut G2d, glyphs: &mut Glyphs) {
+    match game.get_transition() {
+        Some(transition) => draw_transition(transition, game.get_scene(transition.get_from()),
+                                            game.get_scene(transition.get_to()), con, graphics, glyphs),
+        None => draw_scene(game.get_current_scene(), con, graphics, glyphs),
+    }
+    if let Some(replay) = game.get_replay() {
+        draw_replay_status(replay, con, graphics, glyphs);
+    }
+}
+
+///
+/// Draws whichever kind of scene `scene` is.
+///
+pub fn draw_scene(scene: &dyn Scene, con: &Context, graphics: &mut G2d, glyphs: &mut Glyphs) {
+    let any = scene.as_any();
+    if let Some(start) = any.downcast_ref::<StartScene>() {
+        draw_start(start, con, graphics, glyphs);
+    } else if let Some(selection) = any.downcast_ref::<LevelSelectScene>() {
+        draw_level_selection(selection, con, graphics, glyphs);
+    } else if let Some(options) = any.downcast_ref::<OptionsScene>() {
+        draw_options(options, con, graphics, glyphs);
+    } else if let Some(slots) = any.downcast_ref::<SaveSlotScene>() {
+        draw_save_slots(slots, con, graphics, glyphs);
+    } else if let Some(battle) = any.downcast_ref::<MidLevelScene>() {
+        draw_mid_level(battle, con, graphics, glyphs);
+    } else if let Some(interstitial) = any.downcast_ref::<InterstitialScene>() {
+        draw_interstitial(interstitial, con, graphics, glyphs);
+    } else if let Some(lobby) = any.downcast_ref::<LobbyScene>() {
+        draw_lobby(lobby, con, graphics, glyphs);
+    } else if let Some(reconnect) = any.downcast_ref::<ReconnectScene>() {
+        draw_reconnect(reconnect, con, graphics, glyphs);
+    } else if let Some(promotion) = any.downcast_ref::<PromotionScene>() {
+        draw_promotion(promotion, con, graphics, glyphs);
+    } else {
+        println!("No way to draw scene {}", scene.get_name());
+    }
+}
 
 pub fn draw_start(scene: &StartScene, con: &Context, graphics: &mut G2d, glyphs: &mut Glyphs) {
-    let button = scene.get_button();
-    let label = button.get_label();
-    let font_size = 14;
-    let fill_color = [0.5, 0.5, 0.9, 1.0];
+    let theme = theme::current();
+    let palette = theme.palette();
+    for button in scene.get_buttons() {
+        let x = button.get_top_left().0 as i32;
+        let y = button.get_top_left().1 as i32;
+        let width = button.get_bottom_right().0 as i32 - x;
+        let height = button.get_bottom_right().1 as i32 - y;
+        draw_button(button.get_label(), &theme.start_button, palette.start_button,
+                    palette.button_border, x, y, width, height, con, graphics, glyphs);
+    }
+}
+
+///
+/// Draws a menu button, greyed out when it is disabled.
+///
+fn draw_menu_button(theme: &Theme, button: &Button, con: &Context, graphics: &mut G2d, glyphs: &mut Glyphs) {
+    let palette = theme.palette();
     let x = button.get_top_left().0 as i32;
     let y = button.get_top_left().1 as i32;
     let width = button.get_bottom_right().0 as i32 - x;
     let height = button.get_bottom_right().1 as i32 - y;
-    draw_button(button.get_label(), font_size, fill_color, x, y, width, height, con, graphics, glyphs);
+    let fill = if button.is_enabled() { palette.level_button } else { palette.disabled_button };
+    draw_button(button.get_label(), &theme.level_button, fill,
+                palette.button_border, x, y, width, height, con, graphics, glyphs);
+}
+
+pub fn draw_options(scene: &OptionsScene, con: &Context, graphics: &mut G2d, glyphs: &mut Glyphs) {
+    let theme = theme::current();
+    let title_bounds = Bounds::new(0.0, 0.0, to_coord(WIDTH), CELL_SIZE);
+    draw_fitted_label("Options", theme.hud_font_size * 2, theme.palette().text, &title_bounds,
+                      con, graphics, glyphs);
+    for button in scene.get_buttons() {
+        draw_menu_button(&theme, button, con, graphics, glyphs);
+    }
+    // Problems with the settings file go beside the back button.
+    let warning_bounds = Bounds::new(HUD_MARGIN, to_coord(HEIGHT - 1), to_coord(HALF_X) - 2.0 * HUD_MARGIN, CELL_SIZE);
+    draw_text_box(&scene.get_warnings().join("\n"), theme.hud_font_size, theme.palette().warning_text, Align::Left,
+                  &warning_bounds, con, graphics, glyphs);
+}
+
+///
+/// Draws the screen shown between a hot seat battle's phases, and after it. Nothing of the
+/// map is drawn, so the next person can't see it before they are ready.
+///
+pub fn draw_interstitial(scene: &InterstitialScene, con: &Context, graphics: &mut G2d, glyphs: &mut Glyphs) {
+    let theme = theme::current();
+    let color = scene.get_team().map_or(theme.palette().text, |team| theme.team_color(team));
+    let message_bounds = Bounds::new(0.0, to_coord(HALF_Y - 1), to_coord(WIDTH), 2.0 * CELL_SIZE);
+    draw_fitted_label(scene.get_message(), theme.hud_font_size * 2, color, &message_bounds,
+                      con, graphics, glyphs);
+    draw_menu_button(&theme, scene.get_button(), con, graphics, glyphs);
+}
+
+///
+/// Draws the promotion scene: each unit ready to be promoted, beside the classes it can be
+/// promoted into, and what each stat does under them.
+///
+pub fn draw_promotion(scene: &PromotionScene, con: &Context, graphics: &mut G2d, glyphs: &mut Glyphs) {
+    let theme = theme::current();
+    let palette = theme.palette();
+    let title_bounds = Bounds::new(0.0, 0.0, to_coord(WIDTH), CELL_SIZE);
+    draw_fitted_label("Promotion", theme.hud_font_size * 2, palette.text, &title_bounds, con, graphics, glyphs);
+    for (label, buttons) in scene.get_rows() {
+        let Some(first) = buttons.first() else { continue };
+        let (left, top) = first.get_top_left();
+        let label_bounds = Bounds::new(HUD_MARGIN, to_coord(top as i32), to_coord(left as i32) - 2.0 * HUD_MARGIN, CELL_SIZE);
+        draw_text_box(label, theme.hud_font_size, palette.text, Align::Left, &label_bounds, con, graphics, glyphs);
+        for button in buttons {
+            draw_menu_button(&theme, button, con, graphics, glyphs);
+        }
+    }
+    let legend_top = to_coord(2 + scene.get_rows().len() as i32);
+    let legend_bottom = to_coord(scene.get_done_button().get_top_left().1 as i32);
+    if legend_bottom > legend_top {
+        let legend = Stat::ALL.iter()
+            .map(|stat| format!("{}: {}", stat.get_name(), stat.get_description()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let bounds = Bounds::new(0.0, legend_top, to_coord(WIDTH), legend_bottom - legend_top).inset(HUD_MARGIN);
+        draw_text_box(&legend, theme.hud_font_size, palette.text, Align::Left, &bounds, con, graphics, glyphs);
+    }
+    draw_menu_button(&theme, scene.get_done_button(), con, graphics, glyphs);
+}
+
+pub fn draw_lobby(scene: &LobbyScene, con: &Context, graphics: &mut G2d, glyphs: &mut Glyphs) {
+    let theme = theme::current();
+    let message_bounds = Bounds::new(0.0, 0.0, to_coord(WIDTH), CELL_SIZE);
+    draw_fitted_label(scene.get_message(), theme.hud_font_size * 2, theme.palette().text, &message_bounds,
+                      con, graphics, glyphs);
+    for button in scene.get_buttons() {
+        draw_menu_button(&theme, button, con, graphics, glyphs);
+    }
+}
+
+///
+/// Draws the screen covering a network battle while the connection is down.
+///
+pub fn draw_reconnect(scene: &ReconnectScene, con: &Context, graphics: &mut G2d, glyphs: &mut Glyphs) {
+    let theme = theme::current();
+    let message_bounds = Bounds::new(0.0, to_coord(HALF_Y - 1), to_coord(WIDTH), 2.0 * CELL_SIZE);
+    draw_fitted_label(scene.get_message(), theme.hud_font_size * 2, theme.palette().text, &message_bounds,
+                      con, graphics, glyphs);
+    draw_menu_button(&theme, scene.get_button(), con, graphics, glyphs);
+}
+
+///
+/// # Returns
+/// * The lines describing a save slot in the save slot menu.
+///
+fn describe_slot(slot: u32, state: &SlotState) -> String {
+    match state {
+        SlotState::Empty => format!("Slot {} - Empty", slot),
+        SlotState::Corrupted(reason) => format!("Slot {} - Corrupted\n{}", slot, reason),
+        SlotState::Saved(info) => format!(
+            "Slot {} - {}\nLevels cleared: {}   Score: {}\nPlay time: {}{}",
+            slot, format_timestamp(info.saved_at), info.levels_cleared, info.total_score,
+            format_play_time(info.play_time), if info.has_battle { "   (mid-battle)" } else { "" }),
+    }
+}
+
+pub fn draw_save_slots(scene: &SaveSlotScene, con: &Context, graphics: &mut G2d, glyphs: &mut Glyphs) {
+    let theme = theme::current();
+    let palette = theme.palette();
+    let title = if scene.is_paused() { "Paused - Save Slots" } else { "Save Slots" };
+    let title_bounds = Bounds::new(0.0, 0.0, to_coord(WIDTH), CELL_SIZE);
+    draw_fitted_label(title, theme.hud_font_size * 2, palette.text, &title_bounds, con, graphics, glyphs);
+
+    for (index, (slot, state)) in scene.get_slots().iter().enumerate() {
+        let ((x1, y1), (x2, y2)) = scene.get_slot_area(index);
+        let area = [to_coord(x1), to_coord(y1), to_coord(x2 - x1), to_coord(y2 - y1)];
+        Rectangle::new_border(faded(palette.button_border), theme.level_button.border_thickness / 2.0)
+            .draw(area, &con.draw_state, con.transform, graphics);
+        let text_color = match state {
+            SlotState::Corrupted(_) => palette.warning_text,
+            _ => palette.text,
+        };
+        let bounds = Bounds::new(area[0], area[1], area[2], area[3]).inset(HUD_MARGIN);
+        draw_text_box(&describe_slot(*slot, state), theme.hud_font_size, text_color, Align::Left,
+                      &bounds, con, graphics, glyphs);
+    }
+    for button in scene.get_buttons() {
+        draw_menu_button(&theme, button, con, graphics, glyphs);
+    }
+    if let Some(dialog) = scene.get_dialog() {
+        draw_dialog(dialog, con, graphics, glyphs);
+    }
+}
+
+///
+/// Dims the scene behind a dialog and draws the dialog's panel, question and buttons.
+///
+pub fn draw_dialog(dialog: &Dialog, con: &Context, graphics: &mut G2d, glyphs: &mut Glyphs) {
+    let theme = theme::current();
+    let palette = theme.palette();
+    rectangle(faded(DIALOG_SHADE), [0.0, 0.0, to_coord(WIDTH), to_coord(HEIGHT)], con.transform, graphics);
+
+    let ((x1, y1), (x2, y2)) = dialog.get_area();
+    let (x1, y1, x2, y2) = (x1 as i32, y1 as i32, x2 as i32, y2 as i32);
+    draw_button("", &theme.level_button, palette.background, palette.button_border,
+                x1, y1, x2 - x1, y2 - y1, con, graphics, glyphs);
+    let message = Bounds::new(to_coord(x1), to_coord(y1), to_coord(x2 - x1), to_coord(2)).inset(HUD_MARGIN);
+    draw_fitted_label(dialog.get_message(), theme.hud_font_size, palette.text, &message, con, graphics, glyphs);
+    for button in dialog.get_buttons() {
+        draw_menu_button(&theme, button, con, graphics, glyphs);
+    }
 }
 
 pub fn draw_level_selection(scene: &LevelSelectScene, con: &Context, graphics: &mut G2d, glyphs: &mut Glyphs) {
+    let theme = theme::current();
     let buttons = scene.get_buttons();
     for button in buttons.iter() {
-        let label = button.get_label();
-        let font_size = 10;
-        let fill_color = [0.5, 0.5, 0.65, 1.0];
-        let x = button.get_top_left().0 as i32;
-        let y = button.get_top_left().1 as i32;
-        let width = button.get_bottom_right().0 as i32 - x;
-        let height = button.get_bottom_right().1 as i32 - y;
-        draw_button(button.get_label(), font_size, fill_color, x, y, width, height, con, graphics, glyphs);
+        draw_menu_button(&theme, button, con, graphics, glyphs);
+    }
+}
+
+pub fn draw_mid_level(scene: &MidLevelScene, con: &Context, graphics: &mut G2d, glyphs: &mut Glyphs) {
+    let theme = theme::current();
+    let palette = theme.palette();
+    let (moves, attacks, reach) = match scene.get_selected() {
+        Some(selected) => (scene.move_options(selected), scene.attack_options(selected),
+                           scene.attack_range_cells(selected)),
+        None => (vec![], vec![], HashSet::new()),
+    };
+    let targetable = scene.targeting_options();
+    let threats = if scene.is_threat_map_shown() { scene.threatened_cells() } else { HashSet::new() };
+    let grid_lines = settings::current().show_grid_lines;
+    for cell in scene.visible_cells() {
+        let (x, y) = cell.get_top_left();
+        let (x, y) = (x as i32, y as i32);
+        draw_block(terrain_color(palette, cell.get_terrain()), x, y, con, graphics);
+        if threats.contains(&cell.get_position()) {
+            draw_block(palette.threat_highlight, x, y, con, graphics);
+        }
+        if let Some(zone) = scene.get_zone() {
+            let turn = scene.get_turn();
+            if !zone.contains(cell.get_position(), turn) {
+                draw_block(palette.outside_zone, x, y, con, graphics);
+            } else if zone.turns_until_shrink(turn).is_some_and(|turns| !zone.contains(cell.get_position(), turn + turns)) {
+                draw_block(palette.zone_closing, x, y, con, graphics);
+            }
+        }
+        if grid_lines {
+            Rectangle::new_border(faded(palette.grid_line), GRID_LINE_THICKNESS / 2.0)
+                .draw([to_coord(x), to_coord(y), CELL_SIZE, CELL_SIZE], &con.draw_state, con.transform, graphics);
+        }
+        if cell.get_elevation() > 0 {
+            let corner = Bounds::new(to_coord(x), to_coord(y), ELEVATION_LABEL_SIZE, ELEVATION_LABEL_SIZE);
+            draw_fitted_label(&cell.get_elevation().to_string(), theme.hud_font_size, palette.text, &corner,
+                              con, graphics, glyphs);
+        }
+        if moves.contains(&cell.get_position()) {
+            draw_block(palette.move_highlight, x, y, con, graphics);
+        } else if reach.contains(&cell.get_position()) {
+            draw_block(palette.attack_range, x, y, con, graphics);
+        }
+        if attacks.contains(&cell.get_position()) {
+            draw_block(palette.attack_highlight, x, y, con, graphics);
+        }
+        if targetable.contains(&cell.get_position()) {
+            draw_block(palette.support_highlight, x, y, con, graphics);
+        }
+        if let Some(unit) = cell.get_unit() {
+            let token = Bounds::new(to_coord(x), to_coord(y), CELL_SIZE, CELL_SIZE).inset(UNIT_INSET);
+            ellipse(faded(theme.team_color(unit.get_team())),
+                    [token.x, token.y, token.width, token.height], con.transform, graphics);
+            draw_fitted_label(class_symbol(unit.get_class()), theme.start_button.font_size,
+                              palette.text, &token, con, graphics, glyphs);
+            if unit.is_done() {
+                ellipse(faded(palette.done_unit),
+                        [token.x, token.y, token.width, token.height], con.transform, graphics);
+            }
+            draw_facing_mark(palette, unit, &token, con, graphics);
+            draw_unit_bars(palette, unit, &token, con, graphics);
+        }
+        if scene.get_selected() == Some(cell.get_position()) {
+            Rectangle::new_border(faded(palette.selection), SELECTION_THICKNESS / 2.0)
+                .draw([to_coord(x), to_coord(y), CELL_SIZE, CELL_SIZE], &con.draw_state, con.transform, graphics);
+        }
+    }
+    draw_minimap(scene, con, graphics);
+
+    let side = scene.get_active_side();
+    let hud = Bounds::new(HUD_MARGIN, HUD_MARGIN, to_coord(WIDTH) - 2.0 * HUD_MARGIN, CELL_SIZE);
+    let mut status = format!("Turn {} - {} Phase", scene.get_turn(), side.name);
+    if let Some(zone) = scene.get_zone() {
+        match zone.turns_until_shrink(scene.get_turn()) {
+            Some(turns) => status.push_str(&format!("   Zone shrinks in {} turn(s)", turns)),
+            None => status.push_str("   Zone at its smallest"),
+        }
+    }
+    match scene.get_targeting() {
+        Some(Targeting::Support(support)) => status.push_str(&format!("   {}: pick an ally", support.get_name())),
+        Some(Targeting::Skill(skill)) => status.push_str(&format!("   {}: pick where to aim", skill.get_name())),
+        Some(Targeting::Facing) => status.push_str("   Wait: pick which way to face"),
+        None => {}
+    }
+    // Keys the player can press right now go on the right, apart from the phase.
+    let mut keys = Vec::new();
+    if !scene.is_local_phase() && scene.is_human_phase() {
+        status.push_str(&format!("   Waiting for {}...", side.name));
+    } else {
+        if scene.can_undo() {
+            keys.push("Backspace: undo");
+        }
+        if scene.is_casual() && scene.is_human_phase() {
+            keys.push("R: rewind turn");
+        }
+    }
+    // With several people playing, the phase is shown in the color of whoever's it is.
+    let several_people = scene.is_hot_seat() || scene.is_network_battle();
+    let status_color = if several_people { theme.team_color(side.team) } else { palette.text };
+    draw_text_box(&status, theme.hud_font_size, status_color, Align::Left, &hud, con, graphics, glyphs);
+    draw_text_box(&keys.join("   "), theme.hud_font_size, palette.text, Align::Right, &hud, con, graphics, glyphs);
+    let selected = scene.get_selected().and_then(|(x, y)| scene.get_cell(x, y)).and_then(|cell| cell.get_unit());
+    if let Some(unit) = selected {
+        let panel = Bounds::new(HUD_MARGIN, to_coord(HEIGHT) - HUD_MARGIN - CELL_SIZE,
+                                to_coord(WIDTH) * 2.0 / 3.0, CELL_SIZE);
+        draw_rich_text_box(&describe_unit(palette, unit), theme.hud_font_size, Align::Left, &panel,
+                           con, graphics, glyphs);
     }
-}
\ No newline at end of file
+    for button in scene.get_action_menu() {
+        draw_menu_button(&theme, button, con, graphics, glyphs);
+    }
+    if let Some(dialog) = scene.get_dialog() {
+        draw_dialog(dialog, con, graphics, glyphs);
+    }
+}
+
+///
+/// # Returns
+/// * Text about `unit`, the weapon it fights with and its passive skills, for the selected
+///   unit's panel. HP and stamina take the colors of their bars, and anything ailing the unit
+///   is shown as a warning.
+///
+fn describe_unit(palette: &Palette, unit: &Unit) -> Vec<Span> {
+    let mut spans = vec![
+        Span::new(&format!("{} - {} Lv {} ({}/{} XP) - ", unit.get_name(), unit.get_class().get_name(),
+                           unit.get_level(), unit.get_experience(), XP_PER_LEVEL), palette.text),
+        Span::new(&format!("HP {}/{}", unit.get_hp(), unit.get_max_hp()), palette.hp_bar),
+        Span::new(" - ", palette.text),
+        Span::new(&format!("Stamina {}/{}", unit.get_stamina(), unit.get_max_stamina()), palette.stamina_bar),
+    ];
+    let mut text = String::new();
+    match unit.get_weapon() {
+        Some(weapon) => {
+            let (min_range, max_range) = weapon.get_range();
+            text.push_str(&format!(" - {} ({}) {}/{}, range {}-{}", weapon.get_name(), weapon.get_kind().get_name(),
+                                   weapon.get_uses(), weapon.get_max_uses(), min_range, max_range));
+        }
+        None => text.push_str(" - Unarmed"),
+    }
+    if unit.is_exhausted() {
+        spans.push(Span::new(&text, palette.text));
+        spans.push(Span::new(" - Exhausted", palette.warning_text));
+        text.clear();
+    }
+    text.push_str(&format!(" - Facing {}", unit.get_facing().get_name()));
+    for skill in Skill::known_by(unit).into_iter().filter(|skill| !skill.is_active()) {
+        text.push_str(&format!(" - {}", skill.get_name()));
+    }
+    spans.push(Span::new(&text, palette.text));
+    for effect in unit.get_effects() {
+        let color = if effect.kind.is_harmful() { palette.warning_text } else { palette.text };
+        spans.push(Span::new(&format!(" - {} ({})", effect.kind.get_name(), effect.turns), color));
+    }
+    let usable = unit.get_inventory().iter().filter(|w| w.get_kind().usable_by(unit.get_class())).count();
+    if usable > 1 {
+        spans.push(Span::new("   E: switch weapon", palette.text));
+    }
+    spans
+}
+
+///
+/// Marks the edge of a unit's token on the side it is facing.
+///
+fn draw_facing_mark(palette: &Palette, unit: &Unit, token: &Bounds, con: &Context, graphics: &mut G2d) {
+    let (dx, dy) = unit.get_facing().offset();
+    let reach = (token.width - FACING_MARK_SIZE) / 2.0;
+    let x = token.x + reach + dx as f64 * reach;
+    let y = token.y + reach + dy as f64 * reach;
+    rectangle(faded(palette.text), [x, y, FACING_MARK_SIZE, FACING_MARK_SIZE], con.transform, graphics);
+}
+
+///
+/// Draws a unit's remaining HP as a bar just under its token, and its stamina as a thinner
+/// bar under that.
+///
+fn draw_unit_bars(palette: &Palette, unit: &Unit, token: &Bounds, con: &Context, graphics: &mut G2d) {
+    let bars = [
+        (palette.hp_bar, unit.get_hp(), unit.get_max_hp(), HP_BAR_HEIGHT),
+        (palette.stamina_bar, unit.get_stamina(), unit.get_max_stamina(), STAMINA_BAR_HEIGHT),
+    ];
+    let mut y = token.y + token.height + BAR_GAP;
+    for (color, value, max, height) in bars {
+        let filled = token.width * value as f64 / max.max(1) as f64;
+        rectangle(faded(palette.hp_bar_back), [token.x, y, token.width, height], con.transform, graphics);
+        rectangle(faded(color), [token.x, y, filled, height], con.transform, graphics);
+        y += height + BAR_GAP;
+    }
+}
+
+///
+/// Draws the minimap: every cell's terrain as one block, units as team colored dots, and an
+/// outline around the part of the map the camera is showing.
+///
+fn draw_minimap(scene: &MidLevelScene, con: &Context, graphics: &mut G2d) {
+    let theme = theme::current();
+    let palette = theme.palette();
+    let minimap = scene.get_minimap();
+    let (x1, y1, x2, y2) = minimap.get_container_coords();
+    rectangle(faded(palette.minimap_border), [x1, y1, x2 - x1, y2 - y1], con.transform, graphics);
+
+    let block = minimap.block_size();
+    for cell in scene.get_cells() {
+        let (x, y) = cell.get_position();
+        let rect = minimap.block_rect(x, y);
+        rectangle(faded(terrain_color(palette, cell.get_terrain())), rect, con.transform, graphics);
+        if let Some(unit) = cell.get_unit() {
+            // Keep dots visible even when blocks are only a pixel or two wide.
+            let dot = block.max(3.0);
+            let (cx, cy) = (rect[0] + block / 2.0, rect[1] + block / 2.0);
+            ellipse(faded(theme.team_color(unit.get_team())),
+                    [cx - dot / 2.0, cy - dot / 2.0, dot, dot], con.transform, graphics);
+        }
+    }
+
+    let (cam_x, cam_y) = scene.get_camera();
+    let (view_w, view_h) = scene.get_viewport_size();
+    Rectangle::new_border(faded(palette.minimap_viewport), 1.0)
+        .draw(minimap.area_rect(cam_x, cam_y, view_w, view_h), &con.draw_state, con.transform, graphics);
+}
+
+///
+/// Draws the playback state of a replay along the bottom of the screen.
+///
+pub fn draw_replay_status(replay: &ReplayPlayer, con: &Context, graphics: &mut G2d, glyphs: &mut Glyphs) {
+    let theme = theme::current();
+    let (played, total) = replay.get_progress();
+    let state = if replay.is_finished() { "Finished" } else if replay.is_playing() { "Playing" } else { "Paused" };
+    let status = format!("Replay - {} {}x - {}/{}   Space: pause  .: step  +/-: speed  Backspace: stop",
+                         state, replay.get_speed(), played, total);
+    let bounds = Bounds::new(HUD_MARGIN, to_coord(HEIGHT) - CELL_SIZE / 2.0,
+                             to_coord(WIDTH) - 2.0 * HUD_MARGIN, CELL_SIZE / 2.0);
+    draw_text_box(&status, theme.hud_font_size, theme.palette().text, Align::Left, &bounds,
+                  con, graphics, glyphs);
+}
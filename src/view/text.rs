@@ -0,0 +1,417 @@
+//! TEXT.RS:
+//! Text layout and drawing on top of Piston's glyph cache. Provides word
+//! wrapping, left/centre/right alignment, shrink-to-fit for labels that would
+//! overflow their box, and rich text made of differently colored spans.
+
+use piston_window::{CharacterCache, Context, G2d, Glyphs, Text, Transformed};
+use piston_window::types::Color;
+use crate::view::util::faded;
+
+/// Smallest size a label is shrunk to before it is allowed to overflow.
+pub const MIN_FONT_SIZE: u32 = 6;
+
+/// Distance between two baselines, as a multiple of the font size.
+const LINE_SPACING: f64 = 1.2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+///
+/// A run of text drawn in a single color.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub color: Color,
+}
+
+impl Span {
+    pub fn new(text: &str, color: Color) -> Self {
+        Self { text: text.to_string(), color }
+    }
+}
+
+///
+/// A pixel rectangle text is laid out in.
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Bounds {
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        Self { x, y, width, height }
+    }
+
+    ///
+    /// # Returns
+    /// * These bounds shrunk by `padding` on every side.
+    ///
+    pub fn inset(&self, padding: f64) -> Self {
+        Self {
+            x: self.x + padding,
+            y: self.y + padding,
+            width: (self.width - 2.0 * padding).max(0.0),
+            height: (self.height - 2.0 * padding).max(0.0),
+        }
+    }
+}
+
+///
+/// # Returns
+/// * The width of `text` in pixels, estimated from the font size if the glyphs can't be measured.
+///
+pub fn text_width<C: CharacterCache>(glyphs: &mut C, font_size: u32, text: &str) -> f64 {
+    glyphs
+        .width(font_size, text)
+        .unwrap_or(text.chars().count() as f64 * (font_size as f64 * 0.5))
+}
+
+///
+/// # Returns
+/// * The height of `lines` lines of text at `font_size`.
+///
+pub fn lines_height(lines: usize, font_size: u32) -> f64 {
+    if lines == 0 {
+        return 0.0;
+    }
+    font_size as f64 * (1.0 + LINE_SPACING * (lines - 1) as f64)
+}
+
+///
+/// Splits `text` into lines no wider than `max_width`. Breaks between words where possible,
+/// honours explicit newlines, and breaks a single word that is too long on its own.
+///
+/// # Returns
+/// * The wrapped lines, in order.
+///
+pub fn wrap<C: CharacterCache>(glyphs: &mut C, font_size: u32, text: &str, max_width: f64) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+            if text_width(glyphs, font_size, &candidate) <= max_width {
+                line = candidate;
+                continue;
+            }
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            // The word doesn't fit on a line of its own, break it between characters.
+            for ch in word.chars() {
+                line.push(ch);
+                if line.chars().count() > 1 && text_width(glyphs, font_size, &line) > max_width {
+                    line.pop();
+                    lines.push(std::mem::replace(&mut line, ch.to_string()));
+                }
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+///
+/// Finds the largest font size, no bigger than `max_font_size`, at which `text` wraps to fit
+/// inside `bounds`.
+///
+/// # Returns
+/// * The font size to use, or `MIN_FONT_SIZE` if even that overflows.
+///
+pub fn fit_font_size<C: CharacterCache>(glyphs: &mut C, text: &str, max_font_size: u32, bounds: &Bounds) -> u32 {
+    let mut size = max_font_size;
+    while size > MIN_FONT_SIZE {
+        let lines = wrap(glyphs, size, text, bounds.width);
+        let widest = lines.iter().map(|l| text_width(glyphs, size, l)).fold(0.0, f64::max);
+        if widest <= bounds.width && lines_height(lines.len(), size) <= bounds.height {
+            return size;
+        }
+        size -= 1;
+    }
+    MIN_FONT_SIZE
+}
+
+fn aligned_x(align: Align, bounds: &Bounds, line_width: f64) -> f64 {
+    match align {
+        Align::Left => bounds.x,
+        Align::Center => bounds.x + (bounds.width - line_width) / 2.0,
+        Align::Right => bounds.x + bounds.width - line_width,
+    }
+}
+
+///
+/// Draws already laid-out lines, each made of colored pieces, starting at `top`.
+///
+#[allow(clippy::too_many_arguments)]
+fn draw_lines(
+    lines: &[Vec<Span>],
+    font_size: u32,
+    align: Align,
+    bounds: &Bounds,
+    top: f64,
+    con: &Context,
+    g: &mut G2d,
+    glyphs: &mut Glyphs,
+) {
+    for (i, line) in lines.iter().enumerate() {
+        let line_width: f64 = line.iter().map(|s| text_width(glyphs, font_size, &s.text)).sum();
+        let mut x = aligned_x(align, bounds, line_width);
+        let baseline = top + font_size as f64 + i as f64 * font_size as f64 * LINE_SPACING;
+        for span in line {
+            Text::new_color(faded(span.color), font_size).draw(
+                &span.text,
+                glyphs,
+                &con.draw_state,
+                con.transform.trans(x, baseline),
+                g,
+            ).ok();
+            x += text_width(glyphs, font_size, &span.text);
+        }
+    }
+}
+
+///
+/// Draws word-wrapped text in a box, starting from its top edge.
+///
+/// # Arguments
+///
+/// * `text`      – The text to draw. Explicit newlines start a new line.
+/// * `font_size` – Font size in pixels.
+/// * `color`     – Color of the text.
+/// * `align`     – Horizontal alignment of each line inside the box.
+/// * `bounds`    – The box to lay the text out in, in pixels.
+/// * `con`       – Piston drawing context.
+/// * `g`         – Graphics backend.
+/// * `glyphs`    – Loaded font glyphs for text rendering.
+#[allow(clippy::too_many_arguments)]
+pub fn draw_text_box(
+    text: &str,
+    font_size: u32,
+    color: Color,
+    align: Align,
+    bounds: &Bounds,
+    con: &Context,
+    g: &mut G2d,
+    glyphs: &mut Glyphs,
+) {
+    let lines: Vec<Vec<Span>> = wrap(glyphs, font_size, text, bounds.width)
+        .into_iter()
+        .map(|line| vec![Span { text: line, color }])
+        .collect();
+    draw_lines(&lines, font_size, align, bounds, bounds.y, con, g, glyphs);
+}
+
+///
+/// Draws a label centered both ways in a box, shrinking it (and wrapping it if needed)
+/// so that it doesn't overflow.
+///
+/// # Arguments
+///
+/// * `label`         – The text to draw.
+/// * `max_font_size` – The size to use if the label fits as-is.
+/// * `color`         – Color of the text.
+/// * `bounds`        – The box to center the label in, in pixels.
+/// * `con`, `g`, `glyphs` – Piston drawing context, graphics backend and font glyphs.
+pub fn draw_fitted_label(
+    label: &str,
+    max_font_size: u32,
+    color: Color,
+    bounds: &Bounds,
+    con: &Context,
+    g: &mut G2d,
+    glyphs: &mut Glyphs,
+) {
+    let font_size = fit_font_size(glyphs, label, max_font_size, bounds);
+    let lines: Vec<Vec<Span>> = wrap(glyphs, font_size, label, bounds.width)
+        .into_iter()
+        .map(|line| vec![Span { text: line, color }])
+        .collect();
+    let top = bounds.y + (bounds.height - lines_height(lines.len(), font_size)) / 2.0;
+    draw_lines(&lines, font_size, Align::Center, bounds, top, con, g, glyphs);
+}
+
+///
+/// Lays out colored spans into lines no wider than `max_width`, keeping each word's color.
+/// Words are split on whitespace, so a span boundary inside a word (e.g. `"12"` + `"HP"`)
+/// stays glued together.
+///
+/// # Returns
+/// * The lines, each a list of colored pieces.
+///
+pub fn wrap_spans<C: CharacterCache>(glyphs: &mut C, font_size: u32, spans: &[Span], max_width: f64) -> Vec<Vec<Span>> {
+    let mut lines: Vec<Vec<Span>> = vec![vec![]];
+    let mut line_width = 0.0;
+    let mut pending_space = false;
+    let mut pending_newline = false;
+    let space_width = text_width(glyphs, font_size, " ");
+
+    // Break every span into words, remembering whether whitespace preceded each one.
+    let mut words: Vec<(bool, bool, Span)> = Vec::new(); // (space before, newline before, word)
+    for span in spans {
+        let mut word = String::new();
+        for ch in span.text.chars() {
+            if !ch.is_whitespace() {
+                word.push(ch);
+                continue;
+            }
+            if !word.is_empty() {
+                words.push((pending_space, pending_newline, Span { text: std::mem::take(&mut word), color: span.color }));
+                pending_newline = false;
+            }
+            pending_space = true;
+            pending_newline |= ch == '\n';
+        }
+        if !word.is_empty() {
+            words.push((pending_space, pending_newline, Span { text: word, color: span.color }));
+            pending_space = false;
+            pending_newline = false;
+        }
+    }
+
+    for (space_before, newline_before, word) in words {
+        let word_width = text_width(glyphs, font_size, &word.text);
+        let current = lines.last_mut().unwrap();
+        let gap = if space_before && !current.is_empty() { space_width } else { 0.0 };
+        if newline_before || (!current.is_empty() && line_width + gap + word_width > max_width) {
+            lines.push(vec![word]);
+            line_width = word_width;
+            continue;
+        }
+        if gap > 0.0 {
+            current.push(Span { text: " ".to_string(), color: word.color });
+        }
+        line_width += gap + word_width;
+        current.push(word);
+    }
+    lines
+}
+
+///
+/// Draws word-wrapped rich text made of colored spans, starting from the box's top edge.
+///
+/// # Arguments
+///
+/// * `spans`     – The pieces of text, each with its own color.
+/// * `font_size` – Font size in pixels.
+/// * `align`     – Horizontal alignment of each line inside the box.
+/// * `bounds`    – The box to lay the text out in, in pixels.
+/// * `con`, `g`, `glyphs` – Piston drawing context, graphics backend and font glyphs.
+pub fn draw_rich_text_box(
+    spans: &[Span],
+    font_size: u32,
+    align: Align,
+    bounds: &Bounds,
+    con: &Context,
+    g: &mut G2d,
+    glyphs: &mut Glyphs,
+) {
+    let lines = wrap_spans(glyphs, font_size, spans, bounds.width);
+    draw_lines(&lines, font_size, align, bounds, bounds.y, con, g, glyphs);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use piston_window::character::Character;
+    use piston_window::ImageSize;
+
+    struct NoTexture;
+
+    impl ImageSize for NoTexture {
+        fn get_size(&self) -> (u32, u32) {
+            (0, 0)
+        }
+    }
+
+    /// A monospace font where every character is half as wide as the font size.
+    struct FixedWidth(NoTexture);
+
+    impl CharacterCache for FixedWidth {
+        type Texture = NoTexture;
+        type Error = ();
+
+        fn character(&mut self, font_size: u32, _ch: char) -> Result<Character<'_, NoTexture>, ()> {
+            Ok(Character {
+                offset: [0.0, 0.0],
+                advance_size: [font_size as f64 / 2.0, 0.0],
+                atlas_offset: [0.0, 0.0],
+                atlas_size: [0.0, 0.0],
+                texture: &self.0,
+                is_invalid: false,
+            })
+        }
+    }
+
+    // At size 10 every character is 5 pixels wide.
+    fn glyphs() -> FixedWidth {
+        FixedWidth(NoTexture)
+    }
+
+    #[test]
+    fn wraps_between_words() {
+        let lines = wrap(&mut glyphs(), 10, "aaa bbb ccc", 40.0);
+        assert_eq!(lines, vec!["aaa bbb", "ccc"]);
+    }
+
+    #[test]
+    fn starts_a_new_line_at_each_newline() {
+        let lines = wrap(&mut glyphs(), 10, "a\nb c", 100.0);
+        assert_eq!(lines, vec!["a", "b c"]);
+    }
+
+    #[test]
+    fn breaks_a_word_too_long_for_a_line() {
+        let lines = wrap(&mut glyphs(), 10, "abcdefghij", 20.0);
+        assert_eq!(lines, vec!["abcd", "efgh", "ij"]);
+    }
+
+    #[test]
+    fn keeps_each_word_its_color_when_wrapping_spans() {
+        let red = [1.0, 0.0, 0.0, 1.0];
+        let blue = [0.0, 0.0, 1.0, 1.0];
+        let spans = [Span::new("12", red), Span::new("HP left", blue)];
+        let lines = wrap_spans(&mut glyphs(), 10, &spans, 25.0);
+        assert_eq!(lines, vec![
+            vec![Span::new("12", red), Span::new("HP", blue)],
+            vec![Span::new("left", blue)],
+        ]);
+    }
+
+    #[test]
+    fn aligns_lines_inside_their_box() {
+        let bounds = Bounds::new(10.0, 0.0, 100.0, 20.0);
+        assert_eq!(aligned_x(Align::Left, &bounds, 40.0), 10.0);
+        assert_eq!(aligned_x(Align::Center, &bounds, 40.0), 40.0);
+        assert_eq!(aligned_x(Align::Right, &bounds, 40.0), 70.0);
+    }
+
+    #[test]
+    fn keeps_the_font_size_when_the_text_fits() {
+        let bounds = Bounds::new(0.0, 0.0, 100.0, 20.0);
+        assert_eq!(fit_font_size(&mut glyphs(), "Start", 14, &bounds), 14);
+    }
+
+    #[test]
+    fn shrinks_text_until_it_fits() {
+        // "Level Select" is 12 characters, so only size 8 and below fit 50 pixels on one line;
+        // wrapped onto two lines it would need more height than the box has.
+        let bounds = Bounds::new(0.0, 0.0, 50.0, 12.0);
+        let size = fit_font_size(&mut glyphs(), "Level Select", 14, &bounds);
+        assert_eq!(size, 8);
+    }
+
+    #[test]
+    fn stops_shrinking_at_the_smallest_size() {
+        let bounds = Bounds::new(0.0, 0.0, 10.0, 5.0);
+        assert_eq!(fit_font_size(&mut glyphs(), "far too long", 14, &bounds), MIN_FONT_SIZE);
+    }
+}
@@ -0,0 +1,337 @@
+//! THEME.RS:
+//! Centralises every color, font and button style used by the view layer.
+//! A theme is read from a TOML asset at startup so the look of the game can be
+//! changed without recompiling, and holds several named palettes (including a
+//! colorblind-safe one) that can be swapped between.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{OnceLock, RwLock, RwLockReadGuard};
+
+use piston_window::types::Color;
+use serde::Deserialize;
+use crate::assets::{self, AssetError};
+
+/// Name of the palette used when the theme does not pick one, or picks one that doesn't exist.
+pub const DEFAULT_PALETTE: &str = "default";
+
+/// Name of the built-in palette whose team colors are distinguishable under the common
+/// forms of color blindness (Okabe-Ito).
+pub const COLORBLIND_PALETTE: &str = "colorblind";
+
+#[derive(Debug)]
+pub enum ThemeError {
+    Asset(AssetError),
+    Parse(toml::de::Error),
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThemeError::Asset(e) => write!(f, "couldn't read theme file: {}", e),
+            ThemeError::Parse(e) => write!(f, "malformed theme file: {}", e),
+        }
+    }
+}
+
+///
+/// A named set of colors. Every field may be omitted in the theme file, in which
+/// case the value from the default palette is used.
+///
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Palette {
+    pub background: Color,
+    pub text: Color,
+    pub button_border: Color,
+    pub start_button: Color,
+    pub level_button: Color,
+    pub plains: Color,
+    pub forest: Color,
+    pub mountain: Color,
+    pub minimap_border: Color,
+    /// Outline of the area the camera is showing, drawn on the minimap.
+    pub minimap_viewport: Color,
+    /// Text calling out a problem, such as a corrupted save.
+    pub warning_text: Color,
+    /// Buttons that can't be pressed yet, such as locked levels.
+    pub disabled_button: Color,
+    /// Overlay on cells the selected unit can move to.
+    pub move_highlight: Color,
+    /// Overlay on enemies the selected unit can attack.
+    pub attack_highlight: Color,
+    /// Overlay on cells the selected unit could strike, from where it stands or could move to.
+    pub attack_range: Color,
+    /// Overlay on allies the selected unit can use the support picked from its menu on.
+    pub support_highlight: Color,
+    /// Overlay on cells enemies could attack next phase, when the threat map is shown.
+    pub threat_highlight: Color,
+    /// Overlay on cells outside a battle royale's safe zone.
+    pub outside_zone: Color,
+    /// Overlay on cells the safe zone will leave out when it next shrinks.
+    pub zone_closing: Color,
+    /// Lines between cells, when they are shown.
+    pub grid_line: Color,
+    /// Outline of the selected unit's cell.
+    pub selection: Color,
+    pub hp_bar: Color,
+    pub hp_bar_back: Color,
+    /// Fill of the bar under a unit's HP bar showing its stamina.
+    pub stamina_bar: Color,
+    /// Laid over units that have finished acting this phase.
+    pub done_unit: Color,
+    /// Colors used to tell teams apart, indexed by team number.
+    pub team_colors: Vec<Color>,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            background: [0.8, 0.8, 0.8, 1.0],
+            text: [0.0, 0.0, 0.0, 1.0],
+            button_border: [0.0, 0.0, 0.0, 1.0],
+            start_button: [0.5, 0.5, 0.9, 1.0],
+            level_button: [0.5, 0.5, 0.65, 1.0],
+            plains: [0.56, 0.78, 0.42, 1.0],
+            forest: [0.18, 0.45, 0.22, 1.0],
+            mountain: [0.55, 0.5, 0.45, 1.0],
+            minimap_border: [0.1, 0.1, 0.1, 1.0],
+            minimap_viewport: [1.0, 1.0, 1.0, 1.0],
+            warning_text: [0.75, 0.1, 0.1, 1.0],
+            disabled_button: [0.6, 0.6, 0.6, 1.0],
+            move_highlight: [0.3, 0.5, 1.0, 0.35],
+            attack_highlight: [1.0, 0.2, 0.2, 0.4],
+            attack_range: [1.0, 0.2, 0.2, 0.15],
+            support_highlight: [0.2, 0.9, 0.4, 0.4],
+            threat_highlight: [0.9, 0.5, 0.1, 0.3],
+            outside_zone: [0.45, 0.1, 0.6, 0.4],
+            zone_closing: [0.45, 0.1, 0.6, 0.15],
+            grid_line: [0.0, 0.0, 0.0, 0.35],
+            selection: [1.0, 1.0, 0.3, 1.0],
+            hp_bar: [0.2, 0.85, 0.3, 1.0],
+            hp_bar_back: [0.15, 0.15, 0.15, 1.0],
+            stamina_bar: [0.95, 0.8, 0.2, 1.0],
+            done_unit: [0.3, 0.3, 0.3, 0.5],
+            team_colors: vec![
+                [0.2, 0.4, 0.9, 1.0],
+                [0.9, 0.2, 0.2, 1.0],
+                [0.2, 0.7, 0.3, 1.0],
+                [0.9, 0.8, 0.2, 1.0],
+            ],
+        }
+    }
+}
+
+impl Palette {
+    ///
+    /// # Returns
+    /// * The built-in colorblind-safe palette, using the Okabe-Ito team colors.
+    ///
+    pub fn colorblind() -> Self {
+        Self {
+            team_colors: vec![
+                [0.0, 0.447, 0.698, 1.0],   // blue
+                [0.902, 0.624, 0.0, 1.0],   // orange
+                [0.0, 0.620, 0.451, 1.0],   // bluish green
+                [0.800, 0.475, 0.655, 1.0], // reddish purple
+                [0.337, 0.706, 0.914, 1.0], // sky blue
+                [0.835, 0.369, 0.0, 1.0],   // vermillion
+            ],
+            ..Self::default()
+        }
+    }
+}
+
+///
+/// The shape and text size of a family of buttons.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ButtonStyle {
+    pub font_size: u32,
+    pub border_thickness: f64,
+    pub corner_radius: f64,
+}
+
+impl Default for ButtonStyle {
+    fn default() -> Self {
+        Self { font_size: 14, border_thickness: 2.0, corner_radius: 8.0 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    /// Name of the font asset used for every label.
+    pub font: String,
+    /// Name of the palette currently in use.
+    pub palette: String,
+    pub palettes: HashMap<String, Palette>,
+    pub start_button: ButtonStyle,
+    pub level_button: ButtonStyle,
+    /// Font size of the battle's heads-up text, such as the turn counter.
+    pub hud_font_size: u32,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            font: "FiraSans-Medium.ttf".to_string(),
+            palette: DEFAULT_PALETTE.to_string(),
+            palettes: Self::builtin_palettes(),
+            start_button: ButtonStyle::default(),
+            level_button: ButtonStyle { font_size: 10, ..ButtonStyle::default() },
+            hud_font_size: 16,
+        }
+    }
+}
+
+impl Theme {
+    fn builtin_palettes() -> HashMap<String, Palette> {
+        [
+            (DEFAULT_PALETTE.to_string(), Palette::default()),
+            (COLORBLIND_PALETTE.to_string(), Palette::colorblind()),
+        ].into_iter().collect()
+    }
+
+    ///
+    /// Reads a theme from a TOML asset. Palettes defined in the file are added to
+    /// (or replace) the built-in ones, so a file only needs to list what it changes.
+    ///
+    /// # Returns
+    /// * The loaded theme, or why it couldn't be read.
+    ///
+    pub fn load(asset_name: &str) -> Result<Theme, ThemeError> {
+        let contents = assets::load_string(asset_name).map_err(ThemeError::Asset)?;
+        Self::parse(&contents)
+    }
+
+    ///
+    /// Parses a theme from the contents of a theme file.
+    ///
+    pub fn parse(contents: &str) -> Result<Theme, ThemeError> {
+        let mut theme: Theme = toml::from_str(contents).map_err(ThemeError::Parse)?;
+        for (name, palette) in Self::builtin_palettes() {
+            theme.palettes.entry(name).or_insert(palette);
+        }
+        Ok(theme)
+    }
+
+    ///
+    /// # Returns
+    /// * The active palette, or the default one if the active name is unknown.
+    ///
+    pub fn palette(&self) -> &Palette {
+        self.palettes.get(&self.palette)
+            .or_else(|| self.palettes.get(DEFAULT_PALETTE))
+            .expect("default palette is always present")
+    }
+
+    ///
+    /// Switches to another named palette.
+    ///
+    /// # Returns
+    /// * Whether a palette with that name exists.
+    ///
+    pub fn set_palette(&mut self, name: &str) -> bool {
+        if !self.palettes.contains_key(name) {
+            return false;
+        }
+        self.palette = name.to_string();
+        true
+    }
+
+    ///
+    /// # Returns
+    /// * The color of the given team, wrapping around if there are more teams than colors.
+    ///
+    pub fn team_color(&self, team: usize) -> Color {
+        let colors = &self.palette().team_colors;
+        if colors.is_empty() {
+            return self.palette().text;
+        }
+        colors[team % colors.len()]
+    }
+}
+
+static THEME: OnceLock<RwLock<Theme>> = OnceLock::new();
+
+fn theme_lock() -> &'static RwLock<Theme> {
+    THEME.get_or_init(|| RwLock::new(Theme::default()))
+}
+
+///
+/// # Returns
+/// * The theme the view layer is currently drawing with.
+///
+pub fn current() -> RwLockReadGuard<'static, Theme> {
+    theme_lock().read().unwrap()
+}
+
+///
+/// Replaces the theme the view layer draws with. Takes effect on the next frame.
+///
+pub fn set_current(theme: Theme) {
+    *theme_lock().write().unwrap() = theme;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::THEME_ASSET;
+
+    #[test]
+    fn loads_the_bundled_theme() {
+        let theme = Theme::load(THEME_ASSET).unwrap();
+        assert_eq!(theme.palette, DEFAULT_PALETTE);
+        assert!(theme.palettes.contains_key(COLORBLIND_PALETTE));
+    }
+
+    #[test]
+    fn reports_a_missing_theme_file() {
+        assert!(matches!(Theme::load("no-such-theme.toml"), Err(ThemeError::Asset(AssetError::NotFound(_)))));
+    }
+
+    #[test]
+    fn reports_a_malformed_theme_file() {
+        assert!(matches!(Theme::parse("hud_font_size = \"big\""), Err(ThemeError::Parse(_))));
+    }
+
+    #[test]
+    fn fills_in_what_the_file_leaves_out() {
+        let theme = Theme::parse("[palettes.default]\ntext = [1.0, 1.0, 1.0, 1.0]").unwrap();
+        assert_eq!(theme.palette().text, [1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(theme.palette().background, Palette::default().background);
+        assert_eq!(theme.palettes[COLORBLIND_PALETTE], Palette::colorblind());
+        assert_eq!(theme.start_button, ButtonStyle::default());
+    }
+
+    #[test]
+    fn falls_back_to_the_default_palette_for_an_unknown_name() {
+        let theme = Theme::parse("palette = \"sepia\"").unwrap();
+        assert_eq!(theme.palette(), &Palette::default());
+    }
+
+    #[test]
+    fn switches_to_the_colorblind_palette_and_back() {
+        let mut theme = Theme::default();
+        assert!(theme.set_palette(COLORBLIND_PALETTE));
+        assert_eq!(theme.team_color(1), Palette::colorblind().team_colors[1]);
+        assert!(theme.set_palette(DEFAULT_PALETTE));
+        assert_eq!(theme.team_color(1), Palette::default().team_colors[1]);
+    }
+
+    #[test]
+    fn keeps_its_palette_when_asked_for_one_it_lacks() {
+        let mut theme = Theme::default();
+        assert!(!theme.set_palette("sepia"));
+        assert_eq!(theme.palette, DEFAULT_PALETTE);
+    }
+
+    #[test]
+    fn wraps_team_colors_around() {
+        let theme = Theme::default();
+        let count = theme.palette().team_colors.len();
+        assert_eq!(theme.team_color(count + 1), theme.team_color(1));
+    }
+}
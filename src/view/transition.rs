@@ -0,0 +1,64 @@
+//! TRANSITION.RS:
+//! Draws a scene transition in progress. Both the scene being left and the one
+//! being entered are rendered, combined according to the transition's kind.
+
+use piston_window::{rectangle, Context, DrawState, G2d, Glyphs};
+use piston_window::types::Color;
+use crate::model::game::Scene;
+use crate::model::transition::{Transition, TransitionKind};
+use crate::view::scenedrawer::draw_scene;
+use crate::view::theme;
+use crate::view::util::{to_coord, to_scissor, with_opacity};
+use crate::{HEIGHT, WIDTH};
+
+const BLACK: Color = [0.0, 0.0, 0.0, 1.0];
+
+/// Covers the whole window in `color`.
+fn fill_screen(color: Color, con: &Context, g: &mut G2d) {
+    rectangle(color, [0.0, 0.0, to_coord(WIDTH), to_coord(HEIGHT)], con.transform, g);
+}
+
+/// Draws `to` blended over `from`, `opacity` of the way in.
+fn draw_blended(from: &dyn Scene, to: &dyn Scene, opacity: f32,
+                con: &Context, g: &mut G2d, glyphs: &mut Glyphs) {
+    draw_scene(from, con, g, glyphs);
+    let background = theme::current().palette().background;
+    with_opacity(opacity, || {
+        fill_screen([background[0], background[1], background[2], background[3] * opacity], con, g);
+        draw_scene(to, con, g, glyphs);
+    });
+}
+
+///
+/// Draws one frame of a transition between two scenes.
+///
+/// # Arguments
+///
+/// * `transition` - The transition in progress.
+/// * `from`       - The scene being left.
+/// * `to`         - The scene being entered.
+/// * `con`, `g`, `glyphs` - Piston drawing context, graphics backend and font glyphs.
+pub fn draw_transition(transition: &Transition, from: &dyn Scene, to: &dyn Scene,
+                       con: &Context, g: &mut G2d, glyphs: &mut Glyphs) {
+    let t = transition.progress();
+    match transition.get_kind() {
+        TransitionKind::Cut => draw_scene(to, con, g, glyphs),
+        TransitionKind::FadeToBlack => {
+            // Darken the old scene for the first half, then lighten the new one.
+            let (scene, darkness) = if t < 0.5 { (from, t * 2.0) } else { (to, (1.0 - t) * 2.0) };
+            draw_scene(scene, con, g, glyphs);
+            fill_screen([BLACK[0], BLACK[1], BLACK[2], darkness as f32], con, g);
+        }
+        TransitionKind::Crossfade => draw_blended(from, to, t as f32, con, g, glyphs),
+        TransitionKind::Wipe => {
+            draw_scene(from, con, g, glyphs);
+            let revealed = to_scissor([0.0, 0.0, to_coord(WIDTH) * t, to_coord(HEIGHT)]);
+            let clipped = Context {
+                draw_state: DrawState { scissor: Some(revealed), ..con.draw_state },
+                ..*con
+            };
+            fill_screen(theme::current().palette().background, &clipped, g);
+            draw_scene(to, &clipped, g, glyphs);
+        }
+    }
+}
@@ -1,42 +1,110 @@
-/// UTIL.RS:
-/// Contains useful drawing utility functions for rendering grid-based games
-/// using the Piston game engine. Provides coordinate conversion and drawing
-/// primitives to work with a grid of fixed-size cells.
+//! UTIL.RS:
+//! Contains useful drawing utility functions for rendering grid-based games
+//! using the Piston game engine. Provides coordinate conversion and drawing
+//! primitives to work with a grid of fixed-size cells.
 
-use piston_window::{Context, G2d, rectangle, ellipse, Text, Transformed, CharacterCache};
+use piston_window::{Context, G2d, rectangle, ellipse};
 use piston_window::types::Color;
 use piston_window::Glyphs;
+use std::cell::Cell;
+use crate::view::text::{draw_fitted_label, Bounds};
+use crate::view::theme::ButtonStyle;
+use crate::{HEIGHT, WIDTH};
+
+pub use crate::layout::{to_coord, CELL_SIZE};
+
+/// How the game's layout, `WIDTH` by `HEIGHT` cells of `CELL_SIZE` pixels, is fitted into
+/// the window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    /// Window coordinates per layout pixel.
+    pub scale: f64,
+    /// Where the layout's top left corner is, in window coordinates.
+    pub offset: (f64, f64),
+    /// Framebuffer pixels per window coordinate, above 1 on high-DPI screens.
+    pub pixel_ratio: f64,
+}
 
-/// The size of one grid cell in pixels.
-///
-/// Used to convert between game logic coordinates (grid-based) and
-/// screen coordinates (pixel-based).
-pub const CELL_SIZE: f64 = 75.0;
+impl Viewport {
+    pub const IDENTITY: Viewport = Viewport { scale: 1.0, offset: (0.0, 0.0), pixel_ratio: 1.0 };
+}
+
+thread_local! {
+    /// Opacity every drawing helper multiplies its colors by. See `with_opacity`.
+    static OPACITY: Cell<f32> = const { Cell::new(1.0) };
 
-/// Converts a grid-based coordinate to a screen (pixel-based) coordinate.
+    /// The viewport of the last frame drawn. See `fit_viewport`.
+    static VIEWPORT: Cell<Viewport> = const { Cell::new(Viewport::IDENTITY) };
+}
+
+/// Scales the layout as large as it fits in the window, keeping its proportions, and centers it.
 ///
 /// # Arguments
 ///
-/// * `game_coord` - The coordinate on the logical grid.
-///
+/// * `window_size` - Size of the window, in window coordinates.
+/// * `draw_size`   - Size of the window's framebuffer, in pixels.
+pub fn fit_viewport(window_size: [f64; 2], draw_size: [u32; 2]) -> Viewport {
+    let layout = (to_coord(WIDTH), to_coord(HEIGHT));
+    let scale = (window_size[0] / layout.0).min(window_size[1] / layout.1);
+    let offset = ((window_size[0] - layout.0 * scale) / 2.0, (window_size[1] - layout.1 * scale) / 2.0);
+    let pixel_ratio = if window_size[0] > 0.0 { draw_size[0] as f64 / window_size[0] } else { 1.0 };
+    Viewport { scale, offset, pixel_ratio }
+}
+
+/// Makes `viewport` the one drawing and cursor conversion use, until the next frame.
+pub fn set_viewport(viewport: Viewport) {
+    VIEWPORT.with(|v| v.set(viewport));
+}
+
 /// # Returns
 ///
-/// * `f64` - The corresponding pixel-based coordinate.
-pub fn to_coord(game_coord: i32) -> f64 {
-    (game_coord as f64) * CELL_SIZE
+/// * The viewport of the frame being, or last, drawn.
+pub fn viewport() -> Viewport {
+    VIEWPORT.with(|v| v.get())
+}
+
+/// Converts a position in the window, such as the mouse cursor, to layout pixels.
+pub fn to_layout(pos: [f64; 2]) -> [f64; 2] {
+    let v = viewport();
+    [(pos[0] - v.offset.0) / v.scale, (pos[1] - v.offset.1) / v.scale]
+}
+
+/// Converts a rectangle in layout pixels to framebuffer pixels, as clipping rectangles need.
+pub fn to_scissor(rect: [f64; 4]) -> [u32; 4] {
+    let v = viewport();
+    let ratio = v.scale * v.pixel_ratio;
+    [((v.offset.0 * v.pixel_ratio) + rect[0] * ratio) as u32,
+     ((v.offset.1 * v.pixel_ratio) + rect[1] * ratio) as u32,
+     (rect[2] * ratio) as u32,
+     (rect[3] * ratio) as u32]
 }
 
-/// Converts a grid-based coordinate to a screen coordinate as a `u32`.
+/// Runs `draw` with every color drawn through the view helpers made `opacity` times as opaque.
+/// Used to blend whole scenes, e.g. during a crossfade.
 ///
 /// # Arguments
 ///
-/// * `game_coord` - The coordinate on the logical grid.
+/// * `opacity` - From 0.0 (invisible) to 1.0 (unchanged).
+/// * `draw`    - The drawing to do at that opacity.
+pub fn with_opacity<R>(opacity: f32, draw: impl FnOnce() -> R) -> R {
+    let previous = OPACITY.with(|o| o.replace(opacity.clamp(0.0, 1.0)));
+    let result = draw();
+    OPACITY.with(|o| o.set(previous));
+    result
+}
+
+/// Applies the current opacity (see `with_opacity`) to a color.
+///
+/// # Arguments
+///
+/// * `color` - The color as it would be drawn at full opacity.
 ///
 /// # Returns
 ///
-/// * `u32` - The corresponding pixel-based coordinate rounded down to an integer.
-pub fn to_coord_u32(game_coord: i32) -> u32 {
-    to_coord(game_coord) as u32
+/// * `Color` - The color to actually draw with.
+pub fn faded(color: Color) -> Color {
+    let opacity = OPACITY.with(|o| o.get());
+    [color[0], color[1], color[2], color[3] * opacity]
 }
 
 /// Draws a square block on the screen at the given grid position.
@@ -54,89 +122,20 @@ pub fn draw_block(color: Color, x: i32, y: i32, con: &Context, g: &mut G2d) {
     let gui_x: f64 = to_coord(x);
     let gui_y: f64 = to_coord(y);
     rectangle(
-        color,
+        faded(color),
         [gui_x, gui_y, CELL_SIZE, CELL_SIZE],
         con.transform,
         g
     );
 }
 
-/// Draws a square block with a border on the screen at the given grid position.
-///
-/// The block is filled with `fill_color` and surrounded by a border of a constant thickness
-/// in `border_color`. The size of the block is determined by `CELL_SIZE`.
-///
-/// # Arguments
-///
-/// * `fill_color` - The color of the inner block (RGBA).
-/// * `border_color` - The color of the border (RGBA).
-/// * `x` - The x-coordinate on the game grid.
-/// * `y` - The y-coordinate on the game grid.
-/// * `con` - The drawing context from Piston.
-/// * `g` - The graphics buffer used to render the shapes.
-pub fn draw_block_with_border(
-    fill_color: Color,
-    border_color: Color,
-    x: i32,
-    y: i32,
-    con: &Context,
-    g: &mut G2d
-) {
-    const BORDER_THICKNESS: f64 = 5.0;
-
-    let gui_x = to_coord(x);
-    let gui_y = to_coord(y);
-
-    // Draw border (slightly larger rectangle underneath)
-    rectangle(
-        border_color,
-        [
-            gui_x - BORDER_THICKNESS,
-            gui_y - BORDER_THICKNESS,
-            CELL_SIZE + 2.0 * BORDER_THICKNESS,
-            CELL_SIZE + 2.0 * BORDER_THICKNESS,
-        ],
-        con.transform,
-        g,
-    );
-
-    // Draw block (on top of the border)
-    rectangle(
-        fill_color,
-        [gui_x, gui_y, CELL_SIZE, CELL_SIZE],
-        con.transform,
-        g,
-    );
-}
-
-/// Draws a rectangle on the screen with dimensions specified in grid units.
-///
-/// Converts the grid-based dimensions into pixel coordinates using `CELL_SIZE`.
-///
-/// # Arguments
-///
-/// * `color` - The color of the rectangle (RGBA array).
-/// * `x` - The x-coordinate of the top-left corner on the game grid.
-/// * `y` - The y-coordinate of the top-left corner on the game grid.
-/// * `width` - The width of the rectangle in grid cells.
-/// * `height` - The height of the rectangle in grid cells.
-/// * `con` - The drawing context from Piston.
-/// * `g` - The graphics buffer used to render the rectangle.
-pub fn draw_rectangle(color: Color, x: i32, y: i32, width: i32, height: i32,
-                      con: &Context, g: &mut G2d) {
-    let gui_x: f64 = to_coord(x);
-    let gui_y: f64 = to_coord(y);
-    let gui_width: f64 = to_coord(width);
-    let gui_height: f64 = to_coord(height);
-    rectangle(color, [gui_x, gui_y, gui_width, gui_height], con.transform, g);
-}
-
 /// Helper: draw a rounded‐corner rect by combining rectangles + corner ellipses.
 ///
 /// * `col` – fill color RGBA
 /// * `(x,y)` – top‐left in pixels
 /// * `(w,h)` – size in pixels
 /// * `r` – corner radius in pixels
+#[allow(clippy::too_many_arguments)]
 fn draw_rounded_rect(
     color: Color,
     x: f64, y: f64,
@@ -145,6 +144,8 @@ fn draw_rounded_rect(
     con: &Context,
     g: &mut G2d,
 ) {
+    let color = faded(color);
+
     // center cross
     rectangle(color, [x + r, y,       w - 2.0*r, h      ], con.transform, g);
     rectangle(color, [x,       y + r,   w,       h - 2.0*r], con.transform, g);
@@ -157,22 +158,26 @@ fn draw_rounded_rect(
     ellipse(color, [x + w - d, y + h - d, d, d], con.transform, g);         // bot‐right
 }
 
-/// Draws a rectangular button with rounded corners, a black border, and centered text.
+/// Draws a rectangular button with rounded corners, a border, and centered text.
+/// Labels too long for the button are shrunk (and wrapped if needed) to fit.
 ///
 /// # Arguments
 ///
 /// * `label`      – The text to draw inside the button.
-/// * `font_size`  – Font size in pixels for the label.
+/// * `style`      – Font size, border thickness and corner radius of the button.
 /// * `fill_color` – RGB color array for the button’s fill.
+/// * `border_color` – Color of the border and the label.
 /// * `x`, `y`     – Top-left corner of the button, in grid coords.
 /// * `width`, `height` – Size of the button, in grid cells.
 /// * `con`        – Piston drawing context.
 /// * `g`          – Graphics backend.
 /// * `glyphs`     – Loaded font glyphs for text rendering.
+#[allow(clippy::too_many_arguments)]
 pub fn draw_button(
     label: &str,
-    font_size: u32,
+    style: &ButtonStyle,
     fill_color: Color,
+    border_color: Color,
     x: i32,
     y: i32,
     width: i32,
@@ -188,9 +193,10 @@ pub fn draw_button(
     let ph = to_coord(height);
 
     // Style
-    let border_thick = 2.0;
-    let corner_r = 8.0;
-    let border_col: Color = [0.0, 0.0, 0.0, 1.0];
+    let font_size = style.font_size;
+    let border_thick = style.border_thickness;
+    let corner_r = style.corner_radius;
+    let border_col: Color = border_color;
 
     // 1) Border (draw slightly larger rounded rect)
     draw_rounded_rect(border_col, px, py, pw, ph, corner_r, con, g);
@@ -208,22 +214,7 @@ pub fn draw_button(
         g,
     );
 
-    // 3) Centered label
-    // measure text width
-    let text_w = glyphs
-        .width(font_size, label)
-        .unwrap_or(label.len() as f64 * (font_size as f64 * 0.5));
-    let text_x = px + (pw - text_w) / 2.0;
-    let text_y = py + (ph + font_size as f64) / 2.0 - 2.0;
-
-    let text = Text::new_color(border_col, font_size);
-    text.draw(
-        label,
-        glyphs,
-        &con.draw_state,
-        con.transform.trans(text_x, text_y),
-        g,
-    ).ok();
+    // 3) Centered label, kept clear of the border
+    let label_bounds = Bounds::new(px, py, pw, ph).inset(border_thick + corner_r / 2.0);
+    draw_fitted_label(label, font_size, border_col, &label_bounds, con, g, glyphs);
 }
- 
-
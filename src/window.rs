@@ -0,0 +1,131 @@
+//! WINDOW.RS:
+//! Opens the game window and runs the event loop: input goes to the controller,
+//! settings changes are applied to the window, and each frame the view draws
+//! the game. Only built with the `graphics` feature.
+
+use piston_window::*;
+
+use winit::window::Fullscreen;
+use crate::assets::{self, AssetError};
+use crate::controller::controller::Controller;
+use crate::model::game::{Game, GameEvent};
+use crate::settings::{self, Settings};
+use crate::view::scenedrawer::draw_game;
+use crate::view::theme::{self, Theme, COLORBLIND_PALETTE};
+use crate::view::util::{self as view_util, to_coord};
+use crate::{HEIGHT, THEME_ASSET, WIDTH};
+
+///
+/// Opens the game window and runs it until it is closed.
+///
+/// # Arguments
+/// * `startup` - An event the game receives before the first frame, e.g. to host a network battle.
+///
+pub fn run(startup: Option<GameEvent>) {
+    match Theme::load(THEME_ASSET) {
+        Ok(loaded) => theme::set_current(loaded),
+        Err(e) => println!("Using the default theme, {}", e),
+    }
+
+    // The palette the theme picked, to go back to when the colorblind one is turned off.
+    let theme_palette = theme::current().palette.clone();
+    let mut applied = Settings::load_or_default();
+    settings::set_current(applied.clone());
+    apply_palette(&applied, &theme_palette);
+
+    let mut window: PistonWindow = WindowSettings::new(
+        "Ranger Royale",
+        window_size(&applied)
+    ).exit_on_esc(applied.exit_on_esc).fullscreen(applied.fullscreen).build().unwrap();
+
+    let font = theme::current().font.clone();
+    let mut glyphs = match load_font(&mut window, &font) {
+        Ok(glyphs) => glyphs,
+        Err(e) => {
+            eprintln!("Couldn’t load font: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut game = Game::new();
+    if let Some(event) = startup {
+        game.receive_event(&event);
+    }
+    let mut controller: Controller = Controller::new(game);
+
+    while let Some(event) = window.next() {
+        apply_settings(&mut window, &mut applied, &theme_palette);
+        controller.handle_event(&event);
+
+        if let Some(pos) = event.mouse_cursor_args() {
+            controller.update_cursor(view_util::to_layout(pos))
+        }
+
+        window.draw_2d(&event, |context, graphics, device| {
+            clear(theme::current().palette().background, graphics);
+            if let Some(viewport) = context.viewport {
+                view_util::set_viewport(view_util::fit_viewport(viewport.window_size, viewport.draw_size));
+            }
+            let fitted = view_util::viewport();
+            let context = context.trans(fitted.offset.0, fitted.offset.1).scale(fitted.scale, fitted.scale);
+            let model: &Game = controller.get_model();
+            draw_game(model, &context, graphics, &mut glyphs);
+
+            glyphs.factory.encoder.flush(device);
+        });
+
+    }
+}
+
+///
+/// # Returns
+/// * The size of the window, in pixels, when it isn't fullscreen.
+///
+fn window_size(settings: &Settings) -> [u32; 2] {
+    let scale = settings.window_size.scale();
+    [(to_coord(WIDTH) * scale) as u32, (to_coord(HEIGHT) * scale) as u32]
+}
+
+///
+/// Brings the window and the theme in line with the settings, if they changed since they were
+/// last applied.
+///
+fn apply_settings(window: &mut PistonWindow, applied: &mut Settings, theme_palette: &str) {
+    let wanted = settings::current().clone();
+    if wanted == *applied {
+        return;
+    }
+    if wanted.fullscreen != applied.fullscreen {
+        window.window.window.set_fullscreen(wanted.fullscreen.then_some(Fullscreen::Borderless(None)));
+    }
+    let resized = wanted.window_size != applied.window_size || wanted.fullscreen != applied.fullscreen;
+    if resized && !wanted.fullscreen {
+        window.set_size(window_size(&wanted));
+    }
+    window.set_exit_on_esc(wanted.exit_on_esc);
+    if wanted.colorblind_palette != applied.colorblind_palette {
+        apply_palette(&wanted, theme_palette);
+    }
+    *applied = wanted;
+}
+
+///
+/// Draws with the colorblind palette if the settings ask for it, or else with `theme_palette`.
+///
+fn apply_palette(settings: &Settings, theme_palette: &str) {
+    let name = if settings.colorblind_palette { COLORBLIND_PALETTE } else { theme_palette };
+    let mut theme = theme::current().clone();
+    if !theme.set_palette(name) {
+        println!("Warning: the theme has no palette called {}", name);
+    }
+    theme::set_current(theme);
+}
+
+///
+/// Loads a font asset into a glyph cache for the window.
+///
+fn load_font(window: &mut PistonWindow, name: &str) -> Result<Glyphs, AssetError> {
+    let bytes = assets::load(name)?.into_static_bytes();
+    Glyphs::from_bytes(bytes, window.create_texture_context(), TextureSettings::new())
+        .map_err(|_| AssetError::Invalid(name.to_string(), "not a readable font".to_string()))
+}